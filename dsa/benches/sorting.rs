@@ -0,0 +1,65 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use dsa::sorting::*;
+
+type Sort = fn(&mut [u32]);
+
+const SORTS: [(&str, Sort); 7] = [
+    ("slice::sort", |arr| arr.sort()),
+    ("insertion", insertion_sort),
+    ("merge", merge_sort),
+    ("quick", quick_sort),
+    ("heap", heap_sort),
+    ("radix", radix_sort),
+    ("tim", tim_sort),
+];
+
+const SIZES: [usize; 2] = [1_000, 100_000];
+
+/// Insertion sort is O(n^2), and would take too long on the larger sizes.
+const INSERTION_LIMIT: usize = 10_000;
+
+/// Deterministic pseudo-random numbers (xorshift32), so every run sorts the
+/// same input.
+fn random(n: usize) -> Vec<u32> {
+    let mut state = 0x9E37_79B9u32;
+    (0..n)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        })
+        .collect()
+}
+
+fn distributions(n: usize) -> Vec<(&'static str, Vec<u32>)> {
+    let sorted: Vec<u32> = (0..n as u32).collect();
+    let reversed = sorted.iter().rev().copied().collect();
+    let few_unique = random(n).into_iter().map(|x| x % 16).collect();
+    vec![
+        ("sorted", sorted),
+        ("reversed", reversed),
+        ("random", random(n)),
+        ("few unique", few_unique),
+    ]
+}
+
+fn compare_sorts(c: &mut Criterion) {
+    for &n in SIZES.iter() {
+        for (distribution, input) in distributions(n) {
+            let mut group = c.benchmark_group(format!("{} {}", distribution, n));
+            for (name, sort) in SORTS.iter() {
+                if *name == "insertion" && n > INSERTION_LIMIT {
+                    continue;
+                }
+                group.bench_with_input(BenchmarkId::from_parameter(name), &input, |b, input| {
+                    b.iter_batched_ref(|| input.clone(), |arr| sort(arr), BatchSize::LargeInput)
+                });
+            }
+            group.finish();
+        }
+    }
+}
+
+criterion_group!(benches, compare_sorts);
+criterion_main!(benches);
@@ -0,0 +1,218 @@
+/// Names an element pushed onto a `DaryHeap`, to change its priority later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handle(usize);
+
+/**
+ * A min-heap where every node has `d` children rather than two: a shallower
+ * tree, so `push` and `decrease_key` (which sift up) get cheaper, for a
+ * `pop` (which sifts down past `d` children a level) that costs more. That's
+ * the trade Dijkstra and Prim want, with more decreases than pops.
+ *
+ * Every pushed element gets a `Handle`, which `decrease_key` takes to find
+ * the element in O(1).
+ */
+#[derive(Debug, Clone)]
+pub struct DaryHeap<T> {
+    arity: usize,
+    /// The elements, each with the handle it was pushed with.
+    heap: Vec<(T, Handle)>,
+    /// Where in `heap` every handle's element is, until it's popped.
+    positions: Vec<Option<usize>>,
+}
+
+impl<T: Ord> DaryHeap<T> {
+    /// A 4-ary heap, which is about the quickest in practice.
+    pub fn new() -> Self {
+        DaryHeap::with_arity(4)
+    }
+
+    pub fn with_arity(arity: usize) -> Self {
+        assert!(arity >= 2, "a heap needs at least two children a node");
+        DaryHeap {
+            arity,
+            heap: Vec::new(),
+            positions: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// The smallest element.
+    pub fn peek(&self) -> Option<&T> {
+        self.heap.first().map(|(value, _)| value)
+    }
+
+    /// The element pushed with `handle`, if it's not been popped yet.
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        let position = self.positions[handle.0]?;
+        Some(&self.heap[position].0)
+    }
+
+    pub fn push(&mut self, value: T) -> Handle {
+        let handle = Handle(self.positions.len());
+        self.positions.push(Some(self.heap.len()));
+        self.heap.push((value, handle));
+        self.sift_up(self.heap.len() - 1);
+        handle
+    }
+
+    /// Removes the smallest element, with the handle it was pushed with.
+    pub fn pop(&mut self) -> Option<(T, Handle)> {
+        if self.heap.is_empty() {
+            return None;
+        }
+        let last = self.heap.len() - 1;
+        self.swap(0, last);
+        let (value, handle) = self.heap.pop().unwrap();
+        self.positions[handle.0] = None;
+        if !self.heap.is_empty() {
+            self.sift_down(0);
+        }
+        Some((value, handle))
+    }
+
+    /**
+     * Lowers the element pushed with `handle` to `value`. Panics if it's
+     * been popped already, or if `value` is greater than it was.
+     */
+    pub fn decrease_key(&mut self, handle: Handle, value: T) {
+        let position = self.positions[handle.0].expect("the element has been popped");
+        assert!(
+            value <= self.heap[position].0,
+            "decrease_key can't increase a key"
+        );
+        self.heap[position].0 = value;
+        self.sift_up(position);
+    }
+
+    fn sift_up(&mut self, mut child: usize) {
+        while child > 0 {
+            let parent = (child - 1) / self.arity;
+            if self.heap[parent].0 <= self.heap[child].0 {
+                break;
+            }
+            self.swap(parent, child);
+            child = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut parent: usize) {
+        loop {
+            let first = parent * self.arity + 1;
+            let last = (first + self.arity).min(self.heap.len());
+            let smallest = match (first..last).min_by(|&a, &b| self.heap[a].0.cmp(&self.heap[b].0))
+            {
+                Some(child) if self.heap[child].0 < self.heap[parent].0 => child,
+                _ => break,
+            };
+            self.swap(parent, smallest);
+            parent = smallest;
+        }
+    }
+
+    /// Swaps two elements, keeping their positions up to date.
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.positions[self.heap[a].1 .0] = Some(a);
+        self.positions[self.heap[b].1 .0] = Some(b);
+    }
+}
+
+impl<T: Ord> Default for DaryHeap<T> {
+    fn default() -> Self {
+        DaryHeap::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn pops_smallest_first() {
+        let mut heap = DaryHeap::with_arity(3);
+        for value in &[5, 1, 8, 3, 9, 2, 7] {
+            heap.push(*value);
+        }
+        assert_eq!(heap.len(), 7);
+        assert_eq!(heap.peek(), Some(&1));
+        let popped: Vec<_> = std::iter::from_fn(|| heap.pop().map(|(value, _)| value)).collect();
+        assert_eq!(popped, vec![1, 2, 3, 5, 7, 8, 9]);
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn decreases_keys() {
+        let mut heap = DaryHeap::new();
+        let a = heap.push(10);
+        let b = heap.push(20);
+        heap.decrease_key(b, 5);
+        assert_eq!(heap.get(b), Some(&5));
+        assert_eq!(heap.pop(), Some((5, b)));
+        assert_eq!(heap.get(b), None);
+        assert_eq!(heap.pop(), Some((10, a)));
+    }
+
+    #[test]
+    #[should_panic(expected = "can't increase")]
+    fn rejects_increased_keys() {
+        let mut heap = DaryHeap::new();
+        let handle = heap.push(1);
+        heap.decrease_key(handle, 2);
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Push(i32),
+        Pop,
+        /// Lowers the `n`th live element (modulo how many there are) by some.
+        Decrease(usize, i32),
+    }
+
+    fn ops() -> impl Strategy<Value = Vec<Op>> {
+        let op = prop_oneof![
+            (-50..50).prop_map(Op::Push),
+            Just(Op::Pop),
+            (any::<usize>(), 0..20).prop_map(|(n, by)| Op::Decrease(n, by)),
+        ];
+        prop::collection::vec(op, 0..100)
+    }
+
+    proptest! {
+        #[test]
+        fn matches_brute_force(arity in 2..6usize, ops in ops()) {
+            let mut heap = DaryHeap::with_arity(arity);
+            // The live elements, by handle, in a plain list
+            let mut brute: Vec<(Handle, i32)> = Vec::new();
+            for op in ops {
+                match op {
+                    Op::Push(value) => brute.push((heap.push(value), value)),
+                    Op::Pop => {
+                        let smallest = brute.iter().map(|&(_, value)| value).min();
+                        let popped = heap.pop();
+                        prop_assert_eq!(popped.as_ref().map(|(value, _)| *value), smallest);
+                        if let Some((_, handle)) = popped {
+                            brute.retain(|&(h, _)| h != handle);
+                        }
+                    }
+                    Op::Decrease(n, by) if !brute.is_empty() => {
+                        let len = brute.len();
+                        let (handle, value) = &mut brute[n % len];
+                        *value -= by;
+                        heap.decrease_key(*handle, *value);
+                    }
+                    Op::Decrease(..) => {}
+                }
+                prop_assert_eq!(heap.len(), brute.len());
+                prop_assert_eq!(heap.peek().copied(), brute.iter().map(|&(_, value)| value).min());
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+/**
+ * Union-find over the elements `0..len()`: which set each one is in, and
+ * joining two sets. Path compression (pointing every element looked up
+ * straight at its root) and union by rank (hanging the shallower tree under
+ * the deeper) make both about O(1), amortised: O(α(n)), the inverse
+ * Ackermann function.
+ */
+#[derive(Debug, Clone)]
+pub struct DisjointSet {
+    parent: Vec<usize>,
+    /// An upper bound on the height of every root's tree.
+    rank: Vec<u8>,
+    /// How many elements are in every root's set.
+    size: Vec<usize>,
+    sets: usize,
+}
+
+impl DisjointSet {
+    /// `len` elements, each in a set of its own.
+    pub fn new(len: usize) -> Self {
+        DisjointSet {
+            parent: (0..len).collect(),
+            rank: vec![0; len],
+            size: vec![1; len],
+            sets: len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// How many sets there are.
+    pub fn set_count(&self) -> usize {
+        self.sets
+    }
+
+    /// The root of `x`'s set, the same for every element in it.
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        // Point the whole path at the root
+        let mut x = x;
+        while self.parent[x] != root {
+            let next = self.parent[x];
+            self.parent[x] = root;
+            x = next;
+        }
+        root
+    }
+
+    /// Joins the sets of `a` and `b`, false if they were the same already.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        if self.rank[a] < self.rank[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+        if self.rank[a] == self.rank[b] {
+            self.rank[a] += 1;
+        }
+        self.sets -= 1;
+        true
+    }
+
+    pub fn same_set(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// How many elements are in `x`'s set, `x` included.
+    pub fn set_size(&mut self, x: usize) -> usize {
+        let root = self.find(x);
+        self.size[root]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn joins_sets() {
+        let mut sets = DisjointSet::new(5);
+        assert!(sets.union(0, 1));
+        assert!(sets.union(3, 4));
+        assert!(!sets.union(1, 0));
+        assert!(sets.same_set(0, 1));
+        assert!(!sets.same_set(1, 3));
+        assert_eq!(sets.set_count(), 3);
+        assert!(sets.union(1, 4));
+        assert_eq!(sets.set_size(3), 4);
+        assert_eq!(sets.set_size(2), 1);
+        assert_eq!(sets.len(), 5);
+    }
+
+    proptest! {
+        #[test]
+        fn matches_brute_force(
+            (n, unions) in (1..30usize).prop_flat_map(|n| {
+                (Just(n), prop::collection::vec((0..n, 0..n), 0..40))
+            })
+        ) {
+            let mut sets = DisjointSet::new(n);
+            // The label of every element's set, relabelled on every union
+            let mut labels: Vec<usize> = (0..n).collect();
+            for (a, b) in unions {
+                let (from, to) = (labels[b], labels[a]);
+                prop_assert_eq!(sets.union(a, b), from != to);
+                for label in &mut labels {
+                    if *label == from {
+                        *label = to;
+                    }
+                }
+                for x in 0..n {
+                    for y in 0..n {
+                        prop_assert_eq!(sets.same_set(x, y), labels[x] == labels[y]);
+                    }
+                    let size = labels.iter().filter(|&&l| l == labels[x]).count();
+                    prop_assert_eq!(sets.set_size(x), size);
+                }
+                let mut distinct = labels.clone();
+                distinct.sort();
+                distinct.dedup();
+                prop_assert_eq!(sets.set_count(), distinct.len());
+            }
+        }
+    }
+}
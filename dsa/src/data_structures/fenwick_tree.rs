@@ -0,0 +1,129 @@
+use std::ops::{Add, Range, Sub};
+
+/**
+ * A Fenwick (binary indexed) tree: prefix sums of an array that keeps
+ * changing, both in O(log n). Every `tree[i - 1]` holds the sum of the
+ * `i & -i` elements ending at `i - 1`, so the lowest set bit of an index
+ * says how far back it reaches.
+ */
+#[derive(Debug, Clone)]
+pub struct FenwickTree<T> {
+    tree: Vec<T>,
+}
+
+impl<T> FenwickTree<T>
+where
+    T: Copy + Default + Add<Output = T> + Sub<Output = T>,
+{
+    /// `len` elements, all zero (`Default`).
+    pub fn new(len: usize) -> Self {
+        FenwickTree {
+            tree: vec![T::default(); len],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Adds `delta` to the element at `index`.
+    pub fn add(&mut self, index: usize, delta: T) {
+        assert!(index < self.len(), "index {} out of {}", index, self.len());
+        // 1-based from here on, so the lowest set bit works out
+        let mut i = index + 1;
+        while i <= self.len() {
+            self.tree[i - 1] = self.tree[i - 1] + delta;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// The sum of the first `len` elements.
+    pub fn prefix_sum(&self, len: usize) -> T {
+        assert!(len <= self.len(), "prefix {} out of {}", len, self.len());
+        let mut sum = T::default();
+        let mut i = len;
+        while i > 0 {
+            sum = sum + self.tree[i - 1];
+            i -= i & i.wrapping_neg();
+        }
+        sum
+    }
+
+    /// The sum of the elements in `range`.
+    pub fn range_sum(&self, range: Range<usize>) -> T {
+        assert!(range.start <= range.end, "range {:?} goes backwards", range);
+        self.prefix_sum(range.end) - self.prefix_sum(range.start)
+    }
+
+    /// The element at `index`.
+    pub fn get(&self, index: usize) -> T {
+        self.range_sum(index..index + 1)
+    }
+}
+
+impl<T> From<&[T]> for FenwickTree<T>
+where
+    T: Copy + Default + Add<Output = T> + Sub<Output = T>,
+{
+    /// Builds the tree in O(n), pushing every sum up to its parent once.
+    fn from(values: &[T]) -> Self {
+        let mut tree = values.to_vec();
+        for i in 1..=tree.len() {
+            let parent = i + (i & i.wrapping_neg());
+            if parent <= tree.len() {
+                tree[parent - 1] = tree[parent - 1] + tree[i - 1];
+            }
+        }
+        FenwickTree { tree }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn sums_ranges() {
+        let mut tree = FenwickTree::from(&[3, 1, 4, 1, 5, 9, 2, 6][..]);
+        assert_eq!(tree.prefix_sum(0), 0);
+        assert_eq!(tree.prefix_sum(8), 31);
+        assert_eq!(tree.range_sum(2..5), 10);
+        tree.add(3, -1);
+        assert_eq!(tree.get(3), 0);
+        assert_eq!(tree.range_sum(2..5), 9);
+        assert_eq!(FenwickTree::<f64>::new(3).prefix_sum(3), 0.0);
+    }
+
+    proptest! {
+        #[test]
+        fn matches_brute_force(
+            values in prop::collection::vec(-100..100i64, 0..40),
+            adds in prop::collection::vec((any::<usize>(), -100..100i64), 0..40),
+        ) {
+            let mut brute = values.clone();
+            let mut tree = FenwickTree::from(&values[..]);
+            for len in 0..=brute.len() {
+                prop_assert_eq!(tree.prefix_sum(len), brute[..len].iter().sum::<i64>());
+            }
+            for (index, delta) in adds {
+                if brute.is_empty() {
+                    break;
+                }
+                let index = index % brute.len();
+                brute[index] += delta;
+                tree.add(index, delta);
+                for start in 0..=brute.len() {
+                    for end in start..=brute.len() {
+                        prop_assert_eq!(tree.range_sum(start..end), brute[start..end].iter().sum::<i64>());
+                    }
+                }
+            }
+            prop_assert_eq!(tree.len(), brute.len());
+        }
+    }
+}
@@ -0,0 +1,110 @@
+mod d_ary_heap;
+mod disjoint_set;
+mod fenwick_tree;
+mod radix_tree;
+mod segment_tree;
+mod trie;
+
+pub use self::d_ary_heap::{DaryHeap, Handle};
+pub use self::disjoint_set::DisjointSet;
+pub use self::fenwick_tree::FenwickTree;
+pub use self::radix_tree::RadixTree;
+pub use self::segment_tree::{
+    LazySegmentTree, Max, Min, Monoid, RangeAdd, RangeAssign, Sum, Update,
+};
+pub use self::trie::Trie;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::cmp::Reverse;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Insert(Vec<u8>),
+        Remove(Vec<u8>),
+    }
+
+    fn key(max: usize) -> impl Strategy<Value = Vec<u8>> {
+        prop::collection::vec(prop::sample::select(b"abc".to_vec()), 0..max)
+    }
+
+    fn ops() -> impl Strategy<Value = Vec<Op>> {
+        let op = prop_oneof![
+            3 => key(5).prop_map(Op::Insert),
+            1 => key(5).prop_map(Op::Remove),
+        ];
+        prop::collection::vec(op, 0..40)
+    }
+
+    /// Runs `ops` on a prefix tree and a `BTreeMap`, then checks every query
+    /// for `prefix` against the map.
+    macro_rules! matches_brute_force {
+        ($tree:expr, $ops:expr, $prefix:expr) => {{
+            let (mut tree, prefix) = ($tree, $prefix);
+            let mut brute: BTreeMap<Vec<u8>, u64> = BTreeMap::new();
+            for op in $ops {
+                match op {
+                    Op::Insert(key) => {
+                        let frequency = brute.entry(key.clone()).or_insert(0);
+                        *frequency += 1;
+                        prop_assert_eq!(tree.insert(&key), *frequency);
+                    }
+                    Op::Remove(key) => prop_assert_eq!(tree.remove(&key), brute.remove(&key)),
+                }
+            }
+            prop_assert_eq!(tree.len(), brute.len());
+            let all: Vec<_> = brute.clone().into_iter().collect();
+            prop_assert_eq!(tree.iter().collect::<Vec<_>>(), all);
+            for key in brute.keys() {
+                prop_assert!(tree.contains(key));
+            }
+
+            let starting: Vec<_> = brute
+                .into_iter()
+                .filter(|(key, _)| key.starts_with(&prefix))
+                .collect();
+            prop_assert_eq!(
+                tree.with_prefix(&prefix).collect::<Vec<_>>(),
+                starting.clone()
+            );
+
+            let shared = starting.iter().map(|(key, _)| key.clone()).reduce(|a, b| {
+                a.iter()
+                    .zip(&b)
+                    .take_while(|(x, y)| x == y)
+                    .map(|(x, _)| *x)
+                    .collect()
+            });
+            prop_assert_eq!(tree.longest_common_prefix(&prefix), shared);
+
+            let longest = (0..=prefix.len())
+                .rev()
+                .find(|&len| tree.contains(&prefix[..len]));
+            prop_assert_eq!(
+                tree.longest_prefix_of(&prefix),
+                longest.map(|len| prefix[..len].to_vec())
+            );
+
+            // A stable sort keeps equals lexicographic
+            let mut ranked = starting;
+            ranked.sort_by_key(|(_, frequency)| Reverse(*frequency));
+            ranked.truncate(3);
+            prop_assert_eq!(tree.top_k(&prefix, 3), ranked);
+        }};
+    }
+
+    proptest! {
+        #[test]
+        fn trie_matches_brute_force(ops in ops(), prefix in key(4)) {
+            matches_brute_force!(Trie::new(), ops, prefix);
+        }
+
+        #[test]
+        fn radix_tree_matches_brute_force(ops in ops(), prefix in key(4)) {
+            matches_brute_force!(RadixTree::new(), ops, prefix);
+        }
+    }
+}
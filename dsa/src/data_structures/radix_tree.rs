@@ -0,0 +1,313 @@
+use std::collections::BTreeMap;
+
+use super::trie::top_k;
+
+/**
+ * A compressed trie: a chain of nodes with one child each and no key of
+ * their own is merged into a single edge, labelled with all of their
+ * elements. Every node then either ends a key or branches, so there are at
+ * most twice as many nodes as keys, however long the keys. The same
+ * operations as a `Trie`, in the same time.
+ */
+#[derive(Debug, Clone)]
+pub struct RadixTree<T> {
+    root: Node<T>,
+    len: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Node<T> {
+    /// The elements on the edge into this node: never empty but for the
+    /// root's.
+    label: Vec<T>,
+    frequency: Option<u64>,
+    /// By the first element of their labels.
+    children: BTreeMap<T, Node<T>>,
+}
+
+impl<T> Node<T> {
+    fn new(label: Vec<T>) -> Self {
+        Node {
+            label,
+            frequency: None,
+            children: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: Ord + Clone> RadixTree<T> {
+    pub fn new() -> Self {
+        RadixTree {
+            root: Node::new(Vec::new()),
+            len: 0,
+        }
+    }
+
+    /// How many keys there are.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts `key` once more, and returns how often it's been inserted.
+    pub fn insert(&mut self, key: &[T]) -> u64 {
+        let slot = insert(&mut self.root, key);
+        if slot.is_none() {
+            self.len += 1;
+        }
+        let frequency = slot.get_or_insert(0);
+        *frequency += 1;
+        *frequency
+    }
+
+    /// Removes `key`, however often it was inserted, and returns how often
+    /// that was. Edges are merged again where they no longer branch.
+    pub fn remove(&mut self, key: &[T]) -> Option<u64> {
+        let frequency = remove(&mut self.root, key);
+        if frequency.is_some() {
+            self.len -= 1;
+        }
+        frequency
+    }
+
+    pub fn contains(&self, key: &[T]) -> bool {
+        self.frequency(key).is_some()
+    }
+
+    /// How often `key` has been inserted, if it's in the tree.
+    pub fn frequency(&self, key: &[T]) -> Option<u64> {
+        let (found, node) = self.find(key)?;
+        if found.len() == key.len() {
+            node.frequency
+        } else {
+            None
+        }
+    }
+
+    /// Every key, with its frequency, in lexicographic order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.with_prefix(&[])
+    }
+
+    /// Every key starting with `prefix`, in lexicographic order.
+    pub fn with_prefix(&self, prefix: &[T]) -> Iter<'_, T> {
+        Iter {
+            stack: self.find(prefix).into_iter().collect(),
+        }
+    }
+
+    /**
+     * The longest prefix every key starting with `prefix` shares (`prefix`
+     * itself or longer), or `None` if there's no such key. With an empty
+     * `prefix`, that's the longest prefix of all keys.
+     */
+    pub fn longest_common_prefix(&self, prefix: &[T]) -> Option<Vec<T>> {
+        let (mut shared, mut node) = self.find(prefix)?;
+        if node.frequency.is_none() && node.children.is_empty() {
+            return None;
+        }
+        while node.frequency.is_none() && node.children.len() == 1 {
+            node = node.children.values().next().unwrap();
+            shared.extend(node.label.iter().cloned());
+        }
+        Some(shared)
+    }
+
+    /// The longest key that's a prefix of `query` (or all of it).
+    pub fn longest_prefix_of(&self, query: &[T]) -> Option<Vec<T>> {
+        let mut node = &self.root;
+        let mut depth = 0;
+        let mut longest = node.frequency.map(|_| 0);
+        while let Some(child) = query.get(depth).and_then(|first| node.children.get(first)) {
+            if !query[depth..].starts_with(&child.label) {
+                break;
+            }
+            depth += child.label.len();
+            node = child;
+            if node.frequency.is_some() {
+                longest = Some(depth);
+            }
+        }
+        longest.map(|len| query[..len].to_vec())
+    }
+
+    /// The `k` most frequent keys starting with `prefix`, most frequent
+    /// first, and lexicographic among equals.
+    pub fn top_k(&self, prefix: &[T], k: usize) -> Vec<(Vec<T>, u64)> {
+        top_k(self.with_prefix(prefix), k)
+    }
+
+    /**
+     * The highest node whose key starts with `prefix`, with its key. That's
+     * `prefix` itself, or longer when `prefix` ends partway along an edge.
+     */
+    fn find(&self, prefix: &[T]) -> Option<(Vec<T>, &Node<T>)> {
+        let mut node = &self.root;
+        let mut depth = 0;
+        while depth < prefix.len() {
+            let child = node.children.get(&prefix[depth])?;
+            let rest = &prefix[depth..];
+            if rest.len() < child.label.len() {
+                // Partway along the edge into `child`
+                if !child.label.starts_with(rest) {
+                    return None;
+                }
+                let mut key = prefix[..depth].to_vec();
+                key.extend(child.label.iter().cloned());
+                return Some((key, child));
+            }
+            if !rest.starts_with(&child.label) {
+                return None;
+            }
+            depth += child.label.len();
+            node = child;
+        }
+        Some((prefix.to_vec(), node))
+    }
+}
+
+/// The frequency of `key` below `node`, splitting an edge or adding a leaf
+/// to make a node for it if there's none.
+fn insert<'a, T: Ord + Clone>(node: &'a mut Node<T>, key: &[T]) -> &'a mut Option<u64> {
+    let first = match key.first() {
+        Some(first) => first,
+        None => return &mut node.frequency,
+    };
+    let child = node
+        .children
+        .entry(first.clone())
+        .or_insert_with(|| Node::new(key.to_vec()));
+    let shared = child
+        .label
+        .iter()
+        .zip(key)
+        .take_while(|(a, b)| a == b)
+        .count();
+    if shared < child.label.len() {
+        // Split the edge where `key` leaves it
+        let rest = child.label.split_off(shared);
+        let mut below = std::mem::replace(child, Node::new(rest));
+        std::mem::swap(&mut below.label, &mut child.label);
+        child.children.insert(below.label[0].clone(), below);
+    }
+    insert(child, &key[shared..])
+}
+
+/// Removes `key` below `node`, dropping the nodes that lead nowhere now and
+/// merging the ones left with a single child.
+fn remove<T: Ord + Clone>(node: &mut Node<T>, key: &[T]) -> Option<u64> {
+    let first = match key.first() {
+        Some(first) => first,
+        None => return node.frequency.take(),
+    };
+    let child = node.children.get_mut(first)?;
+    if !key.starts_with(&child.label) {
+        return None;
+    }
+    let frequency = remove(child, &key[child.label.len()..])?;
+    if child.frequency.is_none() {
+        match child.children.len() {
+            0 => {
+                node.children.remove(first);
+            }
+            1 => {
+                let (_, mut only) = child.children.pop_first().unwrap();
+                let mut label = std::mem::take(&mut child.label);
+                label.append(&mut only.label);
+                only.label = label;
+                *child = only;
+            }
+            _ => {}
+        }
+    }
+    Some(frequency)
+}
+
+impl<T: Ord + Clone> Default for RadixTree<T> {
+    fn default() -> Self {
+        RadixTree::new()
+    }
+}
+
+/// The keys of a `RadixTree`, with their frequencies, in lexicographic
+/// order.
+pub struct Iter<'a, T> {
+    /// The nodes left to visit, each with its key, the next one on top.
+    stack: Vec<(Vec<T>, &'a Node<T>)>,
+}
+
+impl<'a, T: Ord + Clone> Iterator for Iter<'a, T> {
+    type Item = (Vec<T>, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((key, node)) = self.stack.pop() {
+            for child in node.children.values().rev() {
+                let mut longer = key.clone();
+                longer.extend(child.label.iter().cloned());
+                self.stack.push((longer, child));
+            }
+            if let Some(frequency) = node.frequency {
+                return Some((key, frequency));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// How many nodes there are below the root, to check edges are merged.
+    fn count_nodes<T>(node: &Node<T>) -> usize {
+        node.children
+            .values()
+            .map(|child| 1 + count_nodes(child))
+            .sum()
+    }
+
+    #[test]
+    fn splits_and_merges_edges() {
+        let mut tree = RadixTree::new();
+        tree.insert(b"romane");
+        assert_eq!(count_nodes(&tree.root), 1);
+        tree.insert(b"romanus");
+        tree.insert(b"romulus");
+        // rom -> an -> e, us; ulus
+        assert_eq!(count_nodes(&tree.root), 5);
+        assert_eq!(tree.root.children[&b'r'].label, b"rom");
+        assert!(!tree.contains(b"rom"));
+        assert!(!tree.contains(b"romanes"));
+
+        assert_eq!(tree.remove(b"romanus"), Some(1));
+        // rom -> ane, ulus
+        assert_eq!(count_nodes(&tree.root), 3);
+        assert_eq!(tree.root.children[&b'r'].children[&b'a'].label, b"ane");
+        assert_eq!(tree.remove(b"romulus"), Some(1));
+        assert_eq!(count_nodes(&tree.root), 1);
+        assert_eq!(tree.root.children[&b'r'].label, b"romane");
+    }
+
+    #[test]
+    fn answers_prefix_queries_along_edges() {
+        let mut tree = RadixTree::new();
+        for word in &["test", "tester", "testing", "team"] {
+            tree.insert(word.as_bytes());
+        }
+        let keys: Vec<_> = tree.with_prefix(b"tes").map(|(key, _)| key).collect();
+        assert_eq!(keys, [&b"test"[..], b"tester", b"testing"]);
+        assert_eq!(tree.with_prefix(b"tex").count(), 0);
+        assert_eq!(
+            tree.longest_common_prefix(b"testi"),
+            Some(b"testing".to_vec())
+        );
+        assert_eq!(tree.longest_common_prefix(b"t"), Some(b"te".to_vec()));
+        assert_eq!(tree.longest_prefix_of(b"testers"), Some(b"tester".to_vec()));
+        assert_eq!(tree.longest_prefix_of(b"tes"), None);
+        tree.insert(b"test");
+        assert_eq!(tree.top_k(b"te", 1), vec![(b"test".to_vec(), 2)]);
+    }
+}
@@ -0,0 +1,442 @@
+use std::ops::Range;
+
+/// A value with an associative `combine` and an `identity` that changes
+/// nothing it's combined with, like numbers under `+` and `0`.
+pub trait Monoid: Clone {
+    fn identity() -> Self;
+
+    fn combine(&self, other: &Self) -> Self;
+}
+
+/**
+ * An update to every element in a range of `M`s. Updates are monoids too:
+ * `a.combine(&b)` is `a` followed by `b`, and the identity changes nothing.
+ */
+pub trait Update<M: Monoid>: Monoid {
+    /// `value`, the combination of `len` elements, once every one of them
+    /// is updated.
+    fn apply(&self, value: &M, len: usize) -> M;
+}
+
+/// No updates, for a tree that only has `set`.
+impl Monoid for () {
+    fn identity() -> Self {}
+
+    fn combine(&self, _: &Self) -> Self {}
+}
+
+impl<M: Monoid> Update<M> for () {
+    fn apply(&self, value: &M, _: usize) -> M {
+        value.clone()
+    }
+}
+
+/// The sum of a range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sum<T>(pub T);
+
+/// The smallest in a range. The identity is the type's `MAX`, so add to
+/// an element only once it's been set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Min<T>(pub T);
+
+/// The largest in a range. The identity is the type's `MIN`, like `Min`'s
+/// is its `MAX`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Max<T>(pub T);
+
+/// Adds the same amount to every element of a range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeAdd<T>(pub T);
+
+/// Sets every element of a range to the same value (`None` leaves them be).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeAssign<T>(pub Option<T>);
+
+macro_rules! monoids {
+    ($($t:ty),*) => {$(
+        impl Monoid for Sum<$t> {
+            fn identity() -> Self {
+                Sum(0)
+            }
+
+            fn combine(&self, other: &Self) -> Self {
+                Sum(self.0 + other.0)
+            }
+        }
+
+        impl Monoid for Min<$t> {
+            fn identity() -> Self {
+                Min(<$t>::MAX)
+            }
+
+            fn combine(&self, other: &Self) -> Self {
+                Min(self.0.min(other.0))
+            }
+        }
+
+        impl Monoid for Max<$t> {
+            fn identity() -> Self {
+                Max(<$t>::MIN)
+            }
+
+            fn combine(&self, other: &Self) -> Self {
+                Max(self.0.max(other.0))
+            }
+        }
+
+        impl Monoid for RangeAdd<$t> {
+            fn identity() -> Self {
+                RangeAdd(0)
+            }
+
+            fn combine(&self, other: &Self) -> Self {
+                RangeAdd(self.0 + other.0)
+            }
+        }
+
+        impl Update<Sum<$t>> for RangeAdd<$t> {
+            fn apply(&self, value: &Sum<$t>, len: usize) -> Sum<$t> {
+                Sum(value.0 + self.0 * len as $t)
+            }
+        }
+
+        impl Update<Min<$t>> for RangeAdd<$t> {
+            fn apply(&self, value: &Min<$t>, _: usize) -> Min<$t> {
+                Min(value.0 + self.0)
+            }
+        }
+
+        impl Update<Max<$t>> for RangeAdd<$t> {
+            fn apply(&self, value: &Max<$t>, _: usize) -> Max<$t> {
+                Max(value.0 + self.0)
+            }
+        }
+
+        impl Monoid for RangeAssign<$t> {
+            fn identity() -> Self {
+                RangeAssign(None)
+            }
+
+            fn combine(&self, other: &Self) -> Self {
+                RangeAssign(other.0.or(self.0))
+            }
+        }
+
+        impl Update<Sum<$t>> for RangeAssign<$t> {
+            fn apply(&self, value: &Sum<$t>, len: usize) -> Sum<$t> {
+                self.0.map_or(*value, |to| Sum(to * len as $t))
+            }
+        }
+
+        impl Update<Min<$t>> for RangeAssign<$t> {
+            fn apply(&self, value: &Min<$t>, _: usize) -> Min<$t> {
+                self.0.map_or(*value, Min)
+            }
+        }
+
+        impl Update<Max<$t>> for RangeAssign<$t> {
+            fn apply(&self, value: &Max<$t>, _: usize) -> Max<$t> {
+                self.0.map_or(*value, Max)
+            }
+        }
+    )*};
+}
+
+monoids!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+/**
+ * A segment tree with lazy propagation: combines any range of `M`s, and
+ * applies a `U` update to any range, both in O(log n). Every node holds the
+ * combination of its range, and an update it's yet to pass down to its
+ * children, which it only does once something needs to look below it.
+ */
+#[derive(Debug, Clone)]
+pub struct LazySegmentTree<M, U = ()> {
+    len: usize,
+    /// Node 1 is the root, and node `i` has the children `2i` and `2i + 1`.
+    values: Vec<M>,
+    pending: Vec<U>,
+}
+
+impl<M: Monoid, U: Update<M>> LazySegmentTree<M, U> {
+    /// `len` elements, all the identity.
+    pub fn new(len: usize) -> Self {
+        LazySegmentTree {
+            len,
+            values: vec![M::identity(); 4 * len.max(1)],
+            pending: vec![U::identity(); 4 * len.max(1)],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The combination of the elements in `range`, the identity if it's
+    /// empty.
+    pub fn query(&mut self, range: Range<usize>) -> M {
+        self.check(&range);
+        if range.is_empty() {
+            return M::identity();
+        }
+        self.query_node(1, 0..self.len, &range)
+    }
+
+    pub fn get(&mut self, index: usize) -> M {
+        self.query(index..index + 1)
+    }
+
+    /// Applies `update` to every element in `range`.
+    pub fn update(&mut self, range: Range<usize>, update: &U) {
+        self.check(&range);
+        if !range.is_empty() {
+            self.update_node(1, 0..self.len, &range, update);
+        }
+    }
+
+    /// Replaces the element at `index`.
+    pub fn set(&mut self, index: usize, value: M) {
+        self.check(&(index..index + 1));
+        let (mut node, mut span) = (1, 0..self.len);
+        // Down to the leaf, then back up combining
+        let mut path = Vec::new();
+        while span.len() > 1 {
+            self.push_down(node, &span);
+            path.push(node);
+            let mid = span.start + span.len() / 2;
+            if index < mid {
+                node *= 2;
+                span.end = mid;
+            } else {
+                node = node * 2 + 1;
+                span.start = mid;
+            }
+        }
+        self.values[node] = value;
+        for node in path.into_iter().rev() {
+            self.values[node] = self.values[2 * node].combine(&self.values[2 * node + 1]);
+        }
+    }
+
+    fn check(&self, range: &Range<usize>) {
+        assert!(
+            range.start <= range.end && range.end <= self.len,
+            "range {:?} out of {}",
+            range,
+            self.len
+        );
+    }
+
+    fn build(&mut self, node: usize, span: Range<usize>, values: &[M]) {
+        if span.len() == 1 {
+            self.values[node] = values[span.start].clone();
+            return;
+        }
+        let mid = span.start + span.len() / 2;
+        self.build(2 * node, span.start..mid, values);
+        self.build(2 * node + 1, mid..span.end, values);
+        self.values[node] = self.values[2 * node].combine(&self.values[2 * node + 1]);
+    }
+
+    /// Applies `update` to all of `node`, which covers `len` elements.
+    fn apply(&mut self, node: usize, len: usize, update: &U) {
+        self.values[node] = update.apply(&self.values[node], len);
+        self.pending[node] = self.pending[node].combine(update);
+    }
+
+    /// Passes `node`'s pending update down to its children.
+    fn push_down(&mut self, node: usize, span: &Range<usize>) {
+        let pending = std::mem::replace(&mut self.pending[node], U::identity());
+        let mid = span.start + span.len() / 2;
+        self.apply(2 * node, mid - span.start, &pending);
+        self.apply(2 * node + 1, span.end - mid, &pending);
+    }
+
+    fn query_node(&mut self, node: usize, span: Range<usize>, range: &Range<usize>) -> M {
+        if range.start <= span.start && span.end <= range.end {
+            return self.values[node].clone();
+        }
+        self.push_down(node, &span);
+        let mid = span.start + span.len() / 2;
+        let mut value = M::identity();
+        if range.start < mid {
+            value = value.combine(&self.query_node(2 * node, span.start..mid, range));
+        }
+        if mid < range.end {
+            value = value.combine(&self.query_node(2 * node + 1, mid..span.end, range));
+        }
+        value
+    }
+
+    fn update_node(&mut self, node: usize, span: Range<usize>, range: &Range<usize>, update: &U) {
+        if range.start <= span.start && span.end <= range.end {
+            self.apply(node, span.len(), update);
+            return;
+        }
+        self.push_down(node, &span);
+        let mid = span.start + span.len() / 2;
+        if range.start < mid {
+            self.update_node(2 * node, span.start..mid, range, update);
+        }
+        if mid < range.end {
+            self.update_node(2 * node + 1, mid..span.end, range, update);
+        }
+        self.values[node] = self.values[2 * node].combine(&self.values[2 * node + 1]);
+    }
+}
+
+impl<M: Monoid, U: Update<M>> From<&[M]> for LazySegmentTree<M, U> {
+    /// Builds the tree in O(n).
+    fn from(values: &[M]) -> Self {
+        let mut tree = LazySegmentTree::new(values.len());
+        if !values.is_empty() {
+            tree.build(1, 0..values.len(), values);
+        }
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn queries_and_updates_ranges() {
+        let values: Vec<_> = [5, 3, 8, 1, 9, 2].iter().map(|&v| Sum(v)).collect();
+        let mut sums = LazySegmentTree::<Sum<i64>, RangeAdd<i64>>::from(&values[..]);
+        assert_eq!(sums.query(0..6), Sum(28));
+        assert_eq!(sums.query(2..2), Sum(0));
+        sums.update(1..4, &RangeAdd(10));
+        assert_eq!(sums.query(0..3), Sum(36));
+        assert_eq!(sums.get(3), Sum(11));
+        sums.set(3, Sum(0));
+        assert_eq!(sums.query(0..6), Sum(47));
+
+        let mut mins = LazySegmentTree::<Min<u32>, RangeAssign<u32>>::new(4);
+        assert_eq!(mins.query(0..4), Min(u32::MAX));
+        mins.update(0..3, &RangeAssign(Some(7)));
+        mins.update(1..2, &RangeAssign(Some(4)));
+        assert_eq!(mins.query(0..4), Min(4));
+        assert_eq!(mins.query(2..4), Min(7));
+
+        let mut plain = LazySegmentTree::<Max<i8>>::new(3);
+        plain.set(1, Max(-5));
+        assert_eq!(plain.query(0..3), Max(-5));
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Add(Range<usize>, i64),
+        Assign(Range<usize>, i64),
+    }
+
+    fn ops(len: usize) -> impl Strategy<Value = Vec<Op>> {
+        let range = (0..=len, 0..=len).prop_map(|(a, b)| a.min(b)..a.max(b));
+        let op = prop_oneof![
+            (range.clone(), -50..50i64).prop_map(|(range, by)| Op::Add(range, by)),
+            (range, -50..50i64).prop_map(|(range, to)| Op::Assign(range, to)),
+        ];
+        prop::collection::vec(op, 0..30)
+    }
+
+    /// A range add or assign, or one then the other, for testing both at
+    /// once.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Both {
+        assign: Option<i64>,
+        add: i64,
+    }
+
+    impl Monoid for Both {
+        fn identity() -> Self {
+            Both {
+                assign: None,
+                add: 0,
+            }
+        }
+
+        fn combine(&self, later: &Self) -> Self {
+            match later.assign {
+                Some(_) => *later,
+                None => Both {
+                    assign: self.assign,
+                    add: self.add + later.add,
+                },
+            }
+        }
+    }
+
+    impl Update<Sum<i64>> for Both {
+        fn apply(&self, value: &Sum<i64>, len: usize) -> Sum<i64> {
+            let value = RangeAssign(self.assign).apply(value, len);
+            RangeAdd(self.add).apply(&value, len)
+        }
+    }
+
+    impl Update<Min<i64>> for Both {
+        fn apply(&self, value: &Min<i64>, len: usize) -> Min<i64> {
+            let value = RangeAssign(self.assign).apply(value, len);
+            RangeAdd(self.add).apply(&value, len)
+        }
+    }
+
+    fn matches<M>(values: &[i64], ops: &[Op], monoid: fn(i64) -> M) -> Result<(), TestCaseError>
+    where
+        M: Monoid + PartialEq + std::fmt::Debug,
+        Both: Update<M>,
+    {
+        let mut brute = values.to_vec();
+        let leaves: Vec<M> = values.iter().map(|&v| monoid(v)).collect();
+        let mut tree = LazySegmentTree::<M, Both>::from(&leaves[..]);
+        for op in ops {
+            let (range, update) = match op {
+                Op::Add(range, by) => (
+                    range.clone(),
+                    Both {
+                        assign: None,
+                        add: *by,
+                    },
+                ),
+                Op::Assign(range, to) => (
+                    range.clone(),
+                    Both {
+                        assign: Some(*to),
+                        add: 0,
+                    },
+                ),
+            };
+            for value in &mut brute[range.clone()] {
+                *value = update.assign.unwrap_or(*value) + update.add;
+            }
+            tree.update(range, &update);
+            for start in 0..=brute.len() {
+                for end in start..=brute.len() {
+                    let expected = brute[start..end]
+                        .iter()
+                        .fold(M::identity(), |acc, &v| acc.combine(&monoid(v)));
+                    prop_assert_eq!(tree.query(start..end), expected);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn matches_brute_force(
+            (values, ops) in prop::collection::vec(-50..50i64, 0..20).prop_flat_map(|values| {
+                let len = values.len();
+                (Just(values), ops(len))
+            })
+        ) {
+            matches(&values, &ops, Sum)?;
+            matches(&values, &ops, Min)?;
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+
+/**
+ * A prefix tree of keys made of `T`s (bytes, chars, ...), one node per
+ * element, counting how often every key's been inserted. The children are
+ * kept in order, so iteration is lexicographic, and every query for a
+ * prefix only has to walk down it: O(prefix length) to get to its keys.
+ */
+#[derive(Debug, Clone)]
+pub struct Trie<T> {
+    root: Node<T>,
+    len: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Node<T> {
+    /// How many times the key ending here was inserted, if it's a key.
+    frequency: Option<u64>,
+    children: BTreeMap<T, Node<T>>,
+}
+
+impl<T> Node<T> {
+    fn new() -> Self {
+        Node {
+            frequency: None,
+            children: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: Ord + Clone> Trie<T> {
+    pub fn new() -> Self {
+        Trie {
+            root: Node::new(),
+            len: 0,
+        }
+    }
+
+    /// How many keys there are.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts `key` once more, and returns how often it's been inserted.
+    pub fn insert(&mut self, key: &[T]) -> u64 {
+        let mut node = &mut self.root;
+        for element in key {
+            node = node
+                .children
+                .entry(element.clone())
+                .or_insert_with(Node::new);
+        }
+        if node.frequency.is_none() {
+            self.len += 1;
+        }
+        let frequency = node.frequency.get_or_insert(0);
+        *frequency += 1;
+        *frequency
+    }
+
+    /// Removes `key`, however often it was inserted, and returns how often
+    /// that was. Nodes left leading to no key are removed too.
+    pub fn remove(&mut self, key: &[T]) -> Option<u64> {
+        let frequency = remove(&mut self.root, key);
+        if frequency.is_some() {
+            self.len -= 1;
+        }
+        frequency
+    }
+
+    pub fn contains(&self, key: &[T]) -> bool {
+        self.frequency(key).is_some()
+    }
+
+    /// How often `key` has been inserted, if it's in the trie.
+    pub fn frequency(&self, key: &[T]) -> Option<u64> {
+        self.find(key)?.frequency
+    }
+
+    /// Every key, with its frequency, in lexicographic order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.with_prefix(&[])
+    }
+
+    /// Every key starting with `prefix`, in lexicographic order.
+    pub fn with_prefix(&self, prefix: &[T]) -> Iter<'_, T> {
+        Iter {
+            stack: self
+                .find(prefix)
+                .map(|node| (prefix.to_vec(), node))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /**
+     * The longest prefix every key starting with `prefix` shares (`prefix`
+     * itself or longer), or `None` if there's no such key. With an empty
+     * `prefix`, that's the longest prefix of all keys; while typing, it's
+     * how far it's safe to complete.
+     */
+    pub fn longest_common_prefix(&self, prefix: &[T]) -> Option<Vec<T>> {
+        let mut node = self.find(prefix)?;
+        if node.frequency.is_none() && node.children.is_empty() {
+            return None;
+        }
+        let mut shared = prefix.to_vec();
+        // Down while there's only one way to go
+        while node.frequency.is_none() && node.children.len() == 1 {
+            let (element, child) = node.children.iter().next().unwrap();
+            shared.push(element.clone());
+            node = child;
+        }
+        Some(shared)
+    }
+
+    /// The longest key that's a prefix of `query` (or all of it).
+    pub fn longest_prefix_of(&self, query: &[T]) -> Option<Vec<T>> {
+        let mut node = &self.root;
+        let mut longest = node.frequency.map(|_| 0);
+        for (i, element) in query.iter().enumerate() {
+            node = match node.children.get(element) {
+                Some(child) => child,
+                None => break,
+            };
+            if node.frequency.is_some() {
+                longest = Some(i + 1);
+            }
+        }
+        longest.map(|len| query[..len].to_vec())
+    }
+
+    /// The `k` most frequent keys starting with `prefix`, most frequent
+    /// first, and lexicographic among equals.
+    pub fn top_k(&self, prefix: &[T], k: usize) -> Vec<(Vec<T>, u64)> {
+        top_k(self.with_prefix(prefix), k)
+    }
+
+    fn find(&self, key: &[T]) -> Option<&Node<T>> {
+        let mut node = &self.root;
+        for element in key {
+            node = node.children.get(element)?;
+        }
+        Some(node)
+    }
+}
+
+/// Removes `key` below `node`, pruning the nodes that lead nowhere now.
+fn remove<T: Ord>(node: &mut Node<T>, key: &[T]) -> Option<u64> {
+    let (first, rest) = match key.split_first() {
+        Some(split) => split,
+        None => return node.frequency.take(),
+    };
+    let child = node.children.get_mut(first)?;
+    let frequency = remove(child, rest)?;
+    if child.frequency.is_none() && child.children.is_empty() {
+        node.children.remove(first);
+    }
+    Some(frequency)
+}
+
+impl<T: Ord + Clone> Default for Trie<T> {
+    fn default() -> Self {
+        Trie::new()
+    }
+}
+
+/// The keys of a `Trie`, with their frequencies, in lexicographic order.
+pub struct Iter<'a, T> {
+    /// The nodes left to visit, each with its key, the next one on top.
+    stack: Vec<(Vec<T>, &'a Node<T>)>,
+}
+
+impl<'a, T: Ord + Clone> Iterator for Iter<'a, T> {
+    type Item = (Vec<T>, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // A key comes before every key it's a prefix of, so visit a node
+        // before its children, smallest child first
+        while let Some((key, node)) = self.stack.pop() {
+            for (element, child) in node.children.iter().rev() {
+                let mut longer = key.clone();
+                longer.push(element.clone());
+                self.stack.push((longer, child));
+            }
+            if let Some(frequency) = node.frequency {
+                return Some((key, frequency));
+            }
+        }
+        None
+    }
+}
+
+/**
+ * The `k` most frequent of `entries`, which come in lexicographic order:
+ * most frequent first, and lexicographic among equals. Keeps the best `k`
+ * in a heap, so O(n log k) for `n` entries.
+ */
+pub(crate) fn top_k<T: Ord>(
+    entries: impl Iterator<Item = (Vec<T>, u64)>,
+    k: usize,
+) -> Vec<(Vec<T>, u64)> {
+    if k == 0 {
+        return Vec::new();
+    }
+    // A min-heap of the best so far by (frequency, earlier), the worst on top
+    let mut best = BinaryHeap::with_capacity(k + 1);
+    for (index, (key, frequency)) in entries.enumerate() {
+        best.push(Reverse((frequency, Reverse(index), key)));
+        if best.len() > k {
+            best.pop();
+        }
+    }
+    let mut best: Vec<_> = best.into_iter().map(|Reverse(entry)| entry).collect();
+    best.sort_unstable_by(|a, b| b.cmp(a));
+    best.into_iter()
+        .map(|(frequency, _, key)| (key, frequency))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(trie: &Trie<u8>, entries: Vec<(Vec<u8>, u64)>) -> Vec<String> {
+        assert!(entries.iter().all(|(key, _)| trie.contains(key)));
+        entries
+            .into_iter()
+            .map(|(key, _)| String::from_utf8(key).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn inserts_and_removes() {
+        let mut trie = Trie::new();
+        for word in &["car", "cart", "care", "cat", "car", "dog"] {
+            trie.insert(word.as_bytes());
+        }
+        assert_eq!(trie.len(), 5);
+        assert_eq!(trie.frequency(b"car"), Some(2));
+        assert!(!trie.contains(b"ca"));
+        assert_eq!(
+            words(&trie, trie.iter().collect()),
+            ["car", "care", "cart", "cat", "dog"]
+        );
+        assert_eq!(
+            words(&trie, trie.with_prefix(b"car").collect()),
+            ["car", "care", "cart"]
+        );
+
+        assert_eq!(trie.remove(b"car"), Some(2));
+        assert_eq!(trie.remove(b"car"), None);
+        assert_eq!(trie.remove(b"ca"), None);
+        assert_eq!(
+            words(&trie, trie.with_prefix(b"car").collect()),
+            ["care", "cart"]
+        );
+        assert_eq!(trie.len(), 4);
+    }
+
+    #[test]
+    fn answers_prefix_queries() {
+        let mut trie = Trie::new();
+        for word in &["interval", "internet", "internal", "in"] {
+            trie.insert(&word.chars().collect::<Vec<_>>());
+        }
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(
+            trie.longest_common_prefix(&chars("int")),
+            Some(chars("inter"))
+        );
+        assert_eq!(trie.longest_common_prefix(&chars("")), Some(chars("in")));
+        assert_eq!(trie.longest_common_prefix(&chars("out")), None);
+        assert_eq!(
+            trie.longest_prefix_of(&chars("internals")),
+            Some(chars("internal"))
+        );
+        assert_eq!(trie.longest_prefix_of(&chars("inter")), Some(chars("in")));
+        assert_eq!(trie.longest_prefix_of(&chars("i")), None);
+    }
+
+    #[test]
+    fn completes_the_most_frequent() {
+        let mut trie = Trie::new();
+        for (word, times) in &[
+            ("the", 5),
+            ("then", 2),
+            ("they", 2),
+            ("there", 3),
+            ("to", 9),
+        ] {
+            for _ in 0..*times {
+                trie.insert(word.as_bytes());
+            }
+        }
+        let top = trie.top_k(b"th", 3);
+        assert_eq!(words(&trie, top.clone()), ["the", "there", "then"]);
+        assert_eq!(top[0].1, 5);
+        assert_eq!(trie.top_k(b"x", 3), vec![]);
+        assert_eq!(trie.top_k(b"t", 0), vec![]);
+    }
+}
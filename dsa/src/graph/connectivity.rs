@@ -0,0 +1,332 @@
+use std::cmp::min;
+
+use super::Graph;
+
+/**
+ * Tarjan: the strongly connected components of a directed graph, the sets
+ * of vertices that can all reach each other. They come out in reverse
+ * topological order: no component has an edge to a later one. O(V + E).
+ *
+ * Recursive, so very deep graphs can run out of stack.
+ */
+pub fn tarjan_scc<G: Graph>(graph: &G) -> Vec<Vec<usize>> {
+    assert!(
+        graph.is_directed(),
+        "strongly connected components are for directed graphs"
+    );
+    let mut search = Search::new(graph.vertex_count());
+    let mut stack = Vec::new();
+    let mut on_stack = vec![false; graph.vertex_count()];
+    let mut components = Vec::new();
+    for root in 0..graph.vertex_count() {
+        if search.index[root].is_none() {
+            strong_connect(
+                graph,
+                root,
+                &mut search,
+                &mut stack,
+                &mut on_stack,
+                &mut components,
+            );
+        }
+    }
+    components
+}
+
+fn strong_connect<G: Graph>(
+    graph: &G,
+    vertex: usize,
+    search: &mut Search,
+    stack: &mut Vec<usize>,
+    on_stack: &mut [bool],
+    components: &mut Vec<Vec<usize>>,
+) {
+    let index = search.visit(vertex);
+    stack.push(vertex);
+    on_stack[vertex] = true;
+    for (next, _) in graph.neighbours(vertex) {
+        match search.index[next] {
+            None => {
+                strong_connect(graph, next, search, stack, on_stack, components);
+                search.low[vertex] = min(search.low[vertex], search.low[next]);
+            }
+            Some(next_index) if on_stack[next] => {
+                search.low[vertex] = min(search.low[vertex], next_index);
+            }
+            // Finished, in a component of its own already
+            Some(_) => {}
+        }
+    }
+    // Nothing above `vertex` is reachable from it, so it's the root of a
+    // component: everything pushed since
+    if search.low[vertex] == index {
+        let mut component = Vec::new();
+        loop {
+            let member = stack.pop().unwrap();
+            on_stack[member] = false;
+            component.push(member);
+            if member == vertex {
+                break;
+            }
+        }
+        components.push(component);
+    }
+}
+
+/// The edges of an undirected graph whose removal would split it, as
+/// `(from, to)` with `from < to`, smallest first.
+pub fn bridges<G: Graph>(graph: &G) -> Vec<(usize, usize)> {
+    let mut bridges = Vec::new();
+    cut_search(graph, |cut| {
+        if let Cut::Bridge(from, to) = cut {
+            bridges.push((min(from, to), from.max(to)));
+        }
+    });
+    bridges.sort();
+    bridges
+}
+
+/// The vertices of an undirected graph whose removal would split it,
+/// smallest first.
+pub fn articulation_points<G: Graph>(graph: &G) -> Vec<usize> {
+    let mut is_cut = vec![false; graph.vertex_count()];
+    cut_search(graph, |cut| {
+        if let Cut::Vertex(vertex) = cut {
+            is_cut[vertex] = true;
+        }
+    });
+    (0..graph.vertex_count()).filter(|&v| is_cut[v]).collect()
+}
+
+/// The discovery index of every vertex, and the lowest one it can reach
+/// through its subtree of the depth-first search.
+struct Search {
+    index: Vec<Option<usize>>,
+    low: Vec<usize>,
+    next: usize,
+}
+
+impl Search {
+    fn new(vertices: usize) -> Self {
+        Search {
+            index: vec![None; vertices],
+            low: vec![0; vertices],
+            next: 0,
+        }
+    }
+
+    fn visit(&mut self, vertex: usize) -> usize {
+        self.index[vertex] = Some(self.next);
+        self.low[vertex] = self.next;
+        self.next += 1;
+        self.next - 1
+    }
+}
+
+enum Cut {
+    Bridge(usize, usize),
+    Vertex(usize),
+}
+
+/// Reports every bridge and articulation point (a vertex may come up more
+/// than once) of an undirected graph to `found`.
+fn cut_search<G: Graph, F: FnMut(Cut)>(graph: &G, mut found: F) {
+    assert!(
+        !graph.is_directed(),
+        "bridges and articulation points are for undirected graphs"
+    );
+    let mut search = Search::new(graph.vertex_count());
+    for root in 0..graph.vertex_count() {
+        if search.index[root].is_none() {
+            search.visit(root);
+            let children = cut_children(graph, root, None, &mut search, &mut found);
+            // The root's only cut if it holds apart two subtrees
+            if children > 1 {
+                found(Cut::Vertex(root));
+            }
+        }
+    }
+}
+
+/// Searches below `vertex`, reached from `parent`, and returns how many
+/// subtrees it has.
+fn cut_children<G: Graph, F: FnMut(Cut)>(
+    graph: &G,
+    vertex: usize,
+    parent: Option<usize>,
+    search: &mut Search,
+    found: &mut F,
+) -> usize {
+    let index = search.index[vertex].unwrap();
+    let mut children = 0;
+    // Skip the edge back to the parent once: a parallel one is another way
+    // back
+    let mut skipped_parent = false;
+    for (next, _) in graph.neighbours(vertex) {
+        if Some(next) == parent && !skipped_parent {
+            skipped_parent = true;
+            continue;
+        }
+        match search.index[next] {
+            None => {
+                search.visit(next);
+                children += 1;
+                cut_children(graph, next, Some(vertex), search, found);
+                search.low[vertex] = min(search.low[vertex], search.low[next]);
+                // Nothing below `next` gets above it without this edge
+                if search.low[next] > index {
+                    found(Cut::Bridge(vertex, next));
+                }
+                // ...or above `vertex` without going through it
+                if parent.is_some() && search.low[next] >= index {
+                    found(Cut::Vertex(vertex));
+                }
+            }
+            Some(next_index) => search.low[vertex] = min(search.low[vertex], next_index),
+        }
+    }
+    children
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{bfs, AdjacencyList};
+    use proptest::prelude::*;
+
+    fn build(directed: bool, vertices: usize, edges: &[(usize, usize)]) -> AdjacencyList {
+        let mut graph = if directed {
+            AdjacencyList::new_directed(vertices)
+        } else {
+            AdjacencyList::new_undirected(vertices)
+        };
+        for &(from, to) in edges {
+            graph.add_edge(from, to, ());
+        }
+        graph
+    }
+
+    #[test]
+    fn finds_strongly_connected_components() {
+        let graph = build(
+            true,
+            8,
+            &[
+                (0, 1),
+                (1, 2),
+                (2, 0),
+                (2, 3),
+                (3, 4),
+                (4, 3),
+                (4, 5),
+                (6, 6),
+            ],
+        );
+        let mut components = tarjan_scc(&graph);
+        for component in &mut components {
+            component.sort();
+        }
+        assert_eq!(
+            components,
+            vec![vec![5], vec![3, 4], vec![0, 1, 2], vec![6], vec![7]]
+        );
+    }
+
+    #[test]
+    fn finds_bridges_and_articulation_points() {
+        // Two triangles joined by the path 2 - 3 - 4
+        let edges = [
+            (0, 1),
+            (1, 2),
+            (2, 0),
+            (2, 3),
+            (3, 4),
+            (4, 5),
+            (5, 6),
+            (6, 4),
+        ];
+        let graph = build(false, 8, &edges);
+        assert_eq!(bridges(&graph), vec![(2, 3), (3, 4)]);
+        assert_eq!(articulation_points(&graph), vec![2, 3, 4]);
+
+        // A parallel edge is no bridge
+        let parallel = build(false, 3, &[(0, 1), (0, 1), (1, 2)]);
+        assert_eq!(bridges(&parallel), vec![(1, 2)]);
+        assert_eq!(articulation_points(&parallel), vec![1]);
+    }
+
+    /// How many vertices other than `removed` `start` reaches, without
+    /// `removed` or the edge `without`.
+    fn reached(
+        graph: &AdjacencyList,
+        start: usize,
+        removed: Option<usize>,
+        without: Option<(usize, usize)>,
+    ) -> usize {
+        let mut kept = AdjacencyList::new_undirected(graph.vertex_count());
+        let mut skipped = false;
+        for (from, to, _) in graph.edges() {
+            if Some(from) == removed || Some(to) == removed {
+                continue;
+            }
+            if Some((from, to)) == without && !skipped {
+                skipped = true;
+                continue;
+            }
+            kept.add_edge(from, to, ());
+        }
+        bfs(&kept, start).len()
+    }
+
+    proptest! {
+        #[test]
+        fn cuts_match_brute_force(
+            (n, edges) in (1..9usize).prop_flat_map(|n| {
+                (Just(n), prop::collection::vec((0..n, 0..n), 0..14))
+            })
+        ) {
+            let graph = build(false, n, &edges);
+            let bridges = bridges(&graph);
+            for (from, to, _) in graph.edges() {
+                let splits = reached(&graph, from, None, Some((from, to))) < reached(&graph, from, None, None);
+                prop_assert_eq!(bridges.contains(&(from, to)), splits, "{} - {}", from, to);
+            }
+            let points = articulation_points(&graph);
+            for vertex in 0..n {
+                let others = (0..n).filter(|&v| v != vertex);
+                let splits = others.clone().any(|v| {
+                    reached(&graph, v, Some(vertex), None) + 1 < reached(&graph, v, None, None)
+                });
+                prop_assert_eq!(points.contains(&vertex), splits, "{}", vertex);
+            }
+        }
+
+        #[test]
+        fn components_are_mutually_reachable(
+            (n, edges) in (1..9usize).prop_flat_map(|n| {
+                (Just(n), prop::collection::vec((0..n, 0..n), 0..20))
+            })
+        ) {
+            let graph = build(true, n, &edges);
+            let components = tarjan_scc(&graph);
+            let mut component = vec![0; n];
+            for (i, members) in components.iter().enumerate() {
+                for &v in members {
+                    component[v] = i;
+                }
+            }
+            prop_assert_eq!(components.iter().map(Vec::len).sum::<usize>(), n);
+            let reach: Vec<Vec<usize>> = (0..n).map(|v| bfs(&graph, v)).collect();
+            for a in 0..n {
+                for b in 0..n {
+                    let mutual = reach[a].contains(&b) && reach[b].contains(&a);
+                    prop_assert_eq!(component[a] == component[b], mutual);
+                    // Later components are never reached from earlier ones
+                    if reach[a].contains(&b) {
+                        prop_assert!(component[a] >= component[b]);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::fmt::{Display, Write};
+
+use super::Graph;
+
+/// The graph in Graphviz's DOT language, with the weights as edge labels.
+pub fn to_dot<G>(graph: &G) -> String
+where
+    G: Graph,
+    G::Weight: Display,
+{
+    to_dot_with(graph, |weight| Some(weight.to_string()))
+}
+
+/**
+ * The graph in Graphviz's DOT language, labelling every edge with
+ * `label(weight)`, or leaving it bare for `None`. Every vertex is listed, so
+ * ones without edges show up too.
+ */
+pub fn to_dot_with<G, F>(graph: &G, mut label: F) -> String
+where
+    G: Graph,
+    F: FnMut(&G::Weight) -> Option<String>,
+{
+    let (kind, arrow) = if graph.is_directed() {
+        ("digraph", "->")
+    } else {
+        ("graph", "--")
+    };
+    let mut dot = format!("{} {{\n", kind);
+    for vertex in 0..graph.vertex_count() {
+        writeln!(dot, "    {};", vertex).unwrap();
+    }
+    for (from, to, weight) in graph.edges() {
+        write!(dot, "    {} {} {}", from, arrow, to).unwrap();
+        if let Some(label) = label(&weight) {
+            write!(dot, " [label=\"{}\"]", escape(&label)).unwrap();
+        }
+        dot.push_str(";\n");
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{AdjacencyList, AdjacencyMatrix};
+
+    #[test]
+    fn exports_directed_graphs() {
+        let mut graph = AdjacencyList::new_directed(3);
+        graph.add_edge(0, 1, 5);
+        graph.add_edge(1, 0, -2);
+        assert_eq!(
+            to_dot(&graph),
+            "digraph {\n    0;\n    1;\n    2;\n    0 -> 1 [label=\"5\"];\n    1 -> 0 [label=\"-2\"];\n}\n"
+        );
+    }
+
+    #[test]
+    fn exports_undirected_graphs() {
+        let mut graph = AdjacencyMatrix::new_undirected(2);
+        graph.add_edge(1, 0, "say \"hi\"");
+        assert_eq!(
+            to_dot(&graph),
+            "graph {\n    0;\n    1;\n    0 -- 1 [label=\"say \\\"hi\\\"\"];\n}\n"
+        );
+        let mut unweighted = AdjacencyList::new_undirected(2);
+        unweighted.add_edge(0, 1, ());
+        assert_eq!(
+            to_dot_with(&unweighted, |_| None),
+            "graph {\n    0;\n    1;\n    0 -- 1;\n}\n"
+        );
+    }
+}
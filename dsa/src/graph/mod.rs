@@ -0,0 +1,17 @@
+mod connectivity;
+mod dot;
+mod representation;
+mod shortest_path;
+mod spanning_tree;
+mod topological_sort;
+mod traversal;
+
+pub use self::connectivity::{articulation_points, bridges, tarjan_scc};
+pub use self::dot::{to_dot, to_dot_with};
+pub use self::representation::{AdjacencyList, AdjacencyMatrix, Graph, Weight};
+pub use self::shortest_path::{
+    bellman_ford, dijkstra, floyd_warshall, NegativeCycle, ShortestPaths,
+};
+pub use self::spanning_tree::{kruskal, prim};
+pub use self::topological_sort::topological_sort;
+pub use self::traversal::{bfs, bfs_distances, dfs};
@@ -0,0 +1,196 @@
+use std::ops::Add;
+
+/// What the weighted algorithms (shortest paths, spanning trees) need of edge
+/// weights: adding them up, comparing them, and a zero (`Default`).
+pub trait Weight: Copy + Ord + Add<Output = Self> + Default {}
+
+impl<T: Copy + Ord + Add<Output = T> + Default> Weight for T {}
+
+/**
+ * A graph over the vertices `0..vertex_count()`, with edges weighted by
+ * `Self::Weight` (`()` when they aren't). Undirected edges are seen from
+ * both ends.
+ */
+pub trait Graph {
+    type Weight: Copy;
+
+    fn vertex_count(&self) -> usize;
+
+    fn is_directed(&self) -> bool;
+
+    /// `(vertex, weight)` of every edge out of `vertex`.
+    fn neighbours(&self, vertex: usize) -> impl Iterator<Item = (usize, Self::Weight)> + '_;
+
+    /// `(from, to, weight)` of every edge, once each: undirected ones with
+    /// `from <= to`.
+    fn edges(&self) -> Vec<(usize, usize, Self::Weight)> {
+        let mut edges = Vec::new();
+        for from in 0..self.vertex_count() {
+            for (to, weight) in self.neighbours(from) {
+                if self.is_directed() || from <= to {
+                    edges.push((from, to, weight));
+                }
+            }
+        }
+        edges
+    }
+}
+
+/// Every vertex keeps a list of its edges: O(V + E) space, and quick to
+/// walk the neighbours of sparse graphs.
+#[derive(Debug, Clone)]
+pub struct AdjacencyList<W = ()> {
+    directed: bool,
+    adjacency: Vec<Vec<(usize, W)>>,
+}
+
+impl<W: Copy> AdjacencyList<W> {
+    pub fn new_directed(vertices: usize) -> Self {
+        AdjacencyList {
+            directed: true,
+            adjacency: vec![Vec::new(); vertices],
+        }
+    }
+
+    pub fn new_undirected(vertices: usize) -> Self {
+        AdjacencyList {
+            directed: false,
+            ..AdjacencyList::new_directed(vertices)
+        }
+    }
+
+    /// Adds a vertex without edges, and returns it.
+    pub fn add_vertex(&mut self) -> usize {
+        self.adjacency.push(Vec::new());
+        self.adjacency.len() - 1
+    }
+
+    /// Adds an edge, alongside any already between `from` and `to`.
+    pub fn add_edge(&mut self, from: usize, to: usize, weight: W) {
+        assert!(to < self.adjacency.len(), "no vertex {}", to);
+        self.adjacency[from].push((to, weight));
+        if !self.directed && from != to {
+            self.adjacency[to].push((from, weight));
+        }
+    }
+}
+
+impl<W: Copy> Graph for AdjacencyList<W> {
+    type Weight = W;
+
+    fn vertex_count(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    fn neighbours(&self, vertex: usize) -> impl Iterator<Item = (usize, W)> + '_ {
+        self.adjacency[vertex].iter().copied()
+    }
+}
+
+/// A `V x V` matrix of the edge (if any) between every two vertices: O(V^2)
+/// space, and O(1) to tell whether two vertices are adjacent. There's at most
+/// one edge from a vertex to another.
+#[derive(Debug, Clone)]
+pub struct AdjacencyMatrix<W = ()> {
+    directed: bool,
+    matrix: Vec<Vec<Option<W>>>,
+}
+
+impl<W: Copy> AdjacencyMatrix<W> {
+    pub fn new_directed(vertices: usize) -> Self {
+        AdjacencyMatrix {
+            directed: true,
+            matrix: vec![vec![None; vertices]; vertices],
+        }
+    }
+
+    pub fn new_undirected(vertices: usize) -> Self {
+        AdjacencyMatrix {
+            directed: false,
+            ..AdjacencyMatrix::new_directed(vertices)
+        }
+    }
+
+    /// Adds an edge, replacing the one between `from` and `to`, if any.
+    pub fn add_edge(&mut self, from: usize, to: usize, weight: W) {
+        assert!(to < self.matrix.len(), "no vertex {}", to);
+        self.matrix[from][to] = Some(weight);
+        if !self.directed {
+            self.matrix[to][from] = Some(weight);
+        }
+    }
+
+    /// The weight of the edge from `from` to `to`, if there's one.
+    pub fn edge(&self, from: usize, to: usize) -> Option<W> {
+        self.matrix[from][to]
+    }
+}
+
+impl<W: Copy> Graph for AdjacencyMatrix<W> {
+    type Weight = W;
+
+    fn vertex_count(&self) -> usize {
+        self.matrix.len()
+    }
+
+    fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    fn neighbours(&self, vertex: usize) -> impl Iterator<Item = (usize, W)> + '_ {
+        let row = self.matrix[vertex].iter().enumerate();
+        row.filter_map(|(to, weight)| weight.map(|weight| (to, weight)))
+    }
+}
+
+impl<W: Copy> From<&AdjacencyList<W>> for AdjacencyMatrix<W> {
+    /// The same graph as a matrix, keeping the last of any parallel edges.
+    fn from(list: &AdjacencyList<W>) -> Self {
+        let mut matrix = AdjacencyMatrix {
+            directed: list.directed,
+            matrix: vec![vec![None; list.vertex_count()]; list.vertex_count()],
+        };
+        for (from, to, weight) in list.edges() {
+            matrix.add_edge(from, to, weight);
+        }
+        matrix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_and_matrix_agree() {
+        let mut list = AdjacencyList::new_undirected(3);
+        list.add_edge(0, 1, 5);
+        list.add_edge(2, 1, 7);
+        list.add_edge(2, 2, 1);
+        let matrix = AdjacencyMatrix::from(&list);
+
+        assert_eq!(list.edges(), vec![(0, 1, 5), (1, 2, 7), (2, 2, 1)]);
+        assert_eq!(matrix.edges(), list.edges());
+        assert_eq!(matrix.edge(1, 2), Some(7));
+        assert_eq!(matrix.edge(0, 2), None);
+        let mut neighbours: Vec<_> = list.neighbours(2).collect();
+        neighbours.sort();
+        assert_eq!(neighbours, matrix.neighbours(2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn directed_edges_go_one_way() {
+        let mut list = AdjacencyList::new_directed(2);
+        list.add_edge(1, 0, ());
+        assert_eq!(list.neighbours(0).count(), 0);
+        assert_eq!(list.edges(), vec![(1, 0, ())]);
+        let vertex = list.add_vertex();
+        list.add_edge(vertex, 1, ());
+        assert_eq!(list.vertex_count(), 3);
+        assert!(AdjacencyMatrix::from(&list).is_directed());
+    }
+}
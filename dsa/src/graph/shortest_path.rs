@@ -0,0 +1,295 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
+
+use super::{Graph, Weight};
+
+/// The shortest paths from one vertex to every other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShortestPaths<W> {
+    source: usize,
+    /// How far every vertex is, if it's reachable.
+    distances: Vec<Option<W>>,
+    /// The vertex before every vertex on its shortest path.
+    previous: Vec<Option<usize>>,
+}
+
+impl<W: Copy> ShortestPaths<W> {
+    pub fn distance(&self, to: usize) -> Option<W> {
+        self.distances[to]
+    }
+
+    pub fn distances(&self) -> &[Option<W>] {
+        &self.distances
+    }
+
+    /// The vertices on the shortest path to `to`, from the source, if it's
+    /// reachable.
+    pub fn path(&self, to: usize) -> Option<Vec<usize>> {
+        self.distances[to]?;
+        let mut path = vec![to];
+        while let Some(previous) = self.previous[*path.last().unwrap()] {
+            path.push(previous);
+        }
+        path.reverse();
+        debug_assert_eq!(path[0], self.source);
+        Some(path)
+    }
+}
+
+/// A cycle whose edges add up to less than zero, which any path through it
+/// can go round forever to get shorter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NegativeCycle;
+
+impl fmt::Display for NegativeCycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the graph has a negative cycle")
+    }
+}
+
+/**
+ * Dijkstra: settles vertices nearest first, from a binary heap. Every edge
+ * weight must be at least zero. O((V + E) log V).
+ */
+pub fn dijkstra<G>(graph: &G, source: usize) -> ShortestPaths<G::Weight>
+where
+    G: Graph,
+    G::Weight: Weight,
+{
+    let zero = G::Weight::default();
+    let mut paths = ShortestPaths::new(graph.vertex_count(), source);
+    let mut heap = BinaryHeap::new();
+    heap.push(Reverse((zero, source)));
+    while let Some(Reverse((distance, vertex))) = heap.pop() {
+        // Already settled nearer, this entry's out of date
+        if paths.distances[vertex].is_some_and(|best| best < distance) {
+            continue;
+        }
+        for (next, weight) in graph.neighbours(vertex) {
+            assert!(weight >= zero, "Dijkstra needs weights of at least zero");
+            let candidate = distance + weight;
+            if paths.distances[next].is_none_or(|best| candidate < best) {
+                paths.distances[next] = Some(candidate);
+                paths.previous[next] = Some(vertex);
+                heap.push(Reverse((candidate, next)));
+            }
+        }
+    }
+    paths
+}
+
+/**
+ * Bellman-Ford: relaxes every edge `V - 1` times, which is enough for any
+ * shortest path, and once more to find a negative cycle. Weights may be
+ * negative. O(V E).
+ */
+pub fn bellman_ford<G>(graph: &G, source: usize) -> Result<ShortestPaths<G::Weight>, NegativeCycle>
+where
+    G: Graph,
+    G::Weight: Weight,
+{
+    let mut paths = ShortestPaths::new(graph.vertex_count(), source);
+    let edges = directed_edges(graph);
+    for round in 0..graph.vertex_count() {
+        let mut changed = false;
+        for &(from, to, weight) in &edges {
+            let candidate = match paths.distances[from] {
+                Some(distance) => distance + weight,
+                None => continue,
+            };
+            if paths.distances[to].is_none_or(|best| candidate < best) {
+                paths.distances[to] = Some(candidate);
+                paths.previous[to] = Some(from);
+                changed = true;
+            }
+        }
+        if !changed {
+            return Ok(paths);
+        }
+        // Still getting shorter after `V - 1` rounds
+        if round == graph.vertex_count() - 1 {
+            return Err(NegativeCycle);
+        }
+    }
+    Ok(paths)
+}
+
+/**
+ * Floyd-Warshall: the distance between every two vertices, allowing paths
+ * through vertices `0..k` for every `k` in turn. Weights may be negative.
+ * O(V^3).
+ */
+pub fn floyd_warshall<G>(graph: &G) -> Result<Vec<Vec<Option<G::Weight>>>, NegativeCycle>
+where
+    G: Graph,
+    G::Weight: Weight,
+{
+    let n = graph.vertex_count();
+    let mut distances = vec![vec![None; n]; n];
+    for (vertex, row) in distances.iter_mut().enumerate() {
+        row[vertex] = Some(G::Weight::default());
+    }
+    for (from, to, weight) in directed_edges(graph) {
+        if distances[from][to].is_none_or(|best| weight < best) {
+            distances[from][to] = Some(weight);
+        }
+    }
+    for k in 0..n {
+        // Row `k` can't get shorter through `k` itself, short of a negative
+        // cycle
+        let via = distances[k].clone();
+        for row in &mut distances {
+            let through = match row[k] {
+                Some(through) => through,
+                None => continue,
+            };
+            for (best, rest) in row.iter_mut().zip(&via) {
+                if let Some(rest) = rest {
+                    let candidate = through + *rest;
+                    if best.is_none_or(|best| candidate < best) {
+                        *best = Some(candidate);
+                    }
+                }
+            }
+        }
+    }
+    // A vertex that's less than zero away from itself is on a negative cycle
+    let zero = G::Weight::default();
+    if (0..n).any(|vertex| distances[vertex][vertex].is_some_and(|d| d < zero)) {
+        return Err(NegativeCycle);
+    }
+    Ok(distances)
+}
+
+impl<W> ShortestPaths<W> {
+    fn new(vertices: usize, source: usize) -> Self
+    where
+        W: Default + Copy,
+    {
+        let mut distances = vec![None; vertices];
+        distances[source] = Some(W::default());
+        ShortestPaths {
+            source,
+            distances,
+            previous: vec![None; vertices],
+        }
+    }
+}
+
+/// Every edge, both ways round if the graph's undirected.
+fn directed_edges<G: Graph>(graph: &G) -> Vec<(usize, usize, G::Weight)> {
+    (0..graph.vertex_count())
+        .flat_map(|from| {
+            graph
+                .neighbours(from)
+                .map(move |(to, weight)| (from, to, weight))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{AdjacencyList, AdjacencyMatrix};
+    use proptest::prelude::*;
+
+    fn directed(vertices: usize, edges: &[(usize, usize, i64)]) -> AdjacencyList<i64> {
+        let mut graph = AdjacencyList::new_directed(vertices);
+        for (from, to, weight) in edges {
+            graph.add_edge(*from, *to, *weight);
+        }
+        graph
+    }
+
+    #[test]
+    fn finds_shortest_paths() {
+        let graph = directed(
+            6,
+            &[
+                (0, 1, 7),
+                (0, 2, 9),
+                (0, 5, 14),
+                (1, 2, 10),
+                (1, 3, 15),
+                (2, 3, 11),
+                (2, 5, 2),
+                (3, 4, 6),
+                (5, 4, 9),
+            ],
+        );
+        let expected = vec![Some(0), Some(7), Some(9), Some(20), Some(20), Some(11)];
+        let paths = dijkstra(&graph, 0);
+        assert_eq!(paths.distances(), &expected[..]);
+        assert_eq!(paths.path(4), Some(vec![0, 2, 5, 4]));
+        assert_eq!(bellman_ford(&graph, 0).unwrap().distances(), &expected[..]);
+        assert_eq!(floyd_warshall(&graph).unwrap()[0], expected);
+
+        let paths = dijkstra(&graph, 4);
+        assert_eq!(paths.distance(0), None);
+        assert_eq!(paths.path(0), None);
+        assert_eq!(paths.path(4), Some(vec![4]));
+    }
+
+    #[test]
+    fn handles_negative_weights() {
+        let graph = directed(4, &[(0, 1, 4), (0, 2, 5), (2, 1, -3), (1, 3, 2)]);
+        let paths = bellman_ford(&graph, 0).unwrap();
+        assert_eq!(paths.distance(3), Some(4));
+        assert_eq!(paths.path(3), Some(vec![0, 2, 1, 3]));
+        assert_eq!(floyd_warshall(&graph).unwrap()[0][3], Some(4));
+
+        let cyclic = directed(3, &[(0, 1, 1), (1, 2, -2), (2, 1, 1)]);
+        assert_eq!(bellman_ford(&cyclic, 0), Err(NegativeCycle));
+        assert_eq!(floyd_warshall(&cyclic), Err(NegativeCycle));
+        // Out of reach, so it doesn't matter
+        assert!(bellman_ford(&cyclic, 2).is_err());
+        assert!(bellman_ford(&directed(4, &[(1, 2, -2), (2, 1, 1)]), 0).is_ok());
+    }
+
+    #[test]
+    fn undirected_edges_go_both_ways() {
+        let mut graph = AdjacencyMatrix::new_undirected(3);
+        graph.add_edge(0, 1, 3u32);
+        graph.add_edge(1, 2, 4);
+        assert_eq!(
+            dijkstra(&graph, 2).distances(),
+            &[Some(7), Some(4), Some(0)]
+        );
+    }
+
+    fn graphs() -> impl Strategy<Value = AdjacencyList<i64>> {
+        (1..8usize).prop_flat_map(|n| {
+            prop::collection::vec((0..n, 0..n, 0..20i64), 0..20)
+                .prop_map(move |edges| directed(n, &edges))
+        })
+    }
+
+    fn lightest(graph: &AdjacencyList<i64>, from: usize, to: usize) -> i64 {
+        let edges = graph.neighbours(from).filter(|&(next, _)| next == to);
+        edges.map(|(_, weight)| weight).min().unwrap()
+    }
+
+    proptest! {
+        #[test]
+        fn all_three_agree(graph in graphs()) {
+            let all = floyd_warshall(&graph).unwrap();
+            for (source, expected) in all.iter().enumerate() {
+                let dijkstra = dijkstra(&graph, source);
+                let bellman_ford = bellman_ford(&graph, source).unwrap();
+                prop_assert_eq!(dijkstra.distances(), &expected[..]);
+                prop_assert_eq!(bellman_ford.distances(), &expected[..]);
+                // Every path adds up to its distance, though ties can pick
+                // different ones
+                for paths in &[dijkstra, bellman_ford] {
+                    for to in 0..graph.vertex_count() {
+                        if let Some(path) = paths.path(to) {
+                            let length: i64 = path.windows(2).map(|w| lightest(&graph, w[0], w[1])).sum();
+                            prop_assert_eq!(Some(length), paths.distance(to));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use super::{Graph, Weight};
+use crate::data_structures::DisjointSet;
+
+/**
+ * Kruskal: takes the edges lightest first, skipping any that would close a
+ * cycle, which a disjoint set tells in about O(1). Returns the edges of a
+ * minimum spanning forest (a tree for every connected part). O(E log E).
+ */
+pub fn kruskal<G>(graph: &G) -> Vec<(usize, usize, G::Weight)>
+where
+    G: Graph,
+    G::Weight: Weight,
+{
+    assert!(
+        !graph.is_directed(),
+        "spanning trees are for undirected graphs"
+    );
+    let mut edges = graph.edges();
+    edges.sort_by_key(|&(_, _, weight)| weight);
+    let mut trees = DisjointSet::new(graph.vertex_count());
+    edges
+        .into_iter()
+        .filter(|&(from, to, _)| trees.union(from, to))
+        .collect()
+}
+
+/**
+ * Prim: grows a tree from every vertex not yet in one, always by the
+ * lightest edge out of it, from a binary heap. Returns the edges of a
+ * minimum spanning forest. O(E log V).
+ */
+pub fn prim<G>(graph: &G) -> Vec<(usize, usize, G::Weight)>
+where
+    G: Graph,
+    G::Weight: Weight,
+{
+    assert!(
+        !graph.is_directed(),
+        "spanning trees are for undirected graphs"
+    );
+    let mut in_tree = vec![false; graph.vertex_count()];
+    let mut tree = Vec::new();
+    let mut heap = BinaryHeap::new();
+    for root in 0..graph.vertex_count() {
+        if in_tree[root] {
+            continue;
+        }
+        in_tree[root] = true;
+        heap.extend(
+            graph
+                .neighbours(root)
+                .map(|(to, weight)| Reverse((weight, root, to))),
+        );
+        while let Some(Reverse((weight, from, to))) = heap.pop() {
+            if in_tree[to] {
+                continue;
+            }
+            in_tree[to] = true;
+            tree.push((from, to, weight));
+            let edges = graph.neighbours(to).filter(|&(next, _)| !in_tree[next]);
+            heap.extend(edges.map(|(next, weight)| Reverse((weight, to, next))));
+        }
+    }
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{AdjacencyList, AdjacencyMatrix};
+    use proptest::prelude::*;
+
+    fn total(tree: &[(usize, usize, u32)]) -> u32 {
+        tree.iter().map(|&(_, _, weight)| weight).sum()
+    }
+
+    /// Whether `tree` joins up every vertex `graph` does, without cycles:
+    /// a forest of `V - C` edges, for `C` connected parts.
+    fn spans<G: Graph>(graph: &G, tree: &[(usize, usize, u32)]) -> bool {
+        let mut parts = DisjointSet::new(graph.vertex_count());
+        let joins = graph
+            .edges()
+            .into_iter()
+            .filter(|&(from, to, _)| parts.union(from, to));
+        let mut trees = DisjointSet::new(graph.vertex_count());
+        let acyclic = tree.iter().all(|&(from, to, _)| trees.union(from, to));
+        acyclic && tree.len() == joins.count()
+    }
+
+    #[test]
+    fn finds_minimum_spanning_tree() {
+        let mut graph = AdjacencyList::new_undirected(5);
+        for &(from, to, weight) in &[
+            (0, 1, 2),
+            (0, 3, 6),
+            (1, 2, 3),
+            (1, 3, 8),
+            (1, 4, 5),
+            (2, 4, 7),
+            (3, 4, 9),
+        ] {
+            graph.add_edge(from, to, weight);
+        }
+        let mut tree = kruskal(&graph);
+        tree.sort();
+        assert_eq!(tree, vec![(0, 1, 2), (0, 3, 6), (1, 2, 3), (1, 4, 5)]);
+        assert_eq!(total(&prim(&graph)), 16);
+        assert_eq!(total(&prim(&AdjacencyMatrix::from(&graph))), 16);
+    }
+
+    #[test]
+    fn spans_every_part_of_a_forest() {
+        let mut graph = AdjacencyList::new_undirected(5);
+        graph.add_edge(0, 1, 4u32);
+        graph.add_edge(3, 4, 1);
+        graph.add_edge(4, 3, 2);
+        assert_eq!(kruskal(&graph), vec![(3, 4, 1), (0, 1, 4)]);
+        assert_eq!(prim(&graph), vec![(0, 1, 4), (3, 4, 1)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "undirected")]
+    fn rejects_directed_graphs() {
+        kruskal(&AdjacencyList::<u32>::new_directed(2));
+    }
+
+    proptest! {
+        #[test]
+        fn kruskal_and_prim_agree(
+            graph in (1..10usize).prop_flat_map(|n| {
+                prop::collection::vec((0..n, 0..n, 0..20u32), 0..30).prop_map(move |edges| {
+                    let mut graph = AdjacencyList::new_undirected(n);
+                    for (from, to, weight) in edges {
+                        graph.add_edge(from, to, weight);
+                    }
+                    graph
+                })
+            })
+        ) {
+            let (kruskal, prim) = (kruskal(&graph), prim(&graph));
+            prop_assert_eq!(total(&kruskal), total(&prim));
+            prop_assert!(spans(&graph, &kruskal));
+            prop_assert!(spans(&graph, &prim));
+        }
+    }
+}
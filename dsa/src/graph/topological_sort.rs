@@ -0,0 +1,133 @@
+use super::Graph;
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    New,
+    /// On the current path, so reaching it again closes a cycle.
+    Active,
+    Done,
+}
+
+/**
+ * The vertices of a directed graph ordered so that every edge goes forward,
+ * or, if there's no such order, the vertices of a cycle (each with an edge
+ * to the next, and the last to the first).
+ */
+pub fn topological_sort<G: Graph>(graph: &G) -> Result<Vec<usize>, Vec<usize>> {
+    assert!(
+        graph.is_directed(),
+        "only directed graphs have a topological order"
+    );
+    let mut state = vec![State::New; graph.vertex_count()];
+    let mut finished = Vec::with_capacity(graph.vertex_count());
+
+    for root in 0..graph.vertex_count() {
+        if state[root] != State::New {
+            continue;
+        }
+        // The path from `root`, with the neighbours each vertex has left
+        let mut path = vec![(
+            root,
+            graph
+                .neighbours(root)
+                .map(|(next, _)| next)
+                .collect::<Vec<_>>(),
+        )];
+        state[root] = State::Active;
+        while let Some((vertex, neighbours)) = path.last_mut() {
+            let vertex = *vertex;
+            match neighbours.pop() {
+                Some(next) => match state[next] {
+                    State::New => {
+                        state[next] = State::Active;
+                        let neighbours = graph.neighbours(next).map(|(n, _)| n).collect();
+                        path.push((next, neighbours));
+                    }
+                    State::Active => {
+                        let start = path.iter().position(|(v, _)| *v == next).unwrap();
+                        return Err(path[start..].iter().map(|(v, _)| *v).collect());
+                    }
+                    State::Done => {}
+                },
+                None => {
+                    // Everything after `vertex` is finished already
+                    state[vertex] = State::Done;
+                    finished.push(vertex);
+                    path.pop();
+                }
+            }
+        }
+    }
+    finished.reverse();
+    Ok(finished)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{AdjacencyList, AdjacencyMatrix};
+
+    fn directed(vertices: usize, edges: &[(usize, usize)]) -> AdjacencyList {
+        let mut graph = AdjacencyList::new_directed(vertices);
+        for (from, to) in edges {
+            graph.add_edge(*from, *to, ());
+        }
+        graph
+    }
+
+    fn is_topological(graph: &AdjacencyList, order: &[usize]) -> bool {
+        let mut position = vec![0; order.len()];
+        for (i, vertex) in order.iter().enumerate() {
+            position[*vertex] = i;
+        }
+        order.len() == graph.vertex_count()
+            && graph
+                .edges()
+                .iter()
+                .all(|(from, to, _)| position[*from] < position[*to])
+    }
+
+    #[test]
+    fn orders_a_dag() {
+        let graph = directed(6, &[(5, 2), (5, 0), (4, 0), (4, 1), (2, 3), (3, 1)]);
+        let order = topological_sort(&graph).unwrap();
+        assert!(is_topological(&graph, &order), "{:?}", order);
+        let order = topological_sort(&AdjacencyMatrix::from(&graph)).unwrap();
+        assert!(is_topological(&graph, &order), "{:?}", order);
+        assert_eq!(topological_sort(&directed(0, &[])), Ok(vec![]));
+    }
+
+    #[test]
+    fn finds_cycles() {
+        let graph = directed(5, &[(0, 1), (1, 2), (2, 3), (3, 1), (3, 4)]);
+        let mut cycle = topological_sort(&graph).unwrap_err();
+        let start = cycle.iter().position(|v| *v == 1).unwrap();
+        cycle.rotate_left(start);
+        assert_eq!(cycle, vec![1, 2, 3]);
+        assert_eq!(topological_sort(&directed(1, &[(0, 0)])), Err(vec![0]));
+    }
+
+    #[test]
+    fn orders_every_small_dag() {
+        // Every DAG on 4 vertices whose edges go from lower to higher
+        // vertices, relabelled backwards so the order isn't the identity
+        let pairs: Vec<(usize, usize)> = (0..4)
+            .flat_map(|a| (a + 1..4).map(move |b| (3 - a, 3 - b)))
+            .collect();
+        for mask in 0..1 << pairs.len() {
+            let edges: Vec<_> = (0..pairs.len())
+                .filter(|i| mask & 1 << i != 0)
+                .map(|i| pairs[i])
+                .collect();
+            let graph = directed(4, &edges);
+            assert!(is_topological(&graph, &topological_sort(&graph).unwrap()));
+
+            // Any edge backwards closes a cycle
+            let mut cyclic = graph.clone();
+            if let Some((from, to)) = edges.first() {
+                cyclic.add_edge(*to, *from, ());
+                assert!(topological_sort(&cyclic).is_err(), "{:?}", edges);
+            }
+        }
+    }
+}
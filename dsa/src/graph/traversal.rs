@@ -0,0 +1,88 @@
+use std::collections::VecDeque;
+
+use super::Graph;
+
+/// Every vertex reachable from `start`, nearest first (breadth-first).
+pub fn bfs<G: Graph>(graph: &G, start: usize) -> Vec<usize> {
+    let mut order = Vec::new();
+    let mut seen = vec![false; graph.vertex_count()];
+    let mut queue = VecDeque::from(vec![start]);
+    seen[start] = true;
+    while let Some(vertex) = queue.pop_front() {
+        order.push(vertex);
+        for (next, _) in graph.neighbours(vertex) {
+            if !seen[next] {
+                seen[next] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+    order
+}
+
+/// How many edges away from `start` every vertex is, if it's reachable.
+pub fn bfs_distances<G: Graph>(graph: &G, start: usize) -> Vec<Option<usize>> {
+    let mut distances = vec![None; graph.vertex_count()];
+    distances[start] = Some(0);
+    for vertex in bfs(graph, start) {
+        let distance = distances[vertex].unwrap();
+        for (next, _) in graph.neighbours(vertex) {
+            if distances[next].is_none() {
+                distances[next] = Some(distance + 1);
+            }
+        }
+    }
+    distances
+}
+
+/// Every vertex reachable from `start`, each followed by the ones it leads
+/// to first (depth-first, pre-order). Neighbours are visited in order.
+pub fn dfs<G: Graph>(graph: &G, start: usize) -> Vec<usize> {
+    let mut order = Vec::new();
+    let mut seen = vec![false; graph.vertex_count()];
+    let mut stack = vec![start];
+    while let Some(vertex) = stack.pop() {
+        if seen[vertex] {
+            continue;
+        }
+        seen[vertex] = true;
+        order.push(vertex);
+        // Pushed in reverse, so the first neighbour is popped first
+        let neighbours: Vec<usize> = graph.neighbours(vertex).map(|(next, _)| next).collect();
+        stack.extend(neighbours.into_iter().rev().filter(|next| !seen[*next]));
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::AdjacencyList;
+
+    //   0 - 1 - 3
+    //   |   |
+    //   2 - 4   5
+    fn graph() -> AdjacencyList {
+        let mut graph = AdjacencyList::new_undirected(6);
+        for (from, to) in [(0, 1), (0, 2), (1, 3), (1, 4), (2, 4)].iter() {
+            graph.add_edge(*from, *to, ());
+        }
+        graph
+    }
+
+    #[test]
+    fn breadth_first() {
+        assert_eq!(bfs(&graph(), 0), vec![0, 1, 2, 3, 4]);
+        assert_eq!(bfs(&graph(), 5), vec![5]);
+        assert_eq!(
+            bfs_distances(&graph(), 3),
+            vec![Some(2), Some(1), Some(3), Some(0), Some(2), None]
+        );
+    }
+
+    #[test]
+    fn depth_first() {
+        assert_eq!(dfs(&graph(), 0), vec![0, 1, 3, 4, 2]);
+        assert_eq!(dfs(&graph(), 2), vec![2, 0, 1, 3, 4]);
+    }
+}
@@ -1 +1,5 @@
+pub mod data_structures;
+pub mod graph;
 pub mod searching;
+pub mod sorting;
+pub mod strings;
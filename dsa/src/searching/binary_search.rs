@@ -0,0 +1,311 @@
+use std::cmp::Ordering;
+use std::ops::Range;
+
+// Binary Search Family
+//
+// Everything here narrows a `low..high` range by half on every step, and
+// comes down to finding where a monotonic predicate turns from false to true.
+
+/// Index of the first element `>= target` in the sorted `arr`, or
+/// `arr.len()` if there's none.
+pub fn lower_bound<T: Ord>(arr: &[T], target: &T) -> usize {
+    first_true(0, arr.len(), |i| arr[i] >= *target)
+}
+
+/// Index of the first element `> target` in the sorted `arr`, or
+/// `arr.len()` if there's none.
+pub fn upper_bound<T: Ord>(arr: &[T], target: &T) -> usize {
+    first_true(0, arr.len(), |i| arr[i] > *target)
+}
+
+/// The indexes of every element equal to `target` in the sorted `arr`,
+/// empty (at where it would go) if there's none.
+pub fn equal_range<T: Ord>(arr: &[T], target: &T) -> Range<usize> {
+    lower_bound(arr, target)..upper_bound(arr, target)
+}
+
+/**
+ * Index of `target` in `arr`, a sorted array rotated at some unknown point
+ * (like `[4, 5, 6, 1, 2, 3]`). O(log n) without duplicates; duplicates can
+ * hide which half is sorted, down to O(n) when they're everywhere.
+ */
+pub fn search_rotated<T: Ord>(arr: &[T], target: &T) -> Option<usize> {
+    let (mut low, mut high) = (0, arr.len());
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let last = high - 1;
+        if arr[mid] == *target {
+            return Some(mid);
+        }
+        if arr[low] == arr[mid] && arr[mid] == arr[last] {
+            // Either half could be the sorted one: drop the ends, which
+            // aren't `target`
+            if arr[low] == *target {
+                return Some(low);
+            }
+            low += 1;
+            high -= 1;
+        } else if arr[low] <= arr[mid] {
+            // `arr[low..=mid]` is sorted
+            if arr[low] <= *target && *target < arr[mid] {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        } else if arr[mid] < *target && *target <= arr[last] {
+            // `arr[mid..high]` is sorted, and holds `target`
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    None
+}
+
+/// Index of `target` in the sorted `arr`, found by doubling a bound until it
+/// passes `target`, then searching below it. O(log i) for a `target` at
+/// `i`, so quicker than binary search near the start.
+pub fn exponential_search<T: Ord>(arr: &[T], target: &T) -> Option<usize> {
+    exponential_search_unbounded(|i| arr.get(i), &target)
+}
+
+/**
+ * Index of `target` in a sorted sequence of unknown length, like a stream,
+ * where `get(i)` is the element at `i`, or `None` past the end.
+ */
+pub fn exponential_search_unbounded<T, F>(mut get: F, target: &T) -> Option<usize>
+where
+    T: Ord,
+    F: FnMut(usize) -> Option<T>,
+{
+    // Double `bound` until it's past the end, or `get(bound) >= target`
+    let mut bound = 1;
+    loop {
+        match get(bound - 1) {
+            Some(value) if value < *target => bound *= 2,
+            _ => break,
+        }
+    }
+    // `target` can only be in `bound / 2..bound`, since `get(bound / 2 - 1)`
+    // was smaller
+    let low = bound / 2;
+    let i = first_true(low, bound, |i| get(i).is_none_or(|value| value >= *target));
+    match get(i) {
+        Some(value) if value == *target => Some(i),
+        _ => None,
+    }
+}
+
+/// Integers `first_true` can search between.
+pub trait Integer: Copy + Ord {
+    /// Halfway between `low` and `high`, rounded down, without overflowing.
+    fn midpoint(low: Self, high: Self) -> Self;
+    fn successor(self) -> Self;
+}
+
+macro_rules! integer {
+    ($($t:ty => $u:ty),*) => {$(
+        impl Integer for $t {
+            fn midpoint(low: Self, high: Self) -> Self {
+                // The distance fits in the unsigned type even when the
+                // difference doesn't fit in `$t`
+                let half = (high as $u).wrapping_sub(low as $u) / 2;
+                (low as $u).wrapping_add(half) as $t
+            }
+
+            fn successor(self) -> Self {
+                self + 1
+            }
+        }
+    )*};
+}
+
+integer!(
+    u8 => u8, u16 => u16, u32 => u32, u64 => u64, usize => usize,
+    i8 => u8, i16 => u16, i32 => u32, i64 => u64, isize => usize
+);
+
+/**
+ * The first `x` in `low..high` for which `predicate(x)` is true, or `high`
+ * if it's never true. `predicate` must be monotonic: false, then true from
+ * some point on.
+ */
+pub fn first_true<T, F>(mut low: T, mut high: T, mut predicate: F) -> T
+where
+    T: Integer,
+    F: FnMut(T) -> bool,
+{
+    while low < high {
+        let mid = T::midpoint(low, high);
+        if predicate(mid) {
+            high = mid;
+        } else {
+            low = mid.successor();
+        }
+    }
+    low
+}
+
+/**
+ * Where the monotonic `predicate` turns true between `low` and `high`,
+ * within `tolerance`: it's false just below the answer, and true from it
+ * on. `high` if it's never true.
+ */
+pub fn first_true_f64<F>(mut low: f64, mut high: f64, tolerance: f64, mut predicate: F) -> f64
+where
+    F: FnMut(f64) -> bool,
+{
+    while high - low > tolerance {
+        let mid = low + (high - low) / 2.0;
+        // No float left between them, so no closer answer either
+        if mid <= low || mid >= high {
+            break;
+        }
+        if predicate(mid) {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    high
+}
+
+/// Like `slice::binary_search_by`: the index of an element `compare`
+/// finds `Equal`, or where one would go.
+pub fn binary_search_by<T, F>(arr: &[T], mut compare: F) -> Result<usize, usize>
+where
+    F: FnMut(&T) -> Ordering,
+{
+    let i = first_true(0, arr.len(), |i| compare(&arr[i]) != Ordering::Less);
+    match arr.get(i).map(&mut compare) {
+        Some(Ordering::Equal) => Ok(i),
+        _ => Err(i),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every sorted array of up to `len` elements from `0..values`.
+    fn sorted_arrays(len: usize, values: i32) -> Vec<Vec<i32>> {
+        let mut arrays = vec![vec![]];
+        let mut last = vec![vec![]];
+        for _ in 0..len {
+            let mut next = Vec::new();
+            for arr in &last {
+                let start = arr.last().copied().unwrap_or(0);
+                for value in start..values {
+                    let mut longer = arr.clone();
+                    longer.push(value);
+                    next.push(longer);
+                }
+            }
+            arrays.extend(next.iter().cloned());
+            last = next;
+        }
+        arrays
+    }
+
+    #[test]
+    fn bounds_match_a_linear_scan() {
+        for arr in sorted_arrays(7, 4) {
+            for target in -1..=4 {
+                let lower = arr.iter().position(|x| *x >= target).unwrap_or(arr.len());
+                let upper = arr.iter().position(|x| *x > target).unwrap_or(arr.len());
+                assert_eq!(lower_bound(&arr, &target), lower, "{:?} {}", arr, target);
+                assert_eq!(upper_bound(&arr, &target), upper, "{:?} {}", arr, target);
+                assert_eq!(equal_range(&arr, &target), lower..upper);
+                assert_eq!(
+                    binary_search_by(&arr, |x| x.cmp(&target)).is_ok(),
+                    arr.contains(&target)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rotated_search_finds_every_element() {
+        for arr in sorted_arrays(7, 4) {
+            for shift in 0..arr.len().max(1) {
+                let mut rotated = arr.clone();
+                rotated.rotate_left(shift);
+                for target in -1..=4 {
+                    match search_rotated(&rotated, &target) {
+                        Some(i) => assert_eq!(rotated[i], target, "{:?}", rotated),
+                        None => assert!(!rotated.contains(&target), "{:?} {}", rotated, target),
+                    }
+                }
+            }
+        }
+        assert_eq!(search_rotated(&[4, 5, 6, 1, 2, 3], &2), Some(4));
+        assert_eq!(search_rotated(&[2, 2, 2, 3, 2], &3), Some(3));
+        assert_eq!(search_rotated(&[2, 3, 2, 2, 2], &3), Some(1));
+    }
+
+    #[test]
+    fn exponential_search_finds_every_element() {
+        for arr in sorted_arrays(7, 4) {
+            for target in -1..=4 {
+                match exponential_search(&arr, &target) {
+                    Some(i) => assert_eq!(arr[i], target),
+                    None => assert!(!arr.contains(&target), "{:?} {}", arr, target),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn exponential_search_over_a_stream() {
+        // Squares, as if read one at a time, with no end in sight
+        let mut reads = 0;
+        let squares = |i: usize| {
+            reads += 1;
+            Some(i * i)
+        };
+        assert_eq!(
+            exponential_search_unbounded(squares, &(1000 * 1000)),
+            Some(1000)
+        );
+        assert!(reads < 40, "{} reads", reads);
+        assert_eq!(exponential_search_unbounded(|i| Some(i * i), &50), None);
+        assert_eq!(exponential_search_unbounded(|_| None::<u8>, &0), None);
+    }
+
+    #[test]
+    fn first_true_over_every_threshold() {
+        for threshold in -10..=10 {
+            for low in -10..=10 {
+                for high in low..=10 {
+                    let expected = threshold.clamp(low, high);
+                    assert_eq!(first_true(low, high, |x: i32| x >= threshold), expected);
+                }
+            }
+        }
+        // The whole range of a type, without overflowing
+        for threshold in 0..=255u8 {
+            assert_eq!(first_true(0, 255, |x| x >= threshold), threshold);
+            let signed = i16::from(threshold) - 128;
+            assert_eq!(
+                first_true(i8::MIN, i8::MAX, |x| i16::from(x) >= signed),
+                signed.min(127) as i8
+            );
+        }
+        assert_eq!(first_true(0, u64::MAX, |x| x >= u64::MAX - 1), u64::MAX - 1);
+        assert_eq!(first_true(i64::MIN, i64::MAX, |x| x >= -5), -5);
+    }
+
+    #[test]
+    fn first_true_f64_finds_square_roots() {
+        for n in 0..100 {
+            let n = f64::from(n);
+            let root = first_true_f64(0.0, 10.0, 1e-9, |x| x * x >= n);
+            assert!((root - n.sqrt()).abs() <= 1e-9, "sqrt({}) = {}", n, root);
+        }
+        // As close as floats get
+        let root = first_true_f64(1.0, 2.0, 0.0, |x| x * x >= 2.0);
+        assert!(root * root >= 2.0);
+        assert!(f64::from_bits(root.to_bits() - 1).powi(2) < 2.0);
+        assert_eq!(first_true_f64(0.0, 1.0, 1e-6, |_| false), 1.0);
+    }
+}
@@ -1,4 +1,10 @@
+mod binary_search;
 mod peak_finder;
 
+pub use self::binary_search::{
+    binary_search_by, equal_range, exponential_search, exponential_search_unbounded, first_true,
+    first_true_f64, lower_bound, search_rotated, upper_bound, Integer,
+};
 pub use self::peak_finder::peak_finder_1d_divide_conquer;
 pub use self::peak_finder::peak_finder_1d_greedy;
+pub use self::peak_finder::peak_finder_2d;
@@ -1,58 +1,161 @@
 // 1-D Array Peak Finder
-pub fn peak_finder_1d_greedy(arr: &[usize]) -> Option<usize> {
-    for (i, value) in arr.iter().enumerate() {
-        if arr.len() == 1
-            || (i == 0 && *value >= arr[i + 1])
-            || (i > 0 && i < (arr.len() - 1) && (arr[i - 1] <= *value && *value >= arr[i + 1])
-                || (i == arr.len() - 1 && *value >= arr[i - 1]))
-        {
-            return Some(*value);
+//
+// A peak is an element that is no smaller than the elements next to it, and
+// every non-empty array has one. All finders return the index of the peak.
+pub fn peak_finder_1d_greedy<T: Ord>(arr: &[T]) -> Option<usize> {
+    (0..arr.len())
+        .find(|&i| (i == 0 || arr[i - 1] <= arr[i]) && (i == arr.len() - 1 || arr[i] >= arr[i + 1]))
+}
+
+pub fn peak_finder_1d_divide_conquer<T: Ord>(arr: &[T]) -> Option<usize> {
+    if arr.is_empty() {
+        return None;
+    }
+    // The peak is in `arr[low..high]`, which halves on every step
+    let (mut low, mut high) = (0, arr.len());
+    loop {
+        let mid = low + (high - low) / 2;
+        if mid > low && arr[mid] <= arr[mid - 1] {
+            high = mid;
+        } else if mid < high - 1 && arr[mid] <= arr[mid + 1] {
+            low = mid;
         } else {
-            continue;
+            return Some(mid);
         }
     }
-    None
 }
 
-pub fn peak_finder_1d_divide_conquer(arr: &[usize]) -> Option<usize> {
-    let mut _arr = vec![0; arr.len()];
-    _arr.clone_from_slice(arr);
-    let mid_index = arr.len() / 2;
-    if arr.is_empty() {
+// 2-D Matrix Peak Finder
+//
+// Returns the `(row, column)` of an element no smaller than the ones above,
+// below, left and right of it. Every row must be as long as the first.
+pub fn peak_finder_2d<T: Ord, R: AsRef<[T]>>(matrix: &[R]) -> Option<(usize, usize)> {
+    let columns = matrix.first()?.as_ref().len();
+    assert!(
+        matrix.iter().all(|row| row.as_ref().len() == columns),
+        "every row of the matrix must have the same length"
+    );
+    if columns == 0 {
         return None;
     }
-    if mid_index > 0 && _arr[mid_index] <= _arr[mid_index - 1] {
-        peak_finder_1d_divide_conquer(_arr.split_at(mid_index).0)
-    } else if mid_index < (_arr.len() - 1) && _arr[mid_index] <= _arr[mid_index + 1] {
-        peak_finder_1d_divide_conquer(_arr.split_at(mid_index).1)
-    } else {
-        Some(_arr[mid_index])
+    let at = |row: usize, column: usize| &matrix[row].as_ref()[column];
+
+    // The peak is in `columns[low..high]`, which halves on every step
+    let (mut low, mut high) = (0, columns);
+    loop {
+        let mid = low + (high - low) / 2;
+        let row = (0..matrix.len())
+            .rev()
+            .max_by(|&a, &b| at(a, mid).cmp(at(b, mid)))
+            .unwrap();
+        if mid > low && at(row, mid - 1) > at(row, mid) {
+            high = mid;
+        } else if mid < high - 1 && at(row, mid + 1) > at(row, mid) {
+            low = mid + 1;
+        } else {
+            return Some((row, mid));
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    fn is_peak_1d<T: Ord>(arr: &[T], i: usize) -> bool {
+        (i == 0 || arr[i - 1] <= arr[i]) && (i + 1 == arr.len() || arr[i] >= arr[i + 1])
+    }
+
+    fn is_peak_2d<T: Ord>(matrix: &[Vec<T>], (row, column): (usize, usize)) -> bool {
+        let value = &matrix[row][column];
+        let neighbours = [
+            row.checked_sub(1).map(|r| &matrix[r][column]),
+            matrix.get(row + 1).map(|r| &r[column]),
+            column.checked_sub(1).map(|c| &matrix[row][c]),
+            matrix[row].get(column + 1),
+        ];
+        neighbours.iter().flatten().all(|n| *n <= value)
+    }
 
     #[test]
     fn find_peak_using_loop() {
-        let mut peak = peak_finder_1d_greedy(&[]);
+        let mut peak = peak_finder_1d_greedy::<usize>(&[]);
         assert_eq!(peak, None);
         peak = peak_finder_1d_greedy(&[6, 7, 5, 4, 3, 2, 1, 4, 5]);
-        assert_eq!(peak, Some(7));
+        assert_eq!(peak, Some(1));
         peak = peak_finder_1d_greedy(&[6, 7, 8]);
-        assert_eq!(peak, Some(8));
+        assert_eq!(peak, Some(2));
     }
 
     #[test]
     fn find_peak_using_divide_conquer() {
-        let mut peak = peak_finder_1d_divide_conquer(&[]);
+        let mut peak = peak_finder_1d_divide_conquer::<usize>(&[]);
         assert_eq!(peak, None);
         peak = peak_finder_1d_divide_conquer(&[6, 7, 5, 4, 3, 2, 1, 4, 5]);
-        assert_eq!(peak, Some(7));
+        assert_eq!(peak, Some(1));
         peak = peak_finder_1d_divide_conquer(&[6, 7, 8]);
-        assert_eq!(peak, Some(8));
+        assert_eq!(peak, Some(2));
         peak = peak_finder_1d_divide_conquer(&[9, 7, 8]);
-        assert_eq!(peak, Some(9));
+        assert_eq!(peak, Some(0));
+    }
+
+    #[test]
+    fn find_peak_of_any_ord_type() {
+        let words = ["apple", "pear", "fig", "kiwi", "plum"];
+        assert_eq!(peak_finder_1d_greedy(&words), Some(1));
+        assert_eq!(peak_finder_1d_divide_conquer(&words), Some(1));
+        let chars: Vec<char> = "abcba".chars().collect();
+        assert_eq!(peak_finder_1d_divide_conquer(&chars), Some(2));
+    }
+
+    #[test]
+    fn find_peak_in_matrix() {
+        let empty: [Vec<i32>; 0] = [];
+        assert_eq!(peak_finder_2d(&empty), None);
+        assert_eq!(peak_finder_2d(&[Vec::<i32>::new()]), None);
+        assert_eq!(peak_finder_2d(&[[5]]), Some((0, 0)));
+
+        let matrix = [
+            [10, 8, 10, 10],
+            [14, 13, 12, 11],
+            [15, 9, 11, 21],
+            [16, 17, 19, 20],
+        ];
+        assert_eq!(peak_finder_2d(&matrix), Some((2, 3)));
+    }
+
+    #[test]
+    #[should_panic(expected = "same length")]
+    fn reject_ragged_matrix() {
+        peak_finder_2d(&[vec![1, 2], vec![3]]);
+    }
+
+    proptest! {
+        #[test]
+        fn greedy_finds_a_peak(arr in prop::collection::vec(0..10u8, 0..50)) {
+            match peak_finder_1d_greedy(&arr) {
+                Some(i) => prop_assert!(is_peak_1d(&arr, i)),
+                None => prop_assert!(arr.is_empty()),
+            }
+        }
+
+        #[test]
+        fn divide_conquer_finds_a_peak(arr in prop::collection::vec(0..10u8, 0..50)) {
+            match peak_finder_1d_divide_conquer(&arr) {
+                Some(i) => prop_assert!(is_peak_1d(&arr, i)),
+                None => prop_assert!(arr.is_empty()),
+            }
+        }
+
+        #[test]
+        fn finds_a_peak_in_matrix(
+            matrix in (1..12usize, 1..12usize).prop_flat_map(|(rows, columns)| {
+                prop::collection::vec(prop::collection::vec(0..10i32, columns), rows)
+            })
+        ) {
+            let peak = peak_finder_2d(&matrix).unwrap();
+            prop_assert!(is_peak_2d(&matrix, peak), "{:?} in {:?}", peak, matrix);
+        }
     }
 }
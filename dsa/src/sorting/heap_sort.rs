@@ -0,0 +1,44 @@
+use std::cmp::Ordering;
+
+// Heap Sort
+//
+// In place and not stable, O(n log n) whatever the input.
+pub fn heap_sort<T: Ord>(arr: &mut [T]) {
+    heap_sort_by(arr, T::cmp);
+}
+
+pub fn heap_sort_by<T, F>(arr: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // Build a max-heap, bottom up
+    for i in (0..arr.len() / 2).rev() {
+        sift_down(arr, i, &mut compare);
+    }
+    // Move the largest to the end, and restore the heap in front of it
+    for end in (1..arr.len()).rev() {
+        arr.swap(0, end);
+        sift_down(&mut arr[..end], 0, &mut compare);
+    }
+}
+
+fn sift_down<T, F>(heap: &mut [T], mut i: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    loop {
+        let (left, right) = (2 * i + 1, 2 * i + 2);
+        let mut largest = i;
+        if left < heap.len() && compare(&heap[left], &heap[largest]) == Ordering::Greater {
+            largest = left;
+        }
+        if right < heap.len() && compare(&heap[right], &heap[largest]) == Ordering::Greater {
+            largest = right;
+        }
+        if largest == i {
+            return;
+        }
+        heap.swap(i, largest);
+        i = largest;
+    }
+}
@@ -0,0 +1,30 @@
+use std::cmp::Ordering;
+
+// Insertion Sort
+//
+// Stable, in place, and quick on short or nearly sorted slices.
+pub fn insertion_sort<T: Ord>(arr: &mut [T]) {
+    insertion_sort_by(arr, T::cmp);
+}
+
+pub fn insertion_sort_by<T, F>(arr: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    insert_tail(arr, 1, &mut compare);
+}
+
+/// Sorts `arr`, given that `arr[..sorted]` is sorted already.
+pub(crate) fn insert_tail<T, F>(arr: &mut [T], sorted: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    for i in sorted.max(1)..arr.len() {
+        // Only move past greater elements, so equal ones keep their order
+        let mut j = i;
+        while j > 0 && compare(&arr[j - 1], &arr[j]) == Ordering::Greater {
+            arr.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+}
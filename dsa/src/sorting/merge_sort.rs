@@ -0,0 +1,64 @@
+use std::cmp::Ordering;
+
+// Merge Sort
+//
+// Stable, top-down. Uses a buffer as long as half the slice.
+pub fn merge_sort<T: Ord + Clone>(arr: &mut [T]) {
+    merge_sort_by(arr, T::cmp);
+}
+
+pub fn merge_sort_by<T, F>(arr: &mut [T], mut compare: F)
+where
+    T: Clone,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut buffer = Vec::with_capacity(arr.len() / 2);
+    sort(arr, &mut buffer, &mut compare);
+}
+
+fn sort<T, F>(arr: &mut [T], buffer: &mut Vec<T>, compare: &mut F)
+where
+    T: Clone,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    if arr.len() <= 1 {
+        return;
+    }
+    let mid = arr.len() / 2;
+    sort(&mut arr[..mid], buffer, compare);
+    sort(&mut arr[mid..], buffer, compare);
+    merge(arr, mid, buffer, compare);
+}
+
+/// Merges the sorted runs `arr[..mid]` and `arr[mid..]`, copying the left
+/// one into `buffer` to make room.
+pub(crate) fn merge<T, F>(arr: &mut [T], mid: usize, buffer: &mut Vec<T>, compare: &mut F)
+where
+    T: Clone,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // Already in order, nothing to move
+    if mid == 0 || mid == arr.len() || compare(&arr[mid - 1], &arr[mid]) != Ordering::Greater {
+        return;
+    }
+    buffer.clear();
+    buffer.extend_from_slice(&arr[..mid]);
+
+    let (mut left, mut right, mut out) = (0, mid, 0);
+    while left < buffer.len() && right < arr.len() {
+        // Ties go to the left run, which keeps the sort stable
+        if compare(&arr[right], &buffer[left]) == Ordering::Less {
+            arr[out] = arr[right].clone();
+            right += 1;
+        } else {
+            arr[out] = buffer[left].clone();
+            left += 1;
+        }
+        out += 1;
+    }
+    // Whatever's left of the right run is in place already
+    for value in buffer.drain(left..) {
+        arr[out] = value;
+        out += 1;
+    }
+}
@@ -0,0 +1,142 @@
+mod heap_sort;
+mod insertion_sort;
+mod merge_sort;
+mod quick_sort;
+mod radix_sort;
+mod tim_sort;
+
+pub use self::heap_sort::{heap_sort, heap_sort_by};
+pub use self::insertion_sort::{insertion_sort, insertion_sort_by};
+pub use self::merge_sort::{merge_sort, merge_sort_by};
+pub use self::quick_sort::{quick_sort, quick_sort_by};
+pub use self::radix_sort::{radix_sort, radix_sort_by_key, RadixKey};
+pub use self::tim_sort::{tim_sort, tim_sort_by};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::cmp::Ordering;
+
+    type Sort = fn(&mut [i32]);
+    type SortBy<T> = fn(&mut [T], fn(&T, &T) -> Ordering);
+
+    const SORTS: [(&str, Sort); 6] = [
+        ("insertion", insertion_sort),
+        ("merge", merge_sort),
+        ("quick", quick_sort),
+        ("heap", heap_sort),
+        ("radix", radix_sort),
+        ("tim", tim_sort),
+    ];
+
+    const STABLE_SORTS: [(&str, SortBy<(u8, usize)>); 3] = [
+        ("insertion", insertion_sort_by),
+        ("merge", merge_sort_by),
+        ("tim", tim_sort_by),
+    ];
+
+    fn by_key(a: &(u8, usize), b: &(u8, usize)) -> Ordering {
+        a.0.cmp(&b.0)
+    }
+
+    fn check(arr: &[i32]) {
+        let mut expected = arr.to_vec();
+        expected.sort();
+        for (name, sort) in SORTS.iter() {
+            let mut actual = arr.to_vec();
+            sort(&mut actual);
+            assert_eq!(actual, expected, "{} sort of {:?}", name, arr);
+        }
+    }
+
+    #[test]
+    fn sort_small_inputs() {
+        check(&[]);
+        check(&[1]);
+        check(&[2, 1]);
+        check(&[3, 1, 2]);
+        check(&[5, -5, 0, i32::MAX, i32::MIN, 0, -1]);
+    }
+
+    #[test]
+    fn sort_shaped_inputs() {
+        let n = 1000;
+        let sorted: Vec<i32> = (0..n).collect();
+        let reversed: Vec<i32> = (0..n).rev().collect();
+        let sawtooth: Vec<i32> = (0..n).map(|i| i % 37).collect();
+        let organ_pipe: Vec<i32> = (0..n).map(|i| i.min(n - i)).collect();
+        let same = vec![7; n as usize];
+        for arr in [sorted, reversed, sawtooth, organ_pipe, same].iter() {
+            check(arr);
+        }
+    }
+
+    #[test]
+    fn sort_by_comparator() {
+        let descending = |a: &i32, b: &i32| b.cmp(a);
+        let expected = vec![9, 5, 4, 3, 1, 1];
+        let sorts: [SortBy<i32>; 5] = [
+            insertion_sort_by,
+            merge_sort_by,
+            quick_sort_by,
+            heap_sort_by,
+            tim_sort_by,
+        ];
+        for sort in sorts.iter() {
+            let mut arr = vec![3, 1, 4, 1, 5, 9];
+            sort(&mut arr, descending);
+            assert_eq!(arr, expected);
+        }
+
+        let mut words = vec!["pear", "fig", "banana", "kiwi"];
+        radix_sort_by_key(&mut words, 1, |w| w.len() as u64);
+        assert_eq!(words, vec!["fig", "pear", "kiwi", "banana"]);
+    }
+
+    #[test]
+    fn radix_sort_every_integer_type() {
+        let mut bytes: Vec<u8> = vec![200, 3, 255, 0, 17];
+        radix_sort(&mut bytes);
+        assert_eq!(bytes, vec![0, 3, 17, 200, 255]);
+        let mut small: Vec<i8> = vec![-128, 127, -1, 0, 1];
+        radix_sort(&mut small);
+        assert_eq!(small, vec![-128, -1, 0, 1, 127]);
+        let mut large: Vec<i64> = vec![i64::MAX, -3, i64::MIN, 1 << 40, 0];
+        radix_sort(&mut large);
+        assert_eq!(large, vec![i64::MIN, -3, 0, 1 << 40, i64::MAX]);
+        let mut sizes: Vec<usize> = vec![usize::MAX, 1 << 20, 5];
+        radix_sort(&mut sizes);
+        assert_eq!(sizes, vec![5, 1 << 20, usize::MAX]);
+    }
+
+    proptest! {
+        #[test]
+        fn sorts_like_slice_sort(arr in prop::collection::vec(any::<i32>(), 0..300)) {
+            check(&arr);
+        }
+
+        #[test]
+        fn sorts_duplicates_like_slice_sort(arr in prop::collection::vec(-5..5i32, 0..300)) {
+            check(&arr);
+        }
+
+        #[test]
+        fn stable_sorts_keep_equal_elements_in_order(
+            keys in prop::collection::vec(0..8u8, 0..300)
+        ) {
+            // Every element remembers where it started
+            let arr: Vec<(u8, usize)> = keys.into_iter().zip(0..).collect();
+            let mut expected = arr.clone();
+            expected.sort_by(by_key);
+            for (name, sort) in STABLE_SORTS.iter() {
+                let mut actual = arr.clone();
+                sort(&mut actual, by_key);
+                prop_assert_eq!(&actual, &expected, "{} sort", name);
+            }
+            let mut actual = arr.clone();
+            radix_sort_by_key(&mut actual, 1, |pair| u64::from(pair.0));
+            prop_assert_eq!(&actual, &expected, "radix sort");
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::cmp::Ordering;
+
+// Quick Sort (3-way partition)
+//
+// In place and not stable. Elements equal to the pivot are gathered in the
+// middle and never looked at again, so many duplicates make it faster.
+pub fn quick_sort<T: Ord>(arr: &mut [T]) {
+    quick_sort_by(arr, T::cmp);
+}
+
+pub fn quick_sort_by<T, F>(arr: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    sort(arr, &mut compare);
+}
+
+fn sort<T, F>(mut arr: &mut [T], compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    while arr.len() > 1 {
+        let (lt, gt) = partition(arr, compare);
+        // Recurse into the smaller side and loop on the larger one, so the
+        // stack never grows past O(log n)
+        let (left, rest) = std::mem::take(&mut arr).split_at_mut(lt);
+        let right = &mut rest[gt - lt..];
+        if left.len() < right.len() {
+            sort(left, compare);
+            arr = right;
+        } else {
+            sort(right, compare);
+            arr = left;
+        }
+    }
+}
+
+/// Rearranges `arr` into `[< pivot][== pivot][> pivot]`, and returns where
+/// the middle part starts and ends.
+fn partition<T, F>(arr: &mut [T], compare: &mut F) -> (usize, usize)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let pivot = median_of_three(arr, compare);
+    arr.swap(0, pivot);
+
+    // arr[1..lt] < pivot, arr[lt..i] == pivot, arr[gt..] > pivot, where
+    // the pivot itself stays at 0 until the end
+    let (mut lt, mut i, mut gt) = (1, 1, arr.len());
+    while i < gt {
+        match compare(&arr[i], &arr[0]) {
+            Ordering::Less => {
+                arr.swap(lt, i);
+                lt += 1;
+                i += 1;
+            }
+            Ordering::Greater => {
+                gt -= 1;
+                arr.swap(i, gt);
+            }
+            Ordering::Equal => i += 1,
+        }
+    }
+    arr.swap(0, lt - 1);
+    (lt - 1, gt)
+}
+
+/// The index of the median of the first, middle and last elements, which
+/// keeps sorted and reversed input from taking O(n^2).
+fn median_of_three<T, F>(arr: &[T], compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let (a, b, c) = (0, arr.len() / 2, arr.len() - 1);
+    let less = |x: usize, y: usize, compare: &mut F| compare(&arr[x], &arr[y]) == Ordering::Less;
+    if less(a, b, compare) {
+        if less(b, c, compare) {
+            b
+        } else if less(a, c, compare) {
+            c
+        } else {
+            a
+        }
+    } else if less(a, c, compare) {
+        a
+    } else if less(b, c, compare) {
+        c
+    } else {
+        b
+    }
+}
@@ -0,0 +1,87 @@
+// Radix Sort (LSD)
+//
+// Stable, and doesn't compare elements at all: they're bucketed by one byte
+// of their key at a time, least significant first. O(w * (n + 256)) for
+// keys of `w` bytes.
+
+/// Integers radix sort can order: `radix_key` maps them to `u64`, keeping
+/// their order.
+pub trait RadixKey {
+    /// How many bytes of `radix_key` are used.
+    const BYTES: usize;
+
+    fn radix_key(&self) -> u64;
+}
+
+macro_rules! radix_key_unsigned {
+    ($($t:ty),*) => {$(
+        impl RadixKey for $t {
+            const BYTES: usize = std::mem::size_of::<$t>();
+
+            fn radix_key(&self) -> u64 {
+                *self as u64
+            }
+        }
+    )*};
+}
+
+macro_rules! radix_key_signed {
+    ($($t:ty => $u:ty),*) => {$(
+        impl RadixKey for $t {
+            const BYTES: usize = std::mem::size_of::<$t>();
+
+            // Flipping the sign bit puts negative numbers before positive ones
+            fn radix_key(&self) -> u64 {
+                (*self as $u ^ (1 << (<$u>::BITS - 1))) as u64
+            }
+        }
+    )*};
+}
+
+radix_key_unsigned!(u8, u16, u32, u64, usize);
+radix_key_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, isize => usize);
+
+pub fn radix_sort<T: RadixKey + Clone>(arr: &mut [T]) {
+    radix_sort_by_key(arr, T::BYTES, T::radix_key);
+}
+
+/// Sorts `arr` by the lowest `bytes` bytes of `key`.
+pub fn radix_sort_by_key<T, F>(arr: &mut [T], bytes: usize, key: F)
+where
+    T: Clone,
+    F: Fn(&T) -> u64,
+{
+    if arr.len() <= 1 {
+        return;
+    }
+    let keys: Vec<u64> = arr.iter().map(&key).collect();
+    // Where every element goes, which moves instead of the elements
+    let mut order: Vec<usize> = (0..arr.len()).collect();
+    let mut next = vec![0; arr.len()];
+
+    for byte in 0..bytes.min(8) {
+        let digit = |i: usize| ((keys[i] >> (8 * byte)) & 0xff) as usize;
+        let mut counts = [0usize; 256];
+        for &i in &order {
+            counts[digit(i)] += 1;
+        }
+        // Skip bytes that are the same everywhere, like the high bytes of
+        // small numbers
+        if counts.contains(&arr.len()) {
+            continue;
+        }
+        let mut starts = [0usize; 256];
+        for d in 1..256 {
+            starts[d] = starts[d - 1] + counts[d - 1];
+        }
+        for &i in &order {
+            let d = digit(i);
+            next[starts[d]] = i;
+            starts[d] += 1;
+        }
+        std::mem::swap(&mut order, &mut next);
+    }
+
+    let sorted: Vec<T> = order.iter().map(|&i| arr[i].clone()).collect();
+    arr.clone_from_slice(&sorted);
+}
@@ -0,0 +1,113 @@
+use std::cmp::Ordering;
+
+use super::insertion_sort::insert_tail;
+use super::merge_sort::merge;
+
+// Tim Sort (simplified)
+//
+// Stable. Finds the runs already in the input, makes short ones at least
+// `min_run` long with insertion sort, and merges them in an order that keeps
+// the merges balanced. Sorted and reversed input takes O(n).
+pub fn tim_sort<T: Ord + Clone>(arr: &mut [T]) {
+    tim_sort_by(arr, T::cmp);
+}
+
+pub fn tim_sort_by<T, F>(arr: &mut [T], mut compare: F)
+where
+    T: Clone,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let min_run = min_run(arr.len());
+    let mut buffer = Vec::new();
+    // (start, length) of the runs waiting to be merged, left to right
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+
+    let mut start = 0;
+    while start < arr.len() {
+        let run = &mut arr[start..];
+        let mut length = natural_run(run, &mut compare);
+        if length < min_run {
+            let end = min_run.min(run.len());
+            insert_tail(&mut run[..end], length, &mut compare);
+            length = end;
+        }
+        runs.push((start, length));
+        start += length;
+        collapse(arr, &mut runs, &mut buffer, &mut compare, false);
+    }
+    collapse(arr, &mut runs, &mut buffer, &mut compare, true);
+}
+
+/// Between 32 and 64, such that `n / min_run` is a power of two or just
+/// under one, so the last merges are between runs of about the same size.
+fn min_run(mut n: usize) -> usize {
+    let mut remainder = 0;
+    while n >= 64 {
+        remainder |= n & 1;
+        n >>= 1;
+    }
+    n + remainder
+}
+
+/// The length of the run at the start of `arr`, reversed if it was
+/// strictly descending. Strictly, so that reversing keeps the sort stable.
+fn natural_run<T, F>(arr: &mut [T], compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    if arr.len() < 2 {
+        return arr.len();
+    }
+    let mut end = 2;
+    if compare(&arr[1], &arr[0]) == Ordering::Less {
+        while end < arr.len() && compare(&arr[end], &arr[end - 1]) == Ordering::Less {
+            end += 1;
+        }
+        arr[..end].reverse();
+    } else {
+        while end < arr.len() && compare(&arr[end], &arr[end - 1]) != Ordering::Less {
+            end += 1;
+        }
+    }
+    end
+}
+
+/**
+ * Merges runs until, for the lengths `a`, `b` and `c` of the top three,
+ * `a > b + c` and `b > c`, which keeps the stack O(log n) deep. With
+ * `all`, merges everything left.
+ */
+fn collapse<T, F>(
+    arr: &mut [T],
+    runs: &mut Vec<(usize, usize)>,
+    buffer: &mut Vec<T>,
+    compare: &mut F,
+    all: bool,
+) where
+    T: Clone,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    while runs.len() > 1 {
+        let n = runs.len();
+        let c = runs[n - 1].1;
+        let b = runs[n - 2].1;
+        let a = if n > 2 { runs[n - 3].1 } else { usize::MAX };
+        // Merge `b` with the smaller of its neighbours
+        let at = if n > 2 && a <= b + c {
+            if a < c {
+                n - 3
+            } else {
+                n - 2
+            }
+        } else if all || b <= c {
+            n - 2
+        } else {
+            return;
+        };
+        let (start, left) = runs[at];
+        let right = runs[at + 1].1;
+        merge(&mut arr[start..start + left + right], left, buffer, compare);
+        runs[at].1 = left + right;
+        runs.remove(at + 1);
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::VecDeque;
+
+use super::{Match, Searcher};
+
+/// The root of the trie, the state before anything's matched.
+const ROOT: usize = 0;
+
+/**
+ * Aho-Corasick: a trie of the patterns turned into an automaton that reads
+ * the text once, whatever the number of patterns. Every state is the
+ * longest pattern prefix the text read so far ends with, and moves on any
+ * byte without backing up. O(m * 256) to build for `m` pattern bytes in
+ * all, O(n + matches) to search.
+ */
+#[derive(Debug, Clone)]
+pub struct AhoCorasick {
+    /// The state after every byte from every state.
+    next: Vec<[usize; 256]>,
+    /// The patterns that end at every state, as their index and length.
+    outputs: Vec<Vec<(usize, usize)>>,
+    /// The nearest state down the failure links with outputs, if any: the
+    /// longest shorter pattern that ends here too.
+    output_link: Vec<Option<usize>>,
+}
+
+impl AhoCorasick {
+    pub fn new<P: AsRef<[u8]>>(patterns: &[P]) -> Self {
+        let mut automaton = AhoCorasick {
+            next: vec![[ROOT; 256]],
+            outputs: vec![Vec::new()],
+            output_link: vec![None],
+        };
+        // The trie, with `ROOT` standing for "no edge" (nothing leads back
+        // to the root in a trie)
+        for (index, pattern) in patterns.iter().enumerate() {
+            let pattern = pattern.as_ref();
+            assert!(!pattern.is_empty(), "the patterns can't be empty");
+            let mut state = ROOT;
+            for &byte in pattern {
+                if automaton.next[state][usize::from(byte)] == ROOT {
+                    automaton.next.push([ROOT; 256]);
+                    automaton.outputs.push(Vec::new());
+                    automaton.output_link.push(None);
+                    automaton.next[state][usize::from(byte)] = automaton.next.len() - 1;
+                }
+                state = automaton.next[state][usize::from(byte)];
+            }
+            automaton.outputs[state].push((index, pattern.len()));
+        }
+
+        // Breadth-first, so every failure link (the longest proper suffix
+        // that's a trie state) is at a shallower, finished state. A missing
+        // edge goes where the failure link's does.
+        let mut failure = vec![ROOT; automaton.next.len()];
+        let mut queue: VecDeque<usize> = automaton.next[ROOT]
+            .iter()
+            .copied()
+            .filter(|&s| s != ROOT)
+            .collect();
+        while let Some(state) = queue.pop_front() {
+            let fallback = failure[state];
+            automaton.output_link[state] = if automaton.outputs[fallback].is_empty() {
+                automaton.output_link[fallback]
+            } else {
+                Some(fallback)
+            };
+            for byte in 0..256 {
+                let child = automaton.next[state][byte];
+                if child == ROOT {
+                    automaton.next[state][byte] = automaton.next[fallback][byte];
+                } else {
+                    failure[child] = automaton.next[fallback][byte];
+                    queue.push_back(child);
+                }
+            }
+        }
+        automaton
+    }
+}
+
+impl Searcher for AhoCorasick {
+    fn find_iter<'a>(&'a self, text: &'a [u8]) -> impl Iterator<Item = Match> + 'a {
+        let mut state = ROOT;
+        text.iter().enumerate().flat_map(move |(i, &byte)| {
+            state = self.next[state][usize::from(byte)];
+            // This state's patterns, then ever shorter ones down the links
+            let states = std::iter::successors(Some(state), move |&s| self.output_link[s]);
+            states.flat_map(move |s| {
+                self.outputs[s].iter().map(move |&(pattern, len)| Match {
+                    pattern,
+                    start: i + 1 - len,
+                    end: i + 1,
+                })
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_every_pattern() {
+        let automaton = AhoCorasick::new(&["he", "she", "his", "hers"]);
+        let matches: Vec<_> = automaton
+            .find_iter(b"ushers")
+            .map(|m| (m.pattern, m.start))
+            .collect();
+        assert_eq!(matches, vec![(1, 1), (0, 2), (3, 2)]);
+        assert_eq!(automaton.find(b"ahis").map(|m| m.pattern), Some(2));
+        assert_eq!(automaton.find(b"nothing"), None);
+    }
+
+    #[test]
+    fn reports_duplicate_patterns_once_each() {
+        let automaton = AhoCorasick::new(&[&b"ab"[..], b"ab", b"b"]);
+        let matches: Vec<_> = automaton.find_iter(b"ab").map(|m| m.pattern).collect();
+        assert_eq!(matches, vec![0, 1, 2]);
+    }
+}
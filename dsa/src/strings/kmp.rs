@@ -0,0 +1,89 @@
+use super::{Match, Searcher};
+
+/**
+ * The prefix function of `s`: for every `i`, the length of the longest
+ * proper prefix of `s[..=i]` that's also a suffix of it. O(n).
+ */
+pub fn prefix_function(s: &[u8]) -> Vec<usize> {
+    let mut prefix = vec![0; s.len()];
+    for i in 1..s.len() {
+        // Fall back through the shorter borders until one extends
+        let mut len = prefix[i - 1];
+        while len > 0 && s[i] != s[len] {
+            len = prefix[len - 1];
+        }
+        if s[i] == s[len] {
+            len += 1;
+        }
+        prefix[i] = len;
+    }
+    prefix
+}
+
+/**
+ * Knuth-Morris-Pratt: walks the text once, and on a mismatch falls back to
+ * the longest border of what's matched so far, instead of starting again.
+ * O(m) to build, O(n) to search.
+ */
+#[derive(Debug, Clone)]
+pub struct Kmp {
+    pattern: Vec<u8>,
+    prefix: Vec<usize>,
+}
+
+impl Kmp {
+    pub fn new(pattern: &[u8]) -> Self {
+        assert!(!pattern.is_empty(), "the pattern can't be empty");
+        Kmp {
+            pattern: pattern.to_vec(),
+            prefix: prefix_function(pattern),
+        }
+    }
+}
+
+impl Searcher for Kmp {
+    fn find_iter<'a>(&'a self, text: &'a [u8]) -> impl Iterator<Item = Match> + 'a {
+        let mut matched = 0;
+        text.iter().enumerate().filter_map(move |(i, &byte)| {
+            while matched > 0 && (matched == self.pattern.len() || byte != self.pattern[matched]) {
+                matched = self.prefix[matched - 1];
+            }
+            if byte == self.pattern[matched] {
+                matched += 1;
+            }
+            (matched == self.pattern.len()).then(|| Match {
+                pattern: 0,
+                start: i + 1 - matched,
+                end: i + 1,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_prefix_function() {
+        assert_eq!(prefix_function(b""), vec![]);
+        assert_eq!(prefix_function(b"aabaaab"), vec![0, 1, 0, 1, 2, 2, 3]);
+        assert_eq!(prefix_function(b"abcabcd"), vec![0, 0, 0, 1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn finds_overlapping_matches() {
+        let kmp = Kmp::new(b"aba");
+        let starts: Vec<_> = kmp.find_iter(b"abababa").map(|m| m.start).collect();
+        assert_eq!(starts, vec![0, 2, 4]);
+        assert_eq!(
+            kmp.find(b"xxabax"),
+            Some(Match {
+                pattern: 0,
+                start: 2,
+                end: 5
+            })
+        );
+        assert_eq!(kmp.find(b"ab"), None);
+    }
+}
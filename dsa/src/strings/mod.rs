@@ -0,0 +1,87 @@
+mod aho_corasick;
+mod kmp;
+mod rabin_karp;
+mod suffix_array;
+mod z_function;
+
+pub use self::aho_corasick::AhoCorasick;
+pub use self::kmp::{prefix_function, Kmp};
+pub use self::rabin_karp::RabinKarp;
+pub use self::suffix_array::{lcp_array, suffix_array, SuffixArray};
+pub use self::z_function::{z_function, ZSearcher};
+
+/// Where a pattern was found: the bytes `start..end` of the text, matching
+/// the `pattern`th pattern (always `0` for searchers with only one).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Match {
+    pub pattern: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Searches texts for the patterns it was built with.
+pub trait Searcher {
+    /**
+     * Every match in `text`, overlapping ones included, as they end: the
+     * longest first for matches that end together.
+     */
+    fn find_iter<'a>(&'a self, text: &'a [u8]) -> impl Iterator<Item = Match> + 'a;
+
+    /// The match that ends first in `text`.
+    fn find(&self, text: &[u8]) -> Option<Match> {
+        self.find_iter(text).next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Checks every pattern against every position, longest first.
+    fn naive(patterns: &[Vec<u8>], text: &[u8]) -> Vec<Match> {
+        let mut matches = Vec::new();
+        for end in 0..=text.len() {
+            let mut ending: Vec<Match> = patterns
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| p.len() <= end && text[end - p.len()..end] == p[..])
+                .map(|(pattern, p)| Match {
+                    pattern,
+                    start: end - p.len(),
+                    end,
+                })
+                .collect();
+            ending.sort_by_key(|m| (m.start, m.pattern));
+            matches.extend(ending);
+        }
+        matches
+    }
+
+    /// Short words over a small alphabet, so they turn up often.
+    fn word(max: usize) -> impl Strategy<Value = Vec<u8>> {
+        prop::collection::vec(prop::sample::select(b"abc".to_vec()), 1..max)
+    }
+
+    proptest! {
+        #[test]
+        fn single_pattern_searchers_match_naive(pattern in word(5), text in prop::collection::vec(prop::sample::select(b"abc".to_vec()), 0..40)) {
+            let expected = naive(std::slice::from_ref(&pattern), &text);
+            let kmp = Kmp::new(&pattern);
+            prop_assert_eq!(kmp.find_iter(&text).collect::<Vec<_>>(), expected.clone());
+            let z = ZSearcher::new(&pattern);
+            prop_assert_eq!(z.find_iter(&text).collect::<Vec<_>>(), expected.clone());
+            let rabin_karp = RabinKarp::new(&pattern);
+            prop_assert_eq!(rabin_karp.find_iter(&text).collect::<Vec<_>>(), expected.clone());
+            let suffixes = SuffixArray::new(&text);
+            prop_assert_eq!(suffixes.find_iter(&pattern).collect::<Vec<_>>(), expected.clone());
+            prop_assert_eq!(kmp.find(&text), expected.first().copied());
+        }
+
+        #[test]
+        fn aho_corasick_matches_naive(patterns in prop::collection::vec(word(5), 1..6), text in prop::collection::vec(prop::sample::select(b"abc".to_vec()), 0..40)) {
+            let automaton = AhoCorasick::new(&patterns);
+            prop_assert_eq!(automaton.find_iter(&text).collect::<Vec<_>>(), naive(&patterns, &text));
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use super::{Match, Searcher};
+
+/// Hashes are taken modulo this Mersenne prime, `2^61 - 1`, which makes
+/// collisions rare and the remainder cheap.
+const MODULUS: u64 = (1 << 61) - 1;
+const BASE: u64 = 1_000_003;
+
+fn mul_mod(a: u64, b: u64) -> u64 {
+    let product = u128::from(a) * u128::from(b);
+    // `2^61 = 1` modulo `2^61 - 1`, so the high bits fold onto the low ones
+    let folded = (product & u128::from(MODULUS)) + (product >> 61);
+    (folded as u64) % MODULUS
+}
+
+fn add_mod(a: u64, b: u64) -> u64 {
+    (a + b) % MODULUS
+}
+
+/// The polynomial hash of `bytes`, the first byte the highest power.
+fn hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |hash, &byte| {
+        add_mod(mul_mod(hash, BASE), u64::from(byte))
+    })
+}
+
+/**
+ * Rabin-Karp: slides a window the pattern's length along the text, rolling
+ * its hash along in O(1) a byte, and only compares the bytes when the hash
+ * matches the pattern's. O(n + m) expected.
+ */
+#[derive(Debug, Clone)]
+pub struct RabinKarp {
+    pattern: Vec<u8>,
+    hash: u64,
+    /// `BASE^(m - 1)`, the weight of the byte leaving the window.
+    leading: u64,
+}
+
+impl RabinKarp {
+    pub fn new(pattern: &[u8]) -> Self {
+        assert!(!pattern.is_empty(), "the pattern can't be empty");
+        let leading = (1..pattern.len()).fold(1, |power, _| mul_mod(power, BASE));
+        RabinKarp {
+            pattern: pattern.to_vec(),
+            hash: hash(pattern),
+            leading,
+        }
+    }
+}
+
+impl Searcher for RabinKarp {
+    fn find_iter<'a>(&'a self, text: &'a [u8]) -> impl Iterator<Item = Match> + 'a {
+        let len = self.pattern.len();
+        let mut window = hash(&text[..len.min(text.len())]);
+        (len..=text.len()).filter_map(move |end| {
+            let start = end - len;
+            if start > 0 {
+                // Drop `text[start - 1]`, take on `text[end - 1]`
+                let leaving = mul_mod(u64::from(text[start - 1]), self.leading);
+                let kept = add_mod(window, MODULUS - leaving);
+                window = add_mod(mul_mod(kept, BASE), u64::from(text[end - 1]));
+            }
+            (window == self.hash && text[start..end] == self.pattern[..]).then_some(Match {
+                pattern: 0,
+                start,
+                end,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolls_the_hash() {
+        let text = b"the quick brown fox";
+        let rabin_karp = RabinKarp::new(b"own");
+        assert_eq!(
+            rabin_karp.find(text),
+            Some(Match {
+                pattern: 0,
+                start: 12,
+                end: 15
+            })
+        );
+        assert_eq!(RabinKarp::new(b"the quick brown fox!").find(text), None);
+        let starts: Vec<_> = RabinKarp::new(b"xx")
+            .find_iter(b"xxxax")
+            .map(|m| m.start)
+            .collect();
+        assert_eq!(starts, vec![0, 1]);
+    }
+
+    #[test]
+    fn reduces_modulo_the_prime() {
+        assert_eq!(mul_mod(MODULUS - 1, MODULUS - 1), 1);
+        assert_eq!(mul_mod(1 << 60, 4), 2);
+        assert_eq!(add_mod(MODULUS - 1, 1), 0);
+    }
+}
@@ -0,0 +1,175 @@
+use std::ops::Range;
+
+use super::Match;
+use crate::searching::first_true;
+
+/**
+ * The suffixes of `text`, as their starting offsets, in lexicographic order.
+ * Prefix doubling: with the suffixes ranked by their first `k` bytes, ranks
+ * by the first `2k` are pairs of those, which a stable counting sort by the
+ * second and then the first puts in order. O(n log n).
+ */
+pub fn suffix_array(text: &[u8]) -> Vec<usize> {
+    let n = text.len();
+    let mut suffixes: Vec<usize> = (0..n).collect();
+    suffixes.sort_by_key(|&i| text[i]);
+    let mut rank: Vec<usize> = text.iter().map(|&byte| usize::from(byte)).collect();
+    let mut ranks = 256;
+    let mut k = 1;
+    while k < n {
+        // By the second half: the suffixes too short to have one first,
+        // then in the order of the suffix `k` on
+        let mut by_second: Vec<usize> = (n - k..n).collect();
+        by_second.extend(suffixes.iter().filter(|&&i| i >= k).map(|&i| i - k));
+
+        // Stable counting sort by the first half
+        let mut starts = vec![0; ranks + 1];
+        for &i in &by_second {
+            starts[rank[i] + 1] += 1;
+        }
+        for r in 1..starts.len() {
+            starts[r] += starts[r - 1];
+        }
+        for i in by_second {
+            suffixes[starts[rank[i]]] = i;
+            starts[rank[i]] += 1;
+        }
+
+        // Rank again by both halves, the same rank for the same pair
+        let key = |i: usize| (rank[i], rank.get(i + k).copied());
+        let mut next = vec![0; n];
+        for pair in suffixes.windows(2) {
+            next[pair[1]] = next[pair[0]] + usize::from(key(pair[0]) != key(pair[1]));
+        }
+        ranks = next[suffixes[n - 1]] + 1;
+        rank = next;
+        if ranks == n {
+            break;
+        }
+        k *= 2;
+    }
+    suffixes
+}
+
+/**
+ * Kasai's LCP array: `lcp[i]` is how long a prefix the suffixes
+ * `suffixes[i - 1]` and `suffixes[i]` share, with `lcp[0] = 0`. Going
+ * through the suffixes from longest to shortest, it drops by at most one
+ * each time, so O(n).
+ */
+pub fn lcp_array(text: &[u8], suffixes: &[usize]) -> Vec<usize> {
+    let n = text.len();
+    let mut position = vec![0; n];
+    for (i, &suffix) in suffixes.iter().enumerate() {
+        position[suffix] = i;
+    }
+    let mut lcp = vec![0; n];
+    let mut len: usize = 0;
+    for suffix in 0..n {
+        if position[suffix] == 0 {
+            len = 0;
+            continue;
+        }
+        let previous = suffixes[position[suffix] - 1];
+        while suffix + len < n && previous + len < n && text[suffix + len] == text[previous + len] {
+            len += 1;
+        }
+        lcp[position[suffix]] = len;
+        len = len.saturating_sub(1);
+    }
+    lcp
+}
+
+/// An index of a text, to find any pattern in it in O(m log n).
+#[derive(Debug, Clone)]
+pub struct SuffixArray {
+    text: Vec<u8>,
+    suffixes: Vec<usize>,
+    lcp: Vec<usize>,
+}
+
+impl SuffixArray {
+    pub fn new(text: &[u8]) -> Self {
+        let suffixes = suffix_array(text);
+        let lcp = lcp_array(text, &suffixes);
+        SuffixArray {
+            text: text.to_vec(),
+            suffixes,
+            lcp,
+        }
+    }
+
+    pub fn suffixes(&self) -> &[usize] {
+        &self.suffixes
+    }
+
+    pub fn lcp(&self) -> &[usize] {
+        &self.lcp
+    }
+
+    /// The longest substring that's in the text more than once, which is
+    /// the longest prefix two neighbouring suffixes share.
+    pub fn longest_repeated(&self) -> &[u8] {
+        match (0..self.lcp.len()).max_by_key(|&i| self.lcp[i]) {
+            Some(i) => &self.text[self.suffixes[i]..self.suffixes[i] + self.lcp[i]],
+            None => &[],
+        }
+    }
+
+    /// Every match of `pattern` in the text, in the order they're in the
+    /// text, like a `Searcher`'s.
+    pub fn find_iter(&self, pattern: &[u8]) -> impl Iterator<Item = Match> {
+        assert!(!pattern.is_empty(), "the pattern can't be empty");
+        let mut starts = self.suffixes[self.range(pattern)].to_vec();
+        starts.sort_unstable();
+        let len = pattern.len();
+        starts.into_iter().map(move |start| Match {
+            pattern: 0,
+            start,
+            end: start + len,
+        })
+    }
+
+    /// The suffixes that start with `pattern`, which are all together.
+    fn range(&self, pattern: &[u8]) -> Range<usize> {
+        let suffix = |i: usize| &self.text[self.suffixes[i]..];
+        let low = first_true(0, self.suffixes.len(), |i| suffix(i) >= pattern);
+        let high = first_true(low, self.suffixes.len(), |i| {
+            !suffix(i).starts_with(pattern)
+        });
+        low..high
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn sorts_suffixes() {
+        assert_eq!(suffix_array(b""), vec![]);
+        assert_eq!(suffix_array(b"banana"), vec![5, 3, 1, 0, 4, 2]);
+        assert_eq!(
+            lcp_array(b"banana", &[5, 3, 1, 0, 4, 2]),
+            vec![0, 1, 3, 0, 0, 2]
+        );
+        assert_eq!(SuffixArray::new(b"banana").longest_repeated(), b"ana");
+        assert_eq!(SuffixArray::new(b"abc").longest_repeated(), b"");
+    }
+
+    proptest! {
+        #[test]
+        fn matches_brute_force(text in prop::collection::vec(prop::sample::select(b"ab\xff".to_vec()), 0..60)) {
+            let mut expected: Vec<usize> = (0..text.len()).collect();
+            expected.sort_by_key(|&i| &text[i..]);
+            let index = SuffixArray::new(&text);
+            prop_assert_eq!(index.suffixes(), &expected[..]);
+            for i in 1..expected.len() {
+                let (a, b) = (&text[expected[i - 1]..], &text[expected[i]..]);
+                let shared = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+                prop_assert_eq!(index.lcp()[i], shared);
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use super::{Match, Searcher};
+
+/**
+ * The Z-function of `s`: for every `i`, the length of the longest common
+ * prefix of `s` and `s[i..]`, with `z[0] = 0`. Reuses the rightmost match
+ * found so far to start every `i` part of the way, so it's O(n).
+ */
+pub fn z_function(s: &[u8]) -> Vec<usize> {
+    let mut z = vec![0; s.len()];
+    // `s[left..right]` matches `s[..right - left]`, with `right` the furthest
+    let (mut left, mut right) = (0, 0);
+    for i in 1..s.len() {
+        if i < right {
+            z[i] = z[i - left].min(right - i);
+        }
+        while i + z[i] < s.len() && s[z[i]] == s[i + z[i]] {
+            z[i] += 1;
+        }
+        if i + z[i] > right {
+            left = i;
+            right = i + z[i];
+        }
+    }
+    z
+}
+
+/**
+ * Searches with the Z-function of the pattern followed by the text: the
+ * pattern is at every text offset whose value is at least its length. O(m +
+ * n), all at once rather than as it goes.
+ */
+#[derive(Debug, Clone)]
+pub struct ZSearcher {
+    pattern: Vec<u8>,
+}
+
+impl ZSearcher {
+    pub fn new(pattern: &[u8]) -> Self {
+        assert!(!pattern.is_empty(), "the pattern can't be empty");
+        ZSearcher {
+            pattern: pattern.to_vec(),
+        }
+    }
+}
+
+impl Searcher for ZSearcher {
+    fn find_iter<'a>(&'a self, text: &'a [u8]) -> impl Iterator<Item = Match> + 'a {
+        let len = self.pattern.len();
+        let joined = [&self.pattern[..], text].concat();
+        let z = z_function(&joined);
+        (len..joined.len())
+            .filter(move |&i| z[i] >= len)
+            .map(move |i| Match {
+                pattern: 0,
+                start: i - len,
+                end: i,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_z_function() {
+        assert_eq!(z_function(b""), vec![]);
+        assert_eq!(z_function(b"aaaaa"), vec![0, 4, 3, 2, 1]);
+        assert_eq!(z_function(b"aaabaab"), vec![0, 2, 1, 0, 2, 1, 0]);
+    }
+
+    #[test]
+    fn finds_overlapping_matches() {
+        let searcher = ZSearcher::new(b"aa");
+        let starts: Vec<_> = searcher.find_iter(b"aaab").map(|m| m.start).collect();
+        assert_eq!(starts, vec![0, 1]);
+        assert_eq!(searcher.find(b"abab"), None);
+    }
+}
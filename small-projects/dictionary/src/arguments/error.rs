@@ -1,4 +1,4 @@
-use std::result;
+use std::{fmt, result};
 
 pub type Result<T> = result::Result<T, Error>;
 
@@ -17,23 +17,78 @@ impl Error {
   }
 }
 
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.kind() {
+      ErrorKind::TooMany(count) => write!(f, "{} ({} given)", self.kind.as_str(), count),
+      ErrorKind::UnknownCacheCommand(action) | ErrorKind::UnknownConfigCommand(action)
+        if !action.is_empty() =>
+      {
+        write!(f, "{}, not `{}`", self.kind.as_str(), action)
+      }
+      ErrorKind::UnknownSubcommand(name) | ErrorKind::UnknownFlag(name) => {
+        write!(f, "{} `{}`", self.kind.as_str(), name)
+      }
+      ErrorKind::UnknownFormat(name) => write!(
+        f,
+        "{} `{}`, expected json, csv or markdown",
+        self.kind.as_str(),
+        name
+      ),
+      ErrorKind::MissingValue(name) => write!(f, "`{}` {}", name, self.kind.as_str()),
+      ErrorKind::MissingFlag { command, flag } => {
+        write!(f, "`{}` {} `{}`", command, self.kind.as_str(), flag)
+      }
+      ErrorKind::InvalidValue { flag, value } => write!(
+        f,
+        "{} `{}` for `{}`, expected a number above 0",
+        self.kind.as_str(),
+        value,
+        flag
+      ),
+      _ => write!(f, "{}", self.kind.as_str()),
+    }
+  }
+}
+
 /**
  * Breakdown #1:
  *
  * - `enums` fields are by default public, if the enum is public
- *    unlike `structs`.
+ *   unlike `structs`.
  */
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ErrorKind {
-  NoArgs,
   TooMany(usize),
+  UnknownCacheCommand(String),
+  UnknownConfigCommand(String),
+  UnknownSubcommand(String),
+  UnknownFlag(String),
+  UnknownFormat(String),
+  /// A command without its word, or a flag without its value.
+  MissingValue(String),
+  MissingFlag {
+    command: String,
+    flag: String,
+  },
+  InvalidValue {
+    flag: String,
+    value: String,
+  },
 }
 
 impl ErrorKind {
   pub fn as_str(&self) -> &str {
     match *self {
-      ErrorKind::NoArgs => "no arguments passed",
-      ErrorKind::TooMany(count) => "too many arguments",
+      ErrorKind::TooMany(_) => "too many arguments",
+      ErrorKind::UnknownCacheCommand(_) => "expected `cache stats` or `cache clear`",
+      ErrorKind::UnknownConfigCommand(_) => "expected `config show`",
+      ErrorKind::UnknownSubcommand(_) => "unknown command",
+      ErrorKind::UnknownFlag(_) => "unknown flag",
+      ErrorKind::UnknownFormat(_) => "unknown format",
+      ErrorKind::MissingValue(_) => "needs a value",
+      ErrorKind::MissingFlag { .. } => "needs",
+      ErrorKind::InvalidValue { .. } => "invalid value",
     }
   }
 }
@@ -1,40 +1,550 @@
-use std::env;
-
 mod error;
 
 pub use error::{Error, ErrorKind};
 
+/// How many lookups `batch` runs at once, unless `--jobs` says.
+const DEFAULT_JOBS: usize = 4;
+
+pub const USAGE: &str = "\
+usage: dictionary [--lang <code>] [--limit <n>] [--offline]
+                  [--provider oxford|local] [--config <file>] [<command>]
+
+Without a command, words are read and looked up one after another.
+
+commands:
+  define <word>       definitions, with pronunciations and examples
+  synonyms <word>     words with a similar meaning
+  antonyms <word>     words with the opposite meaning
+  examples <word>     the word used in sentences
+  etymology <word>    where the word comes from
+  batch [<file>] [--format json|csv|markdown] [--jobs <n>]
+                      look up every word in a file (or standard input, if
+                      it's missing or `-`), and write them as a glossary
+  review              quiz yourself on the words looked up so far
+  export --anki [<file>]
+                      write the words looked up as an Anki deck
+  cache stats         how much is cached, and how much of it is stale
+  cache clear         forget every cached lookup
+  config show         the settings in effect, and where they come from
+
+Settings come from the config file (~/.config/dictionary/config.toml, or
+--config), then the environment, then the flags above.";
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+  /// No command: look words up as they're typed in.
+  Repl,
+  /// Look a word up, and show `view` of what was found.
+  Lookup { view: View, word: String },
+  /// `batch [<file>]`: look up every word in `file`, or standard input,
+  /// `jobs` at a time, and write them out as `format`.
+  Batch {
+    file: Option<String>,
+    format: Format,
+    jobs: usize,
+  },
+  /// `review`: flashcards of the words looked up so far.
+  Review,
+  /// `export --anki [<file>]`: the words looked up so far as an Anki deck,
+  /// written to `file` or standard output.
+  Export { file: Option<String> },
+  /// `cache stats`: how much is cached.
+  CacheStats,
+  /// `cache clear`: forget everything cached.
+  CacheClear,
+  /// `config show`: the settings in effect.
+  ConfigShow,
+}
+
+/// What a lookup shows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum View {
+  Define,
+  Synonyms,
+  Antonyms,
+  Examples,
+  Etymology,
+}
+
+impl View {
+  pub fn as_str(&self) -> &str {
+    match *self {
+      View::Define => "definitions",
+      View::Synonyms => "synonyms",
+      View::Antonyms => "antonyms",
+      View::Examples => "examples",
+      View::Etymology => "etymology",
+    }
+  }
+}
+
+/// How `batch` writes what it found.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+  Json,
+  Csv,
+  Markdown,
+}
+
+impl Format {
+  fn from_str(name: &str) -> Option<Self> {
+    match name {
+      "json" => Some(Format::Json),
+      "csv" => Some(Format::Csv),
+      "markdown" | "md" => Some(Format::Markdown),
+      _ => None,
+    }
+  }
+}
+
 #[derive(Debug)]
 pub struct Arguments {
-  query: String,
+  command: Command,
+  /// `--lang`: the language to look words up in, instead of `DICTIONARY_LANG`.
+  lang: Option<String>,
+  /// `--limit`: how many senses, synonyms, etc. to show at most.
+  limit: Option<usize>,
+  /// `--offline`: answer from the cache alone.
+  offline: bool,
+  /// `--provider`: where to look words up, instead of `PROVIDER`.
+  provider: Option<String>,
+  /// `--config`: the config file to read, instead of the default one.
+  config: Option<String>,
 }
 
 impl Arguments {
-  pub fn new(mut args: env::Args) -> error::Result<Self> {
-    let arg = Arguments::parse(&mut args)?;
-    Ok(arg)
+  /// Parses the command line, program name first (as in `env::args()`).
+  pub fn new(args: impl IntoIterator<Item = String>) -> error::Result<Self> {
+    let mut args = args.into_iter();
+    args.next(); // Remove first option, as it's just the filename
+    Arguments::parse(args)
+  }
+
+  pub fn command(&self) -> &Command {
+    &self.command
+  }
+
+  pub fn lang(&self) -> Option<&str> {
+    self.lang.as_deref()
+  }
+
+  pub fn limit(&self) -> Option<usize> {
+    self.limit
+  }
+
+  pub fn offline(&self) -> bool {
+    self.offline
+  }
+
+  pub fn provider(&self) -> Option<&str> {
+    self.provider.as_deref()
+  }
+
+  pub fn config(&self) -> Option<&str> {
+    self.config.as_deref()
   }
 
-  fn parse(args: &mut env::Args) -> error::Result<Arguments> {
-    match Arguments::validate_length(args) {
-      Ok(()) => {
-        let query = args.next().unwrap(); // Second Argument, i.e., querystring
-        Ok(Arguments { query })
+  /**
+   * Breakdown #3:
+   *
+   * - Flags may come before or after the command, and take their value
+   *   either as the next argument (`--lang fr`) or after `=` (`--lang=fr`).
+   * - Everything else is positional: the command, then its arguments.
+   */
+  fn parse(mut args: impl Iterator<Item = String>) -> error::Result<Arguments> {
+    let mut positional = Vec::new();
+    let mut lang = None;
+    let mut limit = None;
+    let mut offline = false;
+    let mut anki = false;
+    let mut format = None;
+    let mut jobs = None;
+    let mut provider = None;
+    let mut config = None;
+
+    while let Some(arg) = args.next() {
+      if !arg.starts_with("--") {
+        positional.push(arg);
+        continue;
+      }
+      let (flag, inline) = match arg.find('=') {
+        Some(i) => (arg[..i].to_string(), Some(arg[i + 1..].to_string())),
+        None => (arg, None),
+      };
+      match flag.as_str() {
+        "--offline" if inline.is_none() => offline = true,
+        "--anki" if inline.is_none() => anki = true,
+        "--lang" => lang = Some(Arguments::value(&flag, inline, &mut args)?),
+        "--provider" => provider = Some(Arguments::value(&flag, inline, &mut args)?),
+        "--config" => config = Some(Arguments::value(&flag, inline, &mut args)?),
+        "--limit" => {
+          let value = Arguments::value(&flag, inline, &mut args)?;
+          limit = Some(Arguments::count(flag, value)?);
+        }
+        "--jobs" => {
+          let value = Arguments::value(&flag, inline, &mut args)?;
+          jobs = Some(Arguments::count(flag, value)?);
+        }
+        "--format" => {
+          let value = Arguments::value(&flag, inline, &mut args)?;
+          match Format::from_str(&value) {
+            Some(f) => format = Some(f),
+            None => return Err(Error::new(ErrorKind::UnknownFormat(value))),
+          }
+        }
+        _ => return Err(Error::new(ErrorKind::UnknownFlag(flag))),
       }
-      Err(e) => Err(e),
     }
+
+    let mut command = Arguments::command_from(&positional)?;
+    match &mut command {
+      Command::Export { .. } if !anki => {
+        return Err(Error::new(ErrorKind::MissingFlag {
+          command: "export".to_string(),
+          flag: "--anki".to_string(),
+        }))
+      }
+      Command::Export { .. } => anki = false,
+      Command::Batch {
+        format: f, jobs: j, ..
+      } => {
+        *f = format.take().unwrap_or(Format::Markdown);
+        *j = jobs.take().unwrap_or(DEFAULT_JOBS);
+      }
+      _ => {}
+    }
+    // Whatever's left over is meant for some other command
+    let unused = [
+      ("--anki", anki),
+      ("--format", format.is_some()),
+      ("--jobs", jobs.is_some()),
+    ];
+    if let Some((flag, _)) = unused.iter().find(|(_, given)| *given) {
+      return Err(Error::new(ErrorKind::UnknownFlag(flag.to_string())));
+    }
+    Ok(Arguments {
+      command,
+      lang,
+      limit,
+      offline,
+      provider,
+      config,
+    })
   }
 
-  fn validate_length(args: &mut env::Args) -> error::Result<()> {
-    if args.len() == 1 {
-      return Err(Error::new(ErrorKind::NoArgs));
+  /// The value of `flag`, given inline or as the next argument.
+  fn value(
+    flag: &str,
+    inline: Option<String>,
+    args: &mut impl Iterator<Item = String>,
+  ) -> error::Result<String> {
+    match inline.or_else(|| args.next()) {
+      Some(value) if !value.is_empty() && !value.starts_with("--") => Ok(value),
+      _ => Err(Error::new(ErrorKind::MissingValue(flag.to_string()))),
     }
+  }
 
-    if args.len() < 3 {
-      args.next(); // Remove first option, as it's just the filename
-      Ok(())
+  /// `value` as a number above 0, for `flag`.
+  fn count(flag: String, value: String) -> error::Result<usize> {
+    match value.parse() {
+      Ok(n) if n > 0 => Ok(n),
+      _ => Err(Error::new(ErrorKind::InvalidValue { flag, value })),
+    }
+  }
+
+  fn command_from(positional: &[String]) -> error::Result<Command> {
+    let name = match positional.first() {
+      Some(name) => name.as_str(),
+      None => return Ok(Command::Repl),
+    };
+    let view = match name {
+      "define" => View::Define,
+      "synonyms" => View::Synonyms,
+      "antonyms" => View::Antonyms,
+      "examples" => View::Examples,
+      "etymology" => View::Etymology,
+      "batch" => {
+        Arguments::validate_length(positional, 2)?;
+        let file = positional.get(1).filter(|file| *file != "-").cloned();
+        return Ok(Command::Batch {
+          file,
+          format: Format::Markdown,
+          jobs: DEFAULT_JOBS,
+        });
+      }
+      "review" => {
+        Arguments::validate_length(positional, 1)?;
+        return Ok(Command::Review);
+      }
+      "export" => {
+        Arguments::validate_length(positional, 2)?;
+        let file = positional.get(1).cloned();
+        return Ok(Command::Export { file });
+      }
+      "cache" => {
+        let command = match positional.get(1).map(String::as_str) {
+          Some("stats") => Command::CacheStats,
+          Some("clear") => Command::CacheClear,
+          action => {
+            let action = action.unwrap_or_default().to_string();
+            return Err(Error::new(ErrorKind::UnknownCacheCommand(action)));
+          }
+        };
+        Arguments::validate_length(positional, 2)?;
+        return Ok(command);
+      }
+      "config" => {
+        if positional.get(1).map(String::as_str) != Some("show") {
+          let action = positional.get(1).cloned().unwrap_or_default();
+          return Err(Error::new(ErrorKind::UnknownConfigCommand(action)));
+        }
+        Arguments::validate_length(positional, 2)?;
+        return Ok(Command::ConfigShow);
+      }
+      _ => return Err(Error::new(ErrorKind::UnknownSubcommand(name.to_string()))),
+    };
+    let word = match positional.get(1) {
+      Some(word) => word.to_string(),
+      None => return Err(Error::new(ErrorKind::MissingValue(name.to_string()))),
+    };
+    Arguments::validate_length(positional, 2)?;
+    Ok(Command::Lookup { view, word })
+  }
+
+  fn validate_length(positional: &[String], expected: usize) -> error::Result<()> {
+    if positional.len() > expected {
+      Err(Error::new(ErrorKind::TooMany(positional.len())))
     } else {
-      Err(Error::new(ErrorKind::TooMany(args.len())))
+      Ok(())
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(args: &[&str]) -> error::Result<Arguments> {
+    let args = std::iter::once("dictionary").chain(args.iter().copied());
+    Arguments::new(args.map(String::from))
+  }
+
+  fn kind(args: &[&str]) -> ErrorKind {
+    parse(args).unwrap_err().kind().clone()
+  }
+
+  fn lookup(view: View, word: &str) -> Command {
+    Command::Lookup {
+      view,
+      word: word.to_string(),
+    }
+  }
+
+  #[test]
+  fn parses_lookups() {
+    let args = parse(&["define", "ace"]).unwrap();
+    assert_eq!(args.command(), &lookup(View::Define, "ace"));
+    assert_eq!(args.lang(), None);
+    assert_eq!(args.limit(), None);
+    assert!(!args.offline());
+
+    for (name, view) in [
+      ("synonyms", View::Synonyms),
+      ("antonyms", View::Antonyms),
+      ("examples", View::Examples),
+      ("etymology", View::Etymology),
+    ]
+    .iter()
+    {
+      let args = parse(&[name, "ice cream"]).unwrap();
+      assert_eq!(args.command(), &lookup(*view, "ice cream"));
+    }
+  }
+
+  #[test]
+  fn starts_a_repl_without_a_command() {
+    assert_eq!(parse(&[]).unwrap().command(), &Command::Repl);
+    let args = parse(&["--lang", "fr", "--offline"]).unwrap();
+    assert_eq!(args.command(), &Command::Repl);
+    assert_eq!(args.lang(), Some("fr"));
+  }
+
+  #[test]
+  fn parses_flags_anywhere() {
+    let args = parse(&["--offline", "synonyms", "--lang", "fr", "ace", "--limit=3"]).unwrap();
+    assert_eq!(args.command(), &lookup(View::Synonyms, "ace"));
+    assert_eq!(args.lang(), Some("fr"));
+    assert_eq!(args.limit(), Some(3));
+    assert!(args.offline());
+
+    let args = parse(&["define", "ace", "--lang=es", "--limit", "10"]).unwrap();
+    assert_eq!(args.lang(), Some("es"));
+    assert_eq!(args.limit(), Some(10));
+  }
+
+  #[test]
+  fn parses_cache_commands() {
+    assert_eq!(
+      parse(&["cache", "stats"]).unwrap().command(),
+      &Command::CacheStats
+    );
+    assert_eq!(
+      parse(&["cache", "clear", "--offline"]).unwrap().command(),
+      &Command::CacheClear
+    );
+    assert_eq!(
+      kind(&["cache"]),
+      ErrorKind::UnknownCacheCommand(String::new())
+    );
+    assert_eq!(
+      kind(&["cache", "purge"]),
+      ErrorKind::UnknownCacheCommand("purge".to_string())
+    );
+  }
+
+  #[test]
+  fn parses_batches() {
+    assert_eq!(
+      parse(&["batch"]).unwrap().command(),
+      &Command::Batch {
+        file: None,
+        format: Format::Markdown,
+        jobs: DEFAULT_JOBS
+      }
+    );
+    assert_eq!(
+      parse(&["batch", "-", "--format=json"]).unwrap().command(),
+      &Command::Batch {
+        file: None,
+        format: Format::Json,
+        jobs: DEFAULT_JOBS
+      }
+    );
+    assert_eq!(
+      parse(&["--jobs", "8", "batch", "words.txt", "--format", "csv"])
+        .unwrap()
+        .command(),
+      &Command::Batch {
+        file: Some("words.txt".to_string()),
+        format: Format::Csv,
+        jobs: 8
+      }
+    );
+    assert_eq!(
+      kind(&["batch", "--format", "yaml"]),
+      ErrorKind::UnknownFormat("yaml".to_string())
+    );
+    assert_eq!(
+      kind(&["batch", "--jobs", "0"]),
+      ErrorKind::InvalidValue {
+        flag: "--jobs".to_string(),
+        value: "0".to_string()
+      }
+    );
+    assert_eq!(
+      kind(&["define", "ace", "--format", "json"]),
+      ErrorKind::UnknownFlag("--format".to_string())
+    );
+    assert_eq!(
+      kind(&["review", "--jobs=2"]),
+      ErrorKind::UnknownFlag("--jobs".to_string())
+    );
+    assert_eq!(kind(&["batch", "a", "b"]), ErrorKind::TooMany(3));
+  }
+
+  #[test]
+  fn parses_config_flags_and_command() {
+    let args = parse(&["config", "show", "--provider=local", "--config", "my.toml"]).unwrap();
+    assert_eq!(args.command(), &Command::ConfigShow);
+    assert_eq!(args.provider(), Some("local"));
+    assert_eq!(args.config(), Some("my.toml"));
+    assert_eq!(parse(&["define", "ace"]).unwrap().provider(), None);
+    assert_eq!(
+      kind(&["config"]),
+      ErrorKind::UnknownConfigCommand(String::new())
+    );
+    assert_eq!(
+      kind(&["config", "edit"]),
+      ErrorKind::UnknownConfigCommand("edit".to_string())
+    );
+    assert_eq!(kind(&["config", "show", "all"]), ErrorKind::TooMany(3));
+  }
+
+  #[test]
+  fn parses_review_and_export() {
+    assert_eq!(parse(&["review"]).unwrap().command(), &Command::Review);
+    assert_eq!(
+      parse(&["export", "--anki"]).unwrap().command(),
+      &Command::Export { file: None }
+    );
+    assert_eq!(
+      parse(&["--anki", "export", "deck.txt"]).unwrap().command(),
+      &Command::Export {
+        file: Some("deck.txt".to_string())
+      }
+    );
+    assert_eq!(
+      kind(&["export", "deck.txt"]),
+      ErrorKind::MissingFlag {
+        command: "export".to_string(),
+        flag: "--anki".to_string()
+      }
+    );
+    assert_eq!(
+      kind(&["review", "--anki"]),
+      ErrorKind::UnknownFlag("--anki".to_string())
+    );
+    assert_eq!(kind(&["review", "now"]), ErrorKind::TooMany(2));
+  }
+
+  #[test]
+  fn rejects_unknown_subcommands_and_flags() {
+    assert_eq!(
+      kind(&["ace"]),
+      ErrorKind::UnknownSubcommand("ace".to_string())
+    );
+    assert_eq!(
+      kind(&["define", "ace", "--verbose"]),
+      ErrorKind::UnknownFlag("--verbose".to_string())
+    );
+    assert_eq!(
+      kind(&["define", "ace", "--offline=yes"]),
+      ErrorKind::UnknownFlag("--offline".to_string())
+    );
+  }
+
+  #[test]
+  fn rejects_missing_and_invalid_values() {
+    assert_eq!(
+      kind(&["define"]),
+      ErrorKind::MissingValue("define".to_string())
+    );
+    assert_eq!(
+      kind(&["define", "ace", "--lang"]),
+      ErrorKind::MissingValue("--lang".to_string())
+    );
+    assert_eq!(
+      kind(&["define", "--lang", "--offline", "ace"]),
+      ErrorKind::MissingValue("--lang".to_string())
+    );
+    assert_eq!(
+      kind(&["define", "ace", "--limit="]),
+      ErrorKind::MissingValue("--limit".to_string())
+    );
+    for value in ["0", "-1", "many"].iter() {
+      assert_eq!(
+        kind(&["define", "ace", "--limit", value]),
+        ErrorKind::InvalidValue {
+          flag: "--limit".to_string(),
+          value: value.to_string()
+        }
+      );
+    }
+  }
+
+  #[test]
+  fn rejects_wrong_lengths() {
+    assert_eq!(kind(&["define", "ace", "bat"]), ErrorKind::TooMany(3));
+    assert_eq!(kind(&["cache", "stats", "now"]), ErrorKind::TooMany(3));
+  }
+}
@@ -0,0 +1,378 @@
+use std::fmt::Write;
+use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use serde::Serialize;
+
+use crate::arguments::Format;
+use crate::provider::{DictionaryProvider, Entry, Result, Sense};
+
+/// What looking one word of a batch up came to.
+pub struct Outcome {
+  pub word: String,
+  pub result: Result<Vec<Entry>>,
+}
+
+/// The words in `input`, one per line, skipping blank lines, `#` comments
+/// and words seen before.
+pub fn read_words(input: impl BufRead) -> io::Result<Vec<String>> {
+  let mut words: Vec<String> = Vec::new();
+  for line in input.lines() {
+    let line = line?;
+    let word = line.trim();
+    if word.is_empty() || word.starts_with('#') {
+      continue;
+    }
+    if !words.iter().any(|w| w.eq_ignore_ascii_case(word)) {
+      words.push(word.to_string());
+    }
+  }
+  Ok(words)
+}
+
+/**
+ * Looks every one of `words` up, with at most `jobs` lookups in flight at
+ * once. The outcomes are in the same order as the words, and a word that
+ * fails doesn't stop the others.
+ */
+pub fn lookup_all<P>(provider: &P, words: &[String], lang: &str, jobs: usize) -> Vec<Outcome>
+where
+  P: DictionaryProvider + Sync + ?Sized,
+{
+  // Each worker takes the next word nobody has taken yet, until none are left
+  let next = AtomicUsize::new(0);
+  let results = Mutex::new(Vec::with_capacity(words.len()));
+  thread::scope(|scope| {
+    for _ in 0..jobs.clamp(1, words.len().max(1)) {
+      scope.spawn(|| loop {
+        let index = next.fetch_add(1, Ordering::Relaxed);
+        let word = match words.get(index) {
+          Some(word) => word,
+          None => break,
+        };
+        let result = provider.lookup(word, lang);
+        results.lock().unwrap().push((index, result));
+      });
+    }
+  });
+
+  let mut results = results.into_inner().unwrap();
+  results.sort_by_key(|(index, _)| *index);
+  results
+    .into_iter()
+    .map(|(index, result)| Outcome {
+      word: words[index].clone(),
+      result,
+    })
+    .collect()
+}
+
+/// `outcomes` written as `format`, with at most `limit` definitions a word
+/// (JSON keeps whole entries).
+pub fn render(format: Format, outcomes: &[Outcome], limit: Option<usize>) -> String {
+  let limit = limit.unwrap_or(usize::MAX);
+  match format {
+    Format::Json => json(outcomes),
+    Format::Csv => csv(outcomes, limit),
+    Format::Markdown => markdown(outcomes, limit),
+  }
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+  word: &'a str,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  entries: Option<&'a [Entry]>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  error: Option<String>,
+}
+
+fn json(outcomes: &[Outcome]) -> String {
+  let records: Vec<Record> = outcomes
+    .iter()
+    .map(|outcome| match &outcome.result {
+      Ok(entries) => Record {
+        word: &outcome.word,
+        entries: Some(entries),
+        error: None,
+      },
+      Err(e) => Record {
+        word: &outcome.word,
+        entries: None,
+        error: Some(e.to_string()),
+      },
+    })
+    .collect();
+  let mut json = serde_json::to_string_pretty(&records).unwrap();
+  json.push('\n');
+  json
+}
+
+/// A row for every definition, and one for every word that failed.
+fn csv(outcomes: &[Outcome], limit: usize) -> String {
+  let mut text = String::from("word,category,definition,examples,synonyms,antonyms,error\n");
+  for outcome in outcomes {
+    let fields = match &outcome.result {
+      Ok(entries) => definitions(entries, limit)
+        .into_iter()
+        .map(|d| {
+          vec![
+            d.word.to_string(),
+            d.category,
+            d.definition.to_string(),
+            d.examples.join(" | "),
+            d.synonyms.join(", "),
+            d.antonyms.join(", "),
+            String::new(),
+          ]
+        })
+        .collect(),
+      Err(e) => vec![vec![
+        outcome.word.clone(),
+        String::new(),
+        String::new(),
+        String::new(),
+        String::new(),
+        String::new(),
+        e.to_string(),
+      ]],
+    };
+    for row in fields {
+      let row: Vec<String> = row.iter().map(|field| quote(field)).collect();
+      writeln!(text, "{}", row.join(",")).unwrap();
+    }
+  }
+  text
+}
+
+/// `field` quoted for CSV (RFC 4180), if it has to be.
+fn quote(field: &str) -> String {
+  if field.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", field.replace('"', "\"\""))
+  } else {
+    field.to_string()
+  }
+}
+
+/// A section for every word found, then a list of the ones that weren't.
+fn markdown(outcomes: &[Outcome], limit: usize) -> String {
+  let mut text = String::from("# Glossary\n");
+  let mut failed = Vec::new();
+  for outcome in outcomes {
+    let entries = match &outcome.result {
+      Ok(entries) => entries,
+      Err(e) => {
+        failed.push((&outcome.word, e));
+        continue;
+      }
+    };
+    write!(text, "\n## {}\n", outcome.word).unwrap();
+    let mut category = None;
+    let mut number = 0;
+    for d in definitions(entries, limit) {
+      if category.as_ref() != Some(&d.category) {
+        write!(text, "\n*{}*\n\n", d.category).unwrap();
+        category = Some(d.category.clone());
+        number = 0;
+      }
+      number += 1;
+      writeln!(text, "{}. {}", number, d.definition).unwrap();
+      for example in d.examples {
+        writeln!(text, "   > {}", example).unwrap();
+      }
+      if !d.synonyms.is_empty() {
+        writeln!(text, "   Synonyms: {}", d.synonyms.join(", ")).unwrap();
+      }
+      if !d.antonyms.is_empty() {
+        writeln!(text, "   Antonyms: {}", d.antonyms.join(", ")).unwrap();
+      }
+    }
+  }
+  if !failed.is_empty() {
+    text.push_str("\n## Not found\n\n");
+    for (word, error) in failed {
+      writeln!(text, "- {}: {}", word, error).unwrap();
+    }
+  }
+  text
+}
+
+/// A definition, with what goes with it.
+struct Definition<'a> {
+  word: &'a str,
+  /// Lowercase, like "noun".
+  category: String,
+  definition: &'a str,
+  examples: &'a [String],
+  synonyms: &'a [String],
+  antonyms: &'a [String],
+}
+
+/// The first `limit` definitions in `entries`, subsenses included.
+fn definitions(entries: &[Entry], limit: usize) -> Vec<Definition<'_>> {
+  fn walk<'a>(
+    word: &'a str,
+    category: &str,
+    senses: &'a [Sense],
+    definitions: &mut Vec<Definition<'a>>,
+  ) {
+    for sense in senses {
+      for definition in &sense.definitions {
+        definitions.push(Definition {
+          word,
+          category: category.to_string(),
+          definition,
+          examples: &sense.examples,
+          synonyms: &sense.synonyms,
+          antonyms: &sense.antonyms,
+        });
+      }
+      walk(word, category, &sense.subsenses, definitions);
+    }
+  }
+
+  let mut definitions = Vec::new();
+  for entry in entries {
+    for lexical_entry in &entry.lexical_entries {
+      let category = lexical_entry.category.to_lowercase();
+      walk(
+        &entry.word,
+        &category,
+        &lexical_entry.senses,
+        &mut definitions,
+      );
+    }
+  }
+  definitions.truncate(limit);
+  definitions
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::local::{parse_tsv, Local};
+  use crate::provider::{Error, ErrorKind};
+
+  /// Fails for "offline", and otherwise answers from a few words.
+  struct Flaky(Local);
+
+  impl DictionaryProvider for Flaky {
+    fn name(&self) -> &str {
+      "Flaky"
+    }
+
+    fn lookup(&self, word: &str, lang: &str) -> Result<Vec<Entry>> {
+      if word == "offline" {
+        return Err(Error::with_detail(ErrorKind::Network, "timed out"));
+      }
+      self.0.lookup(word, lang)
+    }
+  }
+
+  fn provider() -> Flaky {
+    let words = "ace\tNoun\ta playing card\tthe ace of hearts | an ace, high\tone\n\
+                 ace\tAdjective\tvery good\t\texcellent, first-rate\t\tpoor\n\
+                 bat\tNoun\ta flying mammal\n\
+                 \"quoted\"\tNoun\tsaid with \"quotes\"\n";
+    Flaky(Local::new(parse_tsv(words, "en").unwrap()))
+  }
+
+  fn outcomes(words: &[&str], jobs: usize) -> Vec<Outcome> {
+    let words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+    lookup_all(&provider(), &words, "en", jobs)
+  }
+
+  #[test]
+  fn reads_words_once_each() {
+    let input = "ace\n\n# animals\n  bat \nAce\nice cream\n";
+    assert_eq!(
+      read_words(input.as_bytes()).unwrap(),
+      vec!["ace", "bat", "ice cream"]
+    );
+  }
+
+  #[test]
+  fn looks_up_in_order_despite_failures() {
+    let words = ["bat", "offline", "cat", "ace", "bat", "ace"];
+    for jobs in [1, 2, 4, 100].iter() {
+      let outcomes = outcomes(&words, *jobs);
+      let found: Vec<(&str, bool)> = outcomes
+        .iter()
+        .map(|o| (o.word.as_str(), o.result.is_ok()))
+        .collect();
+      assert_eq!(
+        found,
+        vec![
+          ("bat", true),
+          ("offline", false),
+          ("cat", false),
+          ("ace", true),
+          ("bat", true),
+          ("ace", true)
+        ]
+      );
+    }
+    assert!(outcomes(&[], 4).is_empty());
+  }
+
+  #[test]
+  fn writes_a_markdown_glossary() {
+    let outcomes = outcomes(&["ace", "cat", "offline", "bat"], 2);
+    assert_eq!(
+      render(Format::Markdown, &outcomes, None),
+      "# Glossary\n\
+       \n## ace\n\
+       \n*noun*\n\n\
+       1. a playing card\n   \
+       > the ace of hearts\n   \
+       > an ace, high\n   \
+       Synonyms: one\n\
+       \n*adjective*\n\n\
+       1. very good\n   \
+       Synonyms: excellent, first-rate\n   \
+       Antonyms: poor\n\
+       \n## bat\n\
+       \n*noun*\n\n\
+       1. a flying mammal\n\
+       \n## Not found\n\n\
+       - cat: no entry found\n\
+       - offline: could not reach the dictionary: timed out\n"
+    );
+    assert_eq!(
+      render(Format::Markdown, &outcomes[..1], Some(1)),
+      "# Glossary\n\n## ace\n\n*noun*\n\n1. a playing card\n   \
+       > the ace of hearts\n   > an ace, high\n   Synonyms: one\n"
+    );
+  }
+
+  #[test]
+  fn writes_csv() {
+    let outcomes = outcomes(&["ace", "\"quoted\"", "cat"], 2);
+    assert_eq!(
+      render(Format::Csv, &outcomes, None),
+      "word,category,definition,examples,synonyms,antonyms,error\n\
+       ace,noun,a playing card,\"the ace of hearts | an ace, high\",one,,\n\
+       ace,adjective,very good,,\"excellent, first-rate\",poor,\n\
+       \"\"\"quoted\"\"\",noun,\"said with \"\"quotes\"\"\",,,,\n\
+       cat,,,,,,no entry found\n"
+    );
+  }
+
+  #[test]
+  fn writes_json() {
+    let outcomes = outcomes(&["bat", "cat"], 1);
+    let json: serde_json::Value =
+      serde_json::from_str(&render(Format::Json, &outcomes, Some(1))).unwrap();
+    assert_eq!(json[0]["word"], "bat");
+    assert_eq!(
+      json[0]["entries"][0]["lexical_entries"][0]["senses"][0]["definitions"][0],
+      "a flying mammal"
+    );
+    assert!(json[0].get("error").is_none());
+    assert_eq!(json[1]["word"], "cat");
+    assert_eq!(json[1]["error"], "no entry found");
+    assert!(json[1].get("entries").is_none());
+  }
+}
@@ -0,0 +1,580 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::provider::{DictionaryProvider, Entry, Error, ErrorKind, Result};
+
+/**
+ * Lookups saved on disk, one JSON file per (provider, language, word) at
+ * `<dir>/<provider>/<lang>/<word>.json`.
+ */
+pub struct Cache {
+  dir: PathBuf,
+  /// How long an entry is fresh for. Stale entries are only used when the
+  /// provider can't be asked.
+  ttl: Duration,
+}
+
+/// A lookup read back from the cache.
+pub struct Cached {
+  pub entries: Vec<Entry>,
+  /// How long ago it was looked up.
+  pub age: Duration,
+  pub fresh: bool,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Stats {
+  pub entries: usize,
+  pub stale: usize,
+  pub bytes: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct File {
+  /// Seconds since the Unix epoch.
+  fetched_at: u64,
+  entries: Vec<Entry>,
+}
+
+impl Cache {
+  pub fn new(dir: impl Into<PathBuf>, ttl: Duration) -> Self {
+    Cache {
+      dir: dir.into(),
+      ttl,
+    }
+  }
+
+  pub fn from_config(config: &Config) -> Self {
+    Cache::new(config.cache_dir(), config.cache_ttl())
+  }
+
+  pub fn dir(&self) -> &Path {
+    &self.dir
+  }
+
+  /// The saved lookup, fresh or not. Unreadable files count as missing.
+  pub fn get(&self, provider: &str, lang: &str, word: &str) -> Option<Cached> {
+    let text = fs::read_to_string(self.path(provider, lang, word)).ok()?;
+    let file: File = serde_json::from_str(&text).ok()?;
+    let age = age(file.fetched_at);
+    Some(Cached {
+      entries: file.entries,
+      age,
+      fresh: age <= self.ttl,
+    })
+  }
+
+  pub fn put(&self, provider: &str, lang: &str, word: &str, entries: &[Entry]) -> io::Result<()> {
+    let path = self.path(provider, lang, word);
+    fs::create_dir_all(path.parent().unwrap())?;
+    let file = File {
+      fetched_at: now(),
+      entries: entries.to_vec(),
+    };
+    let json = serde_json::to_string(&file)?;
+    // Written aside and moved in place, so a reader never sees half a file
+    let partial = path.with_extension("json.partial");
+    fs::write(&partial, json)?;
+    fs::rename(&partial, &path)
+  }
+
+  pub fn stats(&self) -> io::Result<Stats> {
+    let mut stats = Stats::default();
+    for path in self.files()? {
+      stats.entries += 1;
+      stats.bytes += fs::metadata(&path)?.len();
+      let fetched_at = fs::read_to_string(&path)
+        .ok()
+        .and_then(|text| serde_json::from_str::<File>(&text).ok())
+        .map(|file| file.fetched_at);
+      if fetched_at.is_none_or(|fetched_at| age(fetched_at) > self.ttl) {
+        stats.stale += 1;
+      }
+    }
+    Ok(stats)
+  }
+
+  /// Every word saved for `provider` in `lang`.
+  pub fn words(&self, provider: &str, lang: &str) -> Vec<String> {
+    let dir = self.dir.join(file_name(provider)).join(file_name(lang));
+    let mut words: Vec<String> = fs::read_dir(dir)
+      .into_iter()
+      .flatten()
+      .filter_map(|entry| {
+        let name = entry.ok()?.file_name().into_string().ok()?;
+        decode(name.strip_suffix(".json")?)
+      })
+      .collect();
+    words.sort_unstable();
+    words
+  }
+
+  /// Removes every saved lookup, returning how many there were. Anything
+  /// else in the directory is left alone, since it may well be a directory
+  /// of the user's: only the provider and language directories that end up
+  /// empty go with them.
+  pub fn clear(&self) -> io::Result<usize> {
+    let files = self.files()?;
+    for path in &files {
+      fs::remove_file(path)?;
+    }
+    for path in &files {
+      for dir in path.ancestors().skip(1).take(2) {
+        // Fails for directories that still hold something, or are gone
+        if fs::remove_dir(dir).is_err() {
+          break;
+        }
+      }
+    }
+    Ok(files.len())
+  }
+
+  fn path(&self, provider: &str, lang: &str, word: &str) -> PathBuf {
+    let word = word.trim().to_lowercase();
+    self
+      .dir
+      .join(file_name(provider))
+      .join(file_name(lang))
+      .join(format!("{}.json", file_name(&word)))
+  }
+
+  /// Every cache file, found three levels down.
+  fn files(&self) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![(self.dir.clone(), 0)];
+    while let Some((dir, depth)) = dirs.pop() {
+      let read = match fs::read_dir(&dir) {
+        Ok(read) => read,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => continue,
+        Err(e) => return Err(e),
+      };
+      for entry in read {
+        let path = entry?.path();
+        if depth < 2 && path.is_dir() {
+          dirs.push((path, depth + 1));
+        } else if depth == 2 && path.extension().is_some_and(|e| e == "json") {
+          files.push(path);
+        }
+      }
+    }
+    Ok(files)
+  }
+}
+
+/**
+ * A provider that answers from the cache while it can.
+ *
+ * - Fresh entries are used as they are, without asking `provider`.
+ * - Offline, entries are used however old they are, and a word that isn't
+ *   cached is an `ErrorKind::NotCached` error.
+ * - When `provider` can't be reached, a stale entry is better than none.
+ */
+pub struct CachedProvider<P> {
+  provider: P,
+  cache: Cache,
+  offline: bool,
+}
+
+impl<P: DictionaryProvider> CachedProvider<P> {
+  pub fn new(provider: P, cache: Cache, offline: bool) -> Self {
+    CachedProvider {
+      provider,
+      cache,
+      offline,
+    }
+  }
+}
+
+impl<P: DictionaryProvider> DictionaryProvider for CachedProvider<P> {
+  fn name(&self) -> &str {
+    self.provider.name()
+  }
+
+  fn lookup(&self, word: &str, lang: &str) -> Result<Vec<Entry>> {
+    let name = self.provider.name();
+    let cached = self.cache.get(name, lang, word);
+    match cached {
+      Some(cached) if cached.fresh || self.offline => return Ok(cached.entries),
+      None if self.offline => return Err(Error::new(ErrorKind::NotCached)),
+      _ => {}
+    }
+
+    match self.provider.lookup(word, lang) {
+      Ok(entries) => {
+        if let Err(e) = self.cache.put(name, lang, word, &entries) {
+          eprintln!("warning: could not cache \"{}\": {}", word, e);
+        }
+        Ok(entries)
+      }
+      Err(e) => match (e.kind(), cached) {
+        (ErrorKind::Network, Some(cached)) | (ErrorKind::Status(_), Some(cached)) => {
+          eprintln!(
+            "warning: {}, showing what was cached {} ago",
+            e,
+            describe(cached.age)
+          );
+          Ok(cached.entries)
+        }
+        _ => Err(e),
+      },
+    }
+  }
+
+  fn words(&self, lang: &str) -> Vec<String> {
+    let mut words = self.cache.words(self.provider.name(), lang);
+    for word in self.provider.words(lang) {
+      if !words.contains(&word) {
+        words.push(word);
+      }
+    }
+    words
+  }
+}
+
+/// Parses a duration like `90`, `90s`, `30m`, `12h` or `7d`. A bare number
+/// is in seconds.
+pub fn parse_duration(text: &str) -> Option<Duration> {
+  let text = text.trim();
+  let (number, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+    Some(i) => text.split_at(i),
+    None => (text, "s"),
+  };
+  let seconds = match unit {
+    "s" => 1,
+    "m" => 60,
+    "h" => 60 * 60,
+    "d" => 24 * 60 * 60,
+    _ => return None,
+  };
+  let number: u64 = number.parse().ok()?;
+  Some(Duration::from_secs(number.checked_mul(seconds)?))
+}
+
+/// Roughly how long `duration` is, like "3 hours".
+fn describe(duration: Duration) -> String {
+  let seconds = duration.as_secs();
+  let (count, unit) = match seconds {
+    0..=119 => (seconds, "second"),
+    120..=7_199 => (seconds / 60, "minute"),
+    7_200..=172_799 => (seconds / (60 * 60), "hour"),
+    _ => (seconds / (24 * 60 * 60), "day"),
+  };
+  if count == 1 {
+    format!("{} {}", count, unit)
+  } else {
+    format!("{} {}s", count, unit)
+  }
+}
+
+/// `name` made safe to use as a file name, by percent-encoding all but
+/// letters, digits, `-` and `_`.
+fn file_name(name: &str) -> String {
+  let mut encoded = String::new();
+  for c in name.chars() {
+    if c.is_alphanumeric() || c == '-' || c == '_' {
+      encoded.push(c);
+    } else {
+      let mut buffer = [0; 4];
+      for byte in c.encode_utf8(&mut buffer).bytes() {
+        encoded.push_str(&format!("%{:02X}", byte));
+      }
+    }
+  }
+  encoded
+}
+
+/// The name `file_name` was given, or `None` if it wasn't from there.
+fn decode(file_name: &str) -> Option<String> {
+  let mut bytes = Vec::new();
+  let mut rest = file_name.as_bytes();
+  while let Some((&byte, tail)) = rest.split_first() {
+    if byte == b'%' {
+      let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+      bytes.push(u8::from_str_radix(hex, 16).ok()?);
+      rest = &tail[2..];
+    } else {
+      bytes.push(byte);
+      rest = tail;
+    }
+  }
+  String::from_utf8(bytes).ok()
+}
+
+/// Seconds since the Unix epoch.
+pub fn now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0, |d| d.as_secs())
+}
+
+fn age(fetched_at: u64) -> Duration {
+  Duration::from_secs(now().saturating_sub(fetched_at))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::cell::{Cell, RefCell};
+
+  use super::*;
+  use crate::provider::{LexicalEntry, Sense};
+
+  /// Provider answering every lookup with whatever `next` holds.
+  struct Fake {
+    next: RefCell<Option<Result<Vec<Entry>>>>,
+    calls: Cell<usize>,
+  }
+
+  impl Fake {
+    fn new() -> Self {
+      Fake {
+        next: RefCell::new(None),
+        calls: Cell::new(0),
+      }
+    }
+
+    fn answer(&self, result: Result<Vec<Entry>>) {
+      *self.next.borrow_mut() = Some(result);
+    }
+  }
+
+  impl DictionaryProvider for &Fake {
+    fn name(&self) -> &str {
+      "fake"
+    }
+
+    fn lookup(&self, _word: &str, _lang: &str) -> Result<Vec<Entry>> {
+      self.calls.set(self.calls.get() + 1);
+      self.next.borrow_mut().take().expect("unexpected lookup")
+    }
+  }
+
+  fn entry(word: &str, definition: &str) -> Entry {
+    Entry {
+      word: word.to_string(),
+      language: "en".to_string(),
+      lexical_entries: vec![LexicalEntry {
+        category: "Noun".to_string(),
+        pronunciations: vec![],
+        senses: vec![Sense {
+          definitions: vec![definition.to_string()],
+          examples: vec![],
+          subsenses: vec![],
+          synonyms: vec![],
+          antonyms: vec![],
+        }],
+        etymologies: vec![],
+      }],
+    }
+  }
+
+  /// Backdates every cache file by `seconds`.
+  fn age_files(cache: &Cache, seconds: u64) {
+    for path in cache.files().unwrap() {
+      let mut file: File = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+      file.fetched_at -= seconds;
+      fs::write(&path, serde_json::to_string(&file).unwrap()).unwrap();
+    }
+  }
+
+  #[test]
+  fn keys_entries_by_provider_language_and_word() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::new(dir.path(), Duration::from_secs(60));
+    cache
+      .put("oxford", "en", "Ice cream", &[entry("ice cream", "a")])
+      .unwrap();
+    cache
+      .put("oxford", "es", "ice cream", &[entry("ice cream", "b")])
+      .unwrap();
+    cache
+      .put("other", "en", "ice cream", &[entry("ice cream", "c")])
+      .unwrap();
+
+    let cached = cache.get("oxford", "en", "ice cream").unwrap();
+    assert!(cached.fresh);
+    assert_eq!(cached.entries, vec![entry("ice cream", "a")]);
+    assert_eq!(
+      cache.get("oxford", "es", "ICE CREAM").unwrap().entries[0],
+      entry("ice cream", "b")
+    );
+    assert_eq!(
+      cache.get("other", "en", "ice cream").unwrap().entries[0],
+      entry("ice cream", "c")
+    );
+    assert!(cache.get("oxford", "fr", "ice cream").is_none());
+    assert!(dir.path().join("oxford/en/ice%20cream.json").is_file());
+
+    cache.put("oxford", "en", "Ålesund", &[]).unwrap();
+    cache.put("oxford", "en", "100%", &[]).unwrap();
+    assert_eq!(
+      cache.words("oxford", "en"),
+      vec!["100%", "ice cream", "ålesund"]
+    );
+    assert!(cache.words("oxford", "fr").is_empty());
+  }
+
+  #[test]
+  fn counts_and_clears_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::new(dir.path().join("cache"), Duration::from_secs(60));
+    assert_eq!(cache.stats().unwrap(), Stats::default());
+    assert_eq!(cache.clear().unwrap(), 0);
+
+    cache
+      .put("oxford", "en", "ace", &[entry("ace", "a")])
+      .unwrap();
+    cache
+      .put("oxford", "en", "bat", &[entry("bat", "b")])
+      .unwrap();
+    age_files(&cache, 30);
+    cache
+      .put("oxford", "en", "cat", &[entry("cat", "c")])
+      .unwrap();
+    age_files(&cache, 31);
+
+    let stats = cache.stats().unwrap();
+    assert_eq!(stats.entries, 3);
+    assert_eq!(stats.stale, 2);
+    assert!(stats.bytes > 0);
+    assert!(!cache.get("oxford", "en", "ace").unwrap().fresh);
+    assert!(cache.get("oxford", "en", "cat").unwrap().fresh);
+
+    assert_eq!(cache.clear().unwrap(), 3);
+    assert_eq!(cache.stats().unwrap(), Stats::default());
+    assert!(cache.get("oxford", "en", "ace").is_none());
+    assert!(!cache.dir().join("oxford").exists());
+  }
+
+  #[test]
+  fn clears_nothing_but_cache_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::new(dir.path(), Duration::from_secs(60));
+    cache.put("oxford", "en", "ace", &[]).unwrap();
+    cache.put("oxford", "es", "as", &[]).unwrap();
+    fs::write(dir.path().join("notes.txt"), "mine").unwrap();
+    fs::write(dir.path().join("oxford/es/notes.txt"), "mine").unwrap();
+
+    assert_eq!(cache.clear().unwrap(), 2);
+    assert_eq!(
+      fs::read_to_string(dir.path().join("notes.txt")).unwrap(),
+      "mine"
+    );
+    assert!(dir.path().join("oxford/es/notes.txt").is_file());
+    assert!(!dir.path().join("oxford/es/as.json").exists());
+    assert!(!dir.path().join("oxford/en").exists());
+  }
+
+  #[test]
+  fn asks_the_provider_only_for_missing_or_stale_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let fake = Fake::new();
+    let provider = CachedProvider::new(
+      &fake,
+      Cache::new(dir.path(), Duration::from_secs(60)),
+      false,
+    );
+
+    fake.answer(Ok(vec![entry("ace", "old")]));
+    assert_eq!(
+      provider.lookup("ace", "en").unwrap(),
+      vec![entry("ace", "old")]
+    );
+    assert_eq!(
+      provider.lookup("ace", "en").unwrap(),
+      vec![entry("ace", "old")]
+    );
+    assert_eq!(fake.calls.get(), 1);
+
+    age_files(&provider.cache, 61);
+    fake.answer(Ok(vec![entry("ace", "new")]));
+    assert_eq!(
+      provider.lookup("ace", "en").unwrap(),
+      vec![entry("ace", "new")]
+    );
+    assert_eq!(fake.calls.get(), 2);
+
+    // Not found isn't cached, nor answered from a stale entry
+    age_files(&provider.cache, 61);
+    fake.answer(Err(Error::new(ErrorKind::NotFound)));
+    let error = provider.lookup("ace", "en").unwrap_err();
+    assert_eq!(error.kind(), &ErrorKind::NotFound);
+  }
+
+  #[test]
+  fn falls_back_to_stale_entries_when_the_provider_is_unreachable() {
+    let dir = tempfile::tempdir().unwrap();
+    let fake = Fake::new();
+    let provider = CachedProvider::new(
+      &fake,
+      Cache::new(dir.path(), Duration::from_secs(60)),
+      false,
+    );
+
+    fake.answer(Ok(vec![entry("ace", "a")]));
+    provider.lookup("ace", "en").unwrap();
+    age_files(&provider.cache, 3600);
+
+    fake.answer(Err(Error::new(ErrorKind::Network)));
+    assert_eq!(
+      provider.lookup("ace", "en").unwrap(),
+      vec![entry("ace", "a")]
+    );
+    fake.answer(Err(Error::new(ErrorKind::Status(503))));
+    assert_eq!(
+      provider.lookup("ace", "en").unwrap(),
+      vec![entry("ace", "a")]
+    );
+
+    fake.answer(Err(Error::new(ErrorKind::Network)));
+    let error = provider.lookup("bat", "en").unwrap_err();
+    assert_eq!(error.kind(), &ErrorKind::Network);
+  }
+
+  #[test]
+  fn answers_offline_from_the_cache_alone() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::new(dir.path(), Duration::from_secs(60));
+    cache
+      .put("fake", "en", "ace", &[entry("ace", "a")])
+      .unwrap();
+    age_files(&cache, 3600);
+
+    let fake = Fake::new();
+    let provider = CachedProvider::new(&fake, cache, true);
+    assert_eq!(
+      provider.lookup("ace", "en").unwrap(),
+      vec![entry("ace", "a")]
+    );
+    let error = provider.lookup("bat", "en").unwrap_err();
+    assert_eq!(error.kind(), &ErrorKind::NotCached);
+    assert_eq!(fake.calls.get(), 0);
+  }
+
+  #[test]
+  fn parses_durations() {
+    assert_eq!(parse_duration("90"), Some(Duration::from_secs(90)));
+    assert_eq!(parse_duration("90s"), Some(Duration::from_secs(90)));
+    assert_eq!(parse_duration("30m"), Some(Duration::from_secs(30 * 60)));
+    assert_eq!(
+      parse_duration(" 12h "),
+      Some(Duration::from_secs(12 * 3600))
+    );
+    assert_eq!(parse_duration("7d"), Some(Duration::from_secs(7 * 86400)));
+    for invalid in ["", "d", "7w", "1.5h", "-1", "7 d"].iter() {
+      assert_eq!(parse_duration(invalid), None, "{}", invalid);
+    }
+  }
+
+  #[test]
+  fn describes_ages() {
+    assert_eq!(describe(Duration::from_secs(1)), "1 second");
+    assert_eq!(describe(Duration::from_secs(150)), "2 minutes");
+    assert_eq!(describe(Duration::from_secs(3 * 3600)), "3 hours");
+    assert_eq!(describe(Duration::from_secs(9 * 86400)), "9 days");
+  }
+}
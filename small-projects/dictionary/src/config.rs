@@ -1,32 +1,516 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
 use dotenv::dotenv;
 
+use crate::cache;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// How long lookups are cached for when `cache_ttl` isn't set.
+const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+const DEFAULT_API_URL: &str = "https://od-api.oxforddictionaries.com:443/api/v2/entries";
+
+/// Every setting, as named in the config file and in the environment.
+/// `LANG` is the locale, set nearly everywhere, so the language has a name
+/// of its own.
+const SETTINGS: [(&str, &str); 9] = [
+  ("provider", "PROVIDER"),
+  ("api_url", "API_URL"),
+  ("app_id", "APP_ID"),
+  ("api_key", "API_KEY"),
+  ("lang", "DICTIONARY_LANG"),
+  ("dictionary_file", "DICTIONARY_FILE"),
+  ("cache_dir", "CACHE_DIR"),
+  ("cache_ttl", "CACHE_TTL"),
+  ("data_dir", "DATA_DIR"),
+];
+
+/// Settings `config show` doesn't print.
+const SECRETS: [&str; 2] = ["app_id", "api_key"];
+
+/// Where lookups go.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+  /// The Oxford Dictionaries API, with `app_id` and `api_key`.
+  Oxford,
+  /// The dictionary file at `dictionary_file`.
+  Local,
+}
+
+impl Source {
+  pub fn as_str(&self) -> &str {
+    match *self {
+      Source::Oxford => "oxford",
+      Source::Local => "local",
+    }
+  }
+}
+
+/// Where a setting came from, each overriding the ones before.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Origin {
+  Default,
+  File,
+  Env,
+  Flag,
+}
+
+impl Origin {
+  pub fn as_str(&self) -> &str {
+    match *self {
+      Origin::Default => "default",
+      Origin::File => "config file",
+      Origin::Env => "environment",
+      Origin::Flag => "command line",
+    }
+  }
+}
+
 #[derive(Debug)]
 pub struct Config {
+  /// The config file, whether or not there was one.
+  file: PathBuf,
+  /// Set explicitly, rather than picked by whether there are credentials.
+  provider: Option<Source>,
   api_url: String,
   app_id: String,
   api_key: String,
   lang: String,
+  cache_dir: PathBuf,
+  cache_ttl: Duration,
+  dictionary_file: PathBuf,
+  data_dir: PathBuf,
+  /// Where every setting that isn't a default came from.
+  origins: BTreeMap<&'static str, Origin>,
 }
 
 impl Config {
-  pub fn new() -> Self {
+  /**
+   * The settings in the config file (`file`, or `config.toml` in the
+   * user's config directory), then the environment (and `.env`), then
+   * `flags` like `("lang", "fr")`, each overriding the ones before.
+   */
+  pub fn new(file: Option<&Path>, flags: &[(&str, &str)]) -> Result<Self> {
     dotenv().ok();
-    Config {
-      api_url: Config::get_var(
-        "API_URL",
-        "https://od-api.oxforddictionaries.com:443/api/v2/entries",
+    Config::load(file, |name| dotenv::var(name).ok(), flags)
+  }
+
+  fn load<E>(file: Option<&Path>, env: E, flags: &[(&str, &str)]) -> Result<Self>
+  where
+    E: Fn(&str) -> Option<String>,
+  {
+    let (path, required) = match file {
+      Some(file) => (file.to_path_buf(), true),
+      None => (Config::default_file(), false),
+    };
+    let mut values = Config::read_file(&path, required)?;
+    for (key, name) in SETTINGS.iter() {
+      if let Some(value) = env(name).filter(|value| !value.is_empty()) {
+        values.insert(key, (value, Origin::Env));
+      }
+    }
+    for (key, value) in flags {
+      let key = Config::key(key)?;
+      values.insert(key, (value.to_string(), Origin::Flag));
+    }
+
+    let text = |key: &str, default: &str| match values.get(key) {
+      Some((value, _)) => value.clone(),
+      None => default.to_string(),
+    };
+    let path_or = |key: &str, default: PathBuf| match values.get(key) {
+      Some((value, _)) => PathBuf::from(value),
+      None => default,
+    };
+    let user_dir = |dir: Option<PathBuf>, fallback: &str| match dir {
+      Some(dir) => dir.join("dictionary"),
+      None => PathBuf::from(fallback),
+    };
+    let data_dir = path_or("data_dir", user_dir(dirs::data_dir(), ".dictionary"));
+
+    let provider = match values.get("provider") {
+      None => None,
+      Some((value, _)) if value == "oxford" => Some(Source::Oxford),
+      Some((value, _)) if value == "local" => Some(Source::Local),
+      Some((value, origin)) => {
+        return Err(Error::invalid(
+          "provider",
+          value,
+          *origin,
+          "oxford or local",
+        ))
+      }
+    };
+    let cache_ttl = match values.get("cache_ttl") {
+      None => DEFAULT_CACHE_TTL,
+      Some((value, origin)) => cache::parse_duration(value)
+        .ok_or_else(|| Error::invalid("cache_ttl", value, *origin, "like 90s, 30m, 12h or 7d"))?,
+    };
+
+    Ok(Config {
+      provider,
+      api_url: text("api_url", DEFAULT_API_URL),
+      app_id: text("app_id", ""),
+      api_key: text("api_key", ""),
+      lang: text("lang", "en"),
+      cache_dir: path_or(
+        "cache_dir",
+        user_dir(dirs::cache_dir(), ".dictionary-cache"),
       ),
-      app_id: Config::get_var("APP_ID", ""),
-      api_key: Config::get_var("API_KEY", ""),
-      lang: Config::get_var("LANG", "en"),
+      cache_ttl,
+      dictionary_file: path_or("dictionary_file", data_dir.join("words.tsv")),
+      data_dir,
+      origins: values
+        .iter()
+        .map(|(key, (_, origin))| (*key, *origin))
+        .collect(),
+      file: path,
+    })
+  }
+
+  /// `config.toml` in the user's config directory, like
+  /// `~/.config/dictionary/config.toml`.
+  fn default_file() -> PathBuf {
+    match dirs::config_dir() {
+      Some(dir) => dir.join("dictionary").join("config.toml"),
+      None => PathBuf::from(".dictionary.toml"),
+    }
+  }
+
+  /// The settings in the TOML file at `path`. It's fine for it to be
+  /// missing, unless it's `required`.
+  fn read_file(path: &Path, required: bool) -> Result<BTreeMap<&'static str, (String, Origin)>> {
+    let invalid = |detail: String| Error::with_detail(ErrorKind::InvalidFile(path.into()), detail);
+    let text = match fs::read_to_string(path) {
+      Ok(text) => text,
+      Err(ref e) if e.kind() == io::ErrorKind::NotFound && !required => return Ok(BTreeMap::new()),
+      Err(e) => return Err(invalid(e.to_string())),
+    };
+    let table: toml::Table = text
+      .parse()
+      .map_err(|e: toml::de::Error| invalid(e.message().to_string()))?;
+
+    let mut values = BTreeMap::new();
+    for (key, value) in table {
+      let key = Config::key(&key)?;
+      let value = match value {
+        toml::Value::String(value) => value,
+        value => {
+          return Err(Error::invalid(
+            key,
+            &value.to_string(),
+            Origin::File,
+            "a string",
+          ))
+        }
+      };
+      values.insert(key, (value, Origin::File));
+    }
+    Ok(values)
+  }
+
+  /// `key` if it names a setting.
+  fn key(key: &str) -> Result<&'static str> {
+    match SETTINGS.iter().find(|(known, _)| *known == key) {
+      Some((known, _)) => Ok(known),
+      None => Err(Error::new(ErrorKind::UnknownKey(key.to_string()))),
+    }
+  }
+
+  pub fn api_url(&self) -> &str {
+    &self.api_url
+  }
+
+  pub fn lang(&self) -> &str {
+    &self.lang
+  }
+
+  pub fn cache_dir(&self) -> &Path {
+    &self.cache_dir
+  }
+
+  pub fn cache_ttl(&self) -> Duration {
+    self.cache_ttl
+  }
+
+  pub fn dictionary_file(&self) -> &Path {
+    &self.dictionary_file
+  }
+
+  /// Where what's kept between runs goes, like the REPL's history.
+  pub fn data_dir(&self) -> &Path {
+    &self.data_dir
+  }
+
+  /// `provider` if it's set, and otherwise the Oxford API if there are
+  /// credentials for it, the local dictionary if not.
+  pub fn source(&self) -> Source {
+    match self.provider {
+      Some(source) => source,
+      None if self.app_id.is_empty() || self.api_key.is_empty() => Source::Local,
+      None => Source::Oxford,
+    }
+  }
+
+  /// `app_id` and `api_key`, for the Oxford API.
+  pub fn credentials(&self) -> Result<(&str, &str)> {
+    if self.app_id.is_empty() || self.api_key.is_empty() {
+      return Err(Error::new(ErrorKind::MissingCredentials));
+    }
+    Ok((&self.app_id, &self.api_key))
+  }
+
+  /**
+   * Every setting in effect as TOML, with where it came from, and the
+   * secrets left out.
+   */
+  pub fn show(&self) -> String {
+    let mut text = format!("# {}\n", self.file.display());
+    for (key, _) in SETTINGS.iter() {
+      let value = match *key {
+        "provider" => self.source().as_str().to_string(),
+        "api_url" => self.api_url.clone(),
+        "app_id" => self.app_id.clone(),
+        "api_key" => self.api_key.clone(),
+        "lang" => self.lang.clone(),
+        "dictionary_file" => self.dictionary_file.display().to_string(),
+        "cache_dir" => self.cache_dir.display().to_string(),
+        "cache_ttl" => format!("{}s", self.cache_ttl.as_secs()),
+        "data_dir" => self.data_dir.display().to_string(),
+        _ => unreachable!(),
+      };
+      let value = if SECRETS.contains(key) && !value.is_empty() {
+        "<redacted>".to_string()
+      } else {
+        value
+      };
+      let origin = self.origins.get(key).unwrap_or(&Origin::Default);
+      writeln!(text, "{} = {:?}  # {}", key, value, origin.as_str()).unwrap();
+    }
+    text
+  }
+}
+
+#[derive(Debug)]
+pub struct Error {
+  kind: ErrorKind,
+  detail: Option<String>,
+}
+
+impl Error {
+  pub fn new(kind: ErrorKind) -> Self {
+    Error { kind, detail: None }
+  }
+
+  pub fn with_detail(kind: ErrorKind, detail: impl Into<String>) -> Self {
+    Error {
+      kind,
+      detail: Some(detail.into()),
+    }
+  }
+
+  /// `value` isn't what `key` expects.
+  fn invalid(key: &str, value: &str, origin: Origin, expected: &str) -> Self {
+    let kind = ErrorKind::InvalidValue {
+      key: key.to_string(),
+      value: value.to_string(),
+      origin,
+    };
+    Error::with_detail(kind, format!("expected {}", expected))
+  }
+
+  pub fn kind(&self) -> &ErrorKind {
+    &self.kind
+  }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match &self.kind {
+      ErrorKind::InvalidFile(path) => write!(f, "{} {}", self.kind.as_str(), path.display())?,
+      ErrorKind::UnknownKey(key) => write!(f, "{} `{}`", self.kind.as_str(), key)?,
+      ErrorKind::InvalidValue { key, value, origin } => write!(
+        f,
+        "{} {:?} for `{}` (from the {})",
+        self.kind.as_str(),
+        value,
+        key,
+        origin.as_str()
+      )?,
+      ErrorKind::MissingCredentials => write!(f, "{}", self.kind.as_str())?,
+    }
+    match &self.detail {
+      Some(detail) => write!(f, ": {}", detail),
+      None => Ok(()),
     }
   }
+}
+
+impl std::error::Error for Error {}
 
-  fn get_var(key: &str, default: &str) -> String {
-    if let Ok(val) = dotenv::var(key) {
-      val
-    } else {
-      String::from(default)
+#[derive(Debug, PartialEq)]
+pub enum ErrorKind {
+  /// The config file couldn't be read, or isn't TOML.
+  InvalidFile(PathBuf),
+  /// A setting that doesn't exist.
+  UnknownKey(String),
+  InvalidValue {
+    key: String,
+    value: String,
+    origin: Origin,
+  },
+  /// The Oxford API is to be used, without `app_id` and `api_key`.
+  MissingCredentials,
+}
+
+impl ErrorKind {
+  pub fn as_str(&self) -> &str {
+    match *self {
+      ErrorKind::InvalidFile(_) => "could not read the config file",
+      ErrorKind::UnknownKey(_) => "unknown setting",
+      ErrorKind::InvalidValue { .. } => "invalid value",
+      ErrorKind::MissingCredentials => {
+        "the Oxford API needs `app_id` and `api_key` (or APP_ID and API_KEY)"
+      }
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use super::*;
+
+  fn load(file: &str, env: &[(&str, &str)], flags: &[(&str, &str)]) -> Result<Config> {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, file).unwrap();
+    let env: HashMap<String, String> = env
+      .iter()
+      .map(|(name, value)| (name.to_string(), value.to_string()))
+      .collect();
+    Config::load(Some(&path), |name| env.get(name).cloned(), flags)
+  }
+
+  #[test]
+  fn layers_file_env_and_flags() {
+    let file = "lang = \"fr\"\ncache_ttl = \"1h\"\ndata_dir = \"/from/file\"\napp_id = \"id\"\n";
+    let env = [
+      ("DICTIONARY_LANG", "es"),
+      ("DATA_DIR", "/from/env"),
+      ("API_KEY", ""),
+    ];
+    let config = load(file, &env, &[("lang", "de")]).unwrap();
+    assert_eq!(config.lang(), "de");
+    assert_eq!(config.data_dir(), Path::new("/from/env"));
+    assert_eq!(config.cache_ttl(), Duration::from_secs(60 * 60));
+    assert_eq!(config.api_url(), DEFAULT_API_URL);
+    assert_eq!(
+      config.dictionary_file(),
+      config.data_dir().join("words.tsv")
+    );
+
+    let config = load(file, &[], &[]).unwrap();
+    assert_eq!(config.lang(), "fr");
+    assert_eq!(config.data_dir(), Path::new("/from/file"));
+    assert_eq!(config.dictionary_file(), Path::new("/from/file/words.tsv"));
+
+    // The locale isn't the language to look words up in
+    let config = load(file, &[("LANG", "en_US.UTF-8")], &[]).unwrap();
+    assert_eq!(config.lang(), "fr");
+    let config = load("", &[("LANG", "en_US.UTF-8")], &[]).unwrap();
+    assert_eq!(config.lang(), "en");
+    let env = [("LANG", "en_US.UTF-8"), ("DICTIONARY_LANG", "es")];
+    assert_eq!(load(file, &env, &[]).unwrap().lang(), "es");
+  }
+
+  #[test]
+  fn needs_credentials_only_for_the_oxford_api() {
+    // Without a provider, the credentials pick one
+    let config = load("app_id = \"id\"\n", &[], &[]).unwrap();
+    assert_eq!(config.source(), Source::Local);
+    let config = load("app_id = \"id\"\n", &[("API_KEY", "key")], &[]).unwrap();
+    assert_eq!(config.source(), Source::Oxford);
+    assert_eq!(config.credentials().unwrap(), ("id", "key"));
+
+    let config = load("provider = \"oxford\"\n", &[("APP_ID", "id")], &[]).unwrap();
+    assert_eq!(config.source(), Source::Oxford);
+    assert_eq!(
+      config.credentials().unwrap_err().kind(),
+      &ErrorKind::MissingCredentials
+    );
+    let config = load("", &[("PROVIDER", "oxford")], &[("provider", "local")]).unwrap();
+    assert_eq!(config.source(), Source::Local);
+  }
+
+  #[test]
+  fn rejects_invalid_settings() {
+    let error = load("", &[("CACHE_TTL", "soon")], &[]).unwrap_err();
+    assert_eq!(
+      error.kind(),
+      &ErrorKind::InvalidValue {
+        key: "cache_ttl".to_string(),
+        value: "soon".to_string(),
+        origin: Origin::Env
+      }
+    );
+    assert_eq!(
+      error.to_string(),
+      "invalid value \"soon\" for `cache_ttl` (from the environment): \
+       expected like 90s, 30m, 12h or 7d"
+    );
+    let error = load("", &[], &[("provider", "wiktionary")]).unwrap_err();
+    assert!(matches!(
+      error.kind(),
+      ErrorKind::InvalidValue {
+        origin: Origin::Flag,
+        ..
+      }
+    ));
+    assert_eq!(
+      load("colour = \"blue\"\n", &[], &[]).unwrap_err().kind(),
+      &ErrorKind::UnknownKey("colour".to_string())
+    );
+    assert!(matches!(
+      load("cache_ttl = 60\n", &[], &[]).unwrap_err().kind(),
+      ErrorKind::InvalidValue {
+        origin: Origin::File,
+        ..
+      }
+    ));
+    assert!(matches!(
+      load("lang = \n", &[], &[]).unwrap_err().kind(),
+      ErrorKind::InvalidFile(_)
+    ));
+  }
+
+  #[test]
+  fn needs_a_config_file_only_when_given_one() {
+    let missing = Path::new("/nonexistent/config.toml");
+    let error = Config::load(Some(missing), |_| None, &[]).unwrap_err();
+    assert_eq!(error.kind(), &ErrorKind::InvalidFile(missing.into()));
+    assert!(Config::read_file(missing, false).unwrap().is_empty());
+  }
+
+  #[test]
+  fn shows_settings_without_secrets() {
+    let env = [("APP_ID", "id"), ("API_KEY", "s3cret")];
+    let config = load("lang = \"fr\"\n", &env, &[("provider", "oxford")]).unwrap();
+    let shown = config.show();
+    assert!(!shown.contains("s3cret"));
+    assert!(shown.contains("\napi_key = \"<redacted>\"  # environment\n"));
+    assert!(shown.contains("\nprovider = \"oxford\"  # command line\n"));
+    assert!(shown.contains("\nlang = \"fr\"  # config file\n"));
+    assert!(shown.contains("\ncache_ttl = \"604800s\"  # default\n"));
+
+    let shown = load("", &[], &[]).unwrap().show();
+    assert!(shown.contains("\napp_id = \"\"  # default\n"));
+    assert!(shown.contains("\nprovider = \"local\"  # default\n"));
+  }
+}
@@ -0,0 +1,340 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cache::now;
+use crate::provider::{DictionaryProvider, Entry, Result};
+
+/// Seconds in a day, the unit of SM-2 intervals.
+const DAY: u64 = 24 * 60 * 60;
+/// How many definitions and examples a card keeps.
+const DEFINITIONS: usize = 5;
+const EXAMPLES: usize = 3;
+
+/**
+ * A word that was looked up, as a flashcard: what it means, and when to
+ * review it next.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Card {
+  pub word: String,
+  pub lang: String,
+  /// Like "(noun) a playing card with a single spot on it".
+  pub definitions: Vec<String>,
+  pub examples: Vec<String>,
+  pub lookups: u32,
+  /// Seconds since the Unix epoch.
+  pub last_lookup: u64,
+  /// Reviews in a row that were remembered.
+  pub repetitions: u32,
+  /// Days until the next review.
+  pub interval: u64,
+  /// How fast the interval grows, at least 1.3.
+  pub ease: f64,
+  /// Seconds since the Unix epoch.
+  pub due: u64,
+}
+
+impl Card {
+  fn new(word: &str, lang: &str, now: u64) -> Self {
+    Card {
+      word: word.to_string(),
+      lang: lang.to_string(),
+      definitions: vec![],
+      examples: vec![],
+      lookups: 0,
+      last_lookup: now,
+      repetitions: 0,
+      interval: 0,
+      ease: 2.5,
+      due: now,
+    }
+  }
+
+  /**
+   * Breakdown #5:
+   *
+   * - SM-2 (SuperMemo 2) grades every review from 0 (blackout) to 5
+   *   (perfect). Below 3 the card starts over, and is due again tomorrow.
+   * - Otherwise it's due after 1 day, then 6, then the previous interval
+   *   times the card's ease.
+   * - The ease goes up for easy answers and down for hard ones, so that
+   *   hard cards come back more often. Forgetting a card doesn't change its
+   *   ease, only restarts it.
+   */
+  pub fn review(&mut self, quality: u8, now: u64) {
+    let quality = quality.min(5);
+    if quality < 3 {
+      self.repetitions = 0;
+      self.interval = 1;
+    } else {
+      self.interval = match self.repetitions {
+        0 => 1,
+        1 => 6,
+        _ => (self.interval as f64 * self.ease).round() as u64,
+      };
+      self.repetitions += 1;
+      let miss = f64::from(5 - quality);
+      self.ease = (self.ease + 0.1 - miss * (0.08 + miss * 0.02)).max(1.3);
+    }
+    self.due = now + self.interval * DAY;
+  }
+
+  /// Keeps what `entries` say the word means.
+  fn remember(&mut self, entries: &[Entry]) {
+    self.definitions.clear();
+    self.examples.clear();
+    for entry in entries {
+      for lexical_entry in &entry.lexical_entries {
+        let category = lexical_entry.category.to_lowercase();
+        for sense in &lexical_entry.senses {
+          for definition in &sense.definitions {
+            if self.definitions.len() < DEFINITIONS {
+              self
+                .definitions
+                .push(format!("({}) {}", category, definition));
+            }
+          }
+          for example in &sense.examples {
+            if self.examples.len() < EXAMPLES {
+              self.examples.push(example.clone());
+            }
+          }
+        }
+      }
+    }
+  }
+}
+
+/**
+ * Every word looked up, kept as JSON in a single file.
+ */
+#[derive(Debug)]
+pub struct History {
+  path: PathBuf,
+  cards: Vec<Card>,
+}
+
+impl History {
+  /// The history at `path`, empty if there's no file yet.
+  pub fn open(path: &Path) -> io::Result<Self> {
+    let cards = match fs::read_to_string(path) {
+      Ok(text) => serde_json::from_str(&text)?,
+      Err(ref e) if e.kind() == io::ErrorKind::NotFound => vec![],
+      Err(e) => return Err(e),
+    };
+    Ok(History {
+      path: path.to_path_buf(),
+      cards,
+    })
+  }
+
+  pub fn cards(&self) -> &[Card] {
+    &self.cards
+  }
+
+  /// Counts a lookup of `word`, and keeps what it means for later review.
+  pub fn record(&mut self, word: &str, lang: &str, entries: &[Entry], now: u64) {
+    let word = word.trim().to_lowercase();
+    let index = match self
+      .cards
+      .iter()
+      .position(|c| c.word == word && c.lang == lang)
+    {
+      Some(index) => index,
+      None => {
+        self.cards.push(Card::new(&word, lang, now));
+        self.cards.len() - 1
+      }
+    };
+    let card = &mut self.cards[index];
+    card.lookups += 1;
+    card.last_lookup = now;
+    card.remember(entries);
+  }
+
+  /// Indexes of the cards due for review at `now`, most overdue first.
+  pub fn due(&self, now: u64) -> Vec<usize> {
+    let mut due: Vec<usize> = (0..self.cards.len())
+      .filter(|&i| self.cards[i].due <= now)
+      .collect();
+    due.sort_by_key(|&i| self.cards[i].due);
+    due
+  }
+
+  pub fn review(&mut self, index: usize, quality: u8, now: u64) {
+    self.cards[index].review(quality, now);
+  }
+
+  pub fn save(&self) -> io::Result<()> {
+    if let Some(dir) = self.path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string_pretty(&self.cards)?;
+    let partial = self.path.with_extension("json.partial");
+    fs::write(&partial, json)?;
+    fs::rename(&partial, &self.path)
+  }
+}
+
+/**
+ * A provider that records its successful lookups in a `History`.
+ */
+pub struct Recorded<P> {
+  provider: P,
+  history: Mutex<History>,
+}
+
+impl<P: DictionaryProvider> Recorded<P> {
+  pub fn new(provider: P, history: History) -> Self {
+    Recorded {
+      provider,
+      history: Mutex::new(history),
+    }
+  }
+}
+
+impl<P: DictionaryProvider> DictionaryProvider for Recorded<P> {
+  fn name(&self) -> &str {
+    self.provider.name()
+  }
+
+  fn lookup(&self, word: &str, lang: &str) -> Result<Vec<Entry>> {
+    let entries = self.provider.lookup(word, lang)?;
+    let mut history = self.history.lock().unwrap();
+    // Read again, so what other processes saved since, like reviews, isn't
+    // overwritten
+    match History::open(&history.path) {
+      Ok(latest) => *history = latest,
+      Err(e) => {
+        eprintln!("warning: could not record the lookup: {}", e);
+        return Ok(entries);
+      }
+    }
+    history.record(word, lang, &entries, now());
+    if let Err(e) = history.save() {
+      eprintln!("warning: could not save the history: {}", e);
+    }
+    Ok(entries)
+  }
+
+  fn words(&self, lang: &str) -> Vec<String> {
+    self.provider.words(lang)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::local::{parse_tsv, Local};
+  use crate::provider::{Error, ErrorKind};
+
+  const NOW: u64 = 1_600_000_000;
+
+  fn local() -> Local {
+    let words = "ace\tNoun\ta playing card\tthe ace of hearts | an ace up the sleeve\n\
+                 ace\tAdjective\tvery good\tan ace skier\n";
+    Local::new(parse_tsv(words, "en").unwrap())
+  }
+
+  #[test]
+  fn schedules_reviews_with_sm2() {
+    let mut card = Card::new("ace", "en", NOW);
+    card.review(5, NOW);
+    assert_eq!((card.repetitions, card.interval), (1, 1));
+    assert!((card.ease - 2.6).abs() < 1e-9);
+    card.review(4, NOW);
+    assert_eq!((card.repetitions, card.interval), (2, 6));
+    assert!((card.ease - 2.6).abs() < 1e-9);
+    card.review(3, NOW);
+    assert_eq!((card.repetitions, card.interval), (3, 16));
+    assert!((card.ease - 2.46).abs() < 1e-9);
+    assert_eq!(card.due, NOW + 16 * DAY);
+
+    // Forgotten: back to the start, as easy as it was
+    card.review(1, NOW);
+    assert_eq!((card.repetitions, card.interval), (0, 1));
+    assert_eq!(card.due, NOW + DAY);
+    assert!((card.ease - 2.46).abs() < 1e-9);
+    card.review(0, NOW);
+    assert!((card.ease - 2.46).abs() < 1e-9);
+
+    // Only hard answers make it harder, down to 1.3
+    for _ in 0..10 {
+      card.review(3, NOW);
+    }
+    assert!((card.ease - 1.3).abs() < 1e-9);
+  }
+
+  #[test]
+  fn records_lookups_as_cards() {
+    let mut history = History::open(Path::new("/nonexistent/history.json")).unwrap();
+    let entries = local().lookup("ace", "en").unwrap();
+    history.record("Ace", "en", &entries, NOW);
+    history.record("ace", "en", &entries, NOW + 60);
+    history.record("ace", "fr", &[], NOW + 120);
+
+    let card = &history.cards()[0];
+    assert_eq!(card.word, "ace");
+    assert_eq!(card.lookups, 2);
+    assert_eq!(card.last_lookup, NOW + 60);
+    assert_eq!(card.due, NOW);
+    assert_eq!(
+      card.definitions,
+      vec!["(noun) a playing card", "(adjective) very good"]
+    );
+    assert_eq!(
+      card.examples,
+      vec!["the ace of hearts", "an ace up the sleeve", "an ace skier"]
+    );
+    assert_eq!(history.cards().len(), 2);
+  }
+
+  #[test]
+  fn finds_due_cards_most_overdue_first() {
+    let mut history = History::open(Path::new("/nonexistent/history.json")).unwrap();
+    for (i, word) in ["ace", "bat", "cat"].iter().enumerate() {
+      history.record(word, "en", &[], NOW + i as u64);
+    }
+    history.review(0, 5, NOW);
+    assert_eq!(history.due(NOW + 10), vec![1, 2]);
+    assert_eq!(history.due(NOW + DAY), vec![1, 2, 0]);
+    assert_eq!(history.due(NOW - 1), Vec::<usize>::new());
+  }
+
+  #[test]
+  fn records_successful_lookups_only() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data/history.json");
+    let provider = Recorded::new(local(), History::open(&path).unwrap());
+    provider.lookup("ace", "en").unwrap();
+    let error: Error = provider.lookup("bat", "en").unwrap_err();
+    assert_eq!(error.kind(), &ErrorKind::NotFound);
+
+    let history = History::open(&path).unwrap();
+    assert_eq!(history.cards().len(), 1);
+    assert_eq!(history.cards()[0].word, "ace");
+  }
+
+  #[test]
+  fn keeps_what_others_saved_while_recording() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("history.json");
+    let provider = Recorded::new(local(), History::open(&path).unwrap());
+    provider.lookup("ace", "en").unwrap();
+
+    // Reviewed in another process, while this one runs
+    let mut other = History::open(&path).unwrap();
+    other.review(0, 5, NOW);
+    other.save().unwrap();
+
+    provider.lookup("ace", "en").unwrap();
+    let history = History::open(&path).unwrap();
+    let card = &history.cards()[0];
+    assert_eq!(card.lookups, 2);
+    assert_eq!((card.repetitions, card.due), (1, NOW + DAY));
+  }
+}
@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::provider::{DictionaryProvider, Entry, Error, ErrorKind, LexicalEntry, Result, Sense};
+
+/**
+ * A dictionary read from a file, for when there are no API keys or no
+ * network. Two formats are understood:
+ *
+ * - `.json`: an array of entries, as the cache stores them.
+ * - Anything else: tab-separated lines of `word`, `category`, `definition`,
+ *   then optionally `examples` (separated by `|`), `synonyms` (separated by
+ *   `,`), `etymology` and `antonyms` (separated by `,`). Every line is a
+ *   sense, and senses of the same word, whatever its case, make up one
+ *   entry; blank lines and lines starting with `#` are skipped.
+ *
+ * The file is read whole and indexed by word, so lookups don't touch it.
+ */
+#[derive(Debug)]
+pub struct Local {
+  /// Lowercased word to its entries.
+  index: HashMap<String, Vec<Entry>>,
+}
+
+impl Local {
+  /// Loads the dictionary at `path`. `language` is that of a TSV file,
+  /// which doesn't say.
+  pub fn open(path: &Path, language: &str) -> Result<Self> {
+    let text = fs::read_to_string(path).map_err(|e| {
+      Error::with_detail(ErrorKind::Unavailable, format!("{}: {}", path.display(), e))
+    })?;
+    let entries = if path.extension().is_some_and(|e| e == "json") {
+      serde_json::from_str(&text).map_err(|e| {
+        Error::with_detail(ErrorKind::Unavailable, format!("{}: {}", path.display(), e))
+      })?
+    } else {
+      parse_tsv(&text, language).map_err(|(line, message)| {
+        let detail = format!("{}, line {}: {}", path.display(), line, message);
+        Error::with_detail(ErrorKind::Unavailable, detail)
+      })?
+    };
+    Ok(Local::new(entries))
+  }
+
+  pub fn new(entries: Vec<Entry>) -> Self {
+    let mut index: HashMap<String, Vec<Entry>> = HashMap::new();
+    for entry in entries {
+      index
+        .entry(entry.word.to_lowercase())
+        .or_default()
+        .push(entry);
+    }
+    Local { index }
+  }
+}
+
+impl DictionaryProvider for Local {
+  fn name(&self) -> &str {
+    "local"
+  }
+
+  fn lookup(&self, word: &str, lang: &str) -> Result<Vec<Entry>> {
+    let entries: Vec<Entry> = self
+      .index
+      .get(&word.trim().to_lowercase())
+      .into_iter()
+      .flatten()
+      .filter(|entry| same_language(&entry.language, lang))
+      .cloned()
+      .collect();
+    if entries.is_empty() {
+      return Err(Error::new(ErrorKind::NotFound));
+    }
+    Ok(entries)
+  }
+
+  fn words(&self, lang: &str) -> Vec<String> {
+    let mut words: Vec<String> = self
+      .index
+      .iter()
+      .filter(|(_, entries)| entries.iter().any(|e| same_language(&e.language, lang)))
+      .map(|(word, _)| word.clone())
+      .collect();
+    words.sort_unstable();
+    words
+  }
+}
+
+/// Whether two language codes are the same language, e.g. `en` and `en-gb`.
+fn same_language(a: &str, b: &str) -> bool {
+  let primary = |code: &str| code.split(['-', '_']).next().unwrap_or("").to_lowercase();
+  primary(a) == primary(b)
+}
+
+/// Entries of a TSV dictionary, or the line number and reason it's invalid.
+pub fn parse_tsv(text: &str, language: &str) -> std::result::Result<Vec<Entry>, (usize, String)> {
+  let mut entries: Vec<Entry> = Vec::new();
+  // Lowercased word to the position of its entry, as `Local` indexes them
+  let mut positions: HashMap<String, usize> = HashMap::new();
+  for (i, line) in text.lines().enumerate() {
+    let line = line.trim_end_matches('\r');
+    if line.trim().is_empty() || line.starts_with('#') {
+      continue;
+    }
+    let columns: Vec<&str> = line.split('\t').map(str::trim).collect();
+    if columns.len() < 3 {
+      let message = format!("expected at least 3 columns, found {}", columns.len());
+      return Err((i + 1, message));
+    }
+    let (word, category, definition) = (columns[0], columns[1], columns[2]);
+    if word.is_empty() || definition.is_empty() {
+      return Err((i + 1, "the word and definition can't be empty".to_string()));
+    }
+    let split = |column: usize, separator: char| -> Vec<String> {
+      columns
+        .get(column)
+        .map(|text| {
+          text
+            .split(separator)
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(String::from)
+            .collect()
+        })
+        .unwrap_or_default()
+    };
+    let sense = Sense {
+      definitions: vec![definition.to_string()],
+      examples: split(3, '|'),
+      subsenses: vec![],
+      synonyms: split(4, ','),
+      antonyms: split(6, ','),
+    };
+    let etymology = columns.get(5).filter(|e| !e.is_empty());
+
+    let position = *positions.entry(word.to_lowercase()).or_insert_with(|| {
+      entries.push(Entry {
+        word: word.to_string(),
+        language: language.to_string(),
+        lexical_entries: vec![],
+      });
+      entries.len() - 1
+    });
+    let entry = &mut entries[position];
+    let position = entry
+      .lexical_entries
+      .iter()
+      .position(|e| e.category.eq_ignore_ascii_case(category));
+    let lexical_entry = match position {
+      Some(position) => &mut entry.lexical_entries[position],
+      None => {
+        entry.lexical_entries.push(LexicalEntry {
+          category: category.to_string(),
+          pronunciations: vec![],
+          senses: vec![],
+          etymologies: vec![],
+        });
+        entry.lexical_entries.last_mut().unwrap()
+      }
+    };
+    lexical_entry.senses.push(sense);
+    if let Some(etymology) = etymology {
+      if !lexical_entry.etymologies.iter().any(|e| e == etymology) {
+        lexical_entry.etymologies.push(etymology.to_string());
+      }
+    }
+  }
+  Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const WORDS: &str = "# word\tcategory\tdefinition\texamples\tsynonyms\tetymology\tantonyms
+ace\tNoun\ta playing card with a single spot\tthe ace of hearts\tone\tfrom Latin ‘as’
+ace\tnoun\tan expert\ta flying ace | an ace pilot\texpert, master\t\tamateur, novice
+ace\tAdjective\tvery good\t\texcellent
+
+Bat\tNoun\ta flying mammal
+bat\tVerb\tto hit with a bat
+";
+
+  fn local() -> Local {
+    Local::new(parse_tsv(WORDS, "en").unwrap())
+  }
+
+  #[test]
+  fn parses_tsv_lines_into_senses() {
+    let entries = local().lookup("ACE", "en").unwrap();
+    assert_eq!(entries.len(), 1);
+    let ace = &entries[0];
+    assert_eq!(ace.language, "en");
+    assert_eq!(ace.lexical_entries.len(), 2);
+
+    let noun = &ace.lexical_entries[0];
+    assert_eq!(noun.category, "Noun");
+    assert_eq!(noun.senses.len(), 2);
+    assert_eq!(noun.senses[0].examples, vec!["the ace of hearts"]);
+    assert_eq!(
+      noun.senses[1].examples,
+      vec!["a flying ace", "an ace pilot"]
+    );
+    assert_eq!(noun.senses[1].synonyms, vec!["expert", "master"]);
+    assert_eq!(noun.senses[1].antonyms, vec!["amateur", "novice"]);
+    assert!(noun.senses[0].antonyms.is_empty());
+    assert_eq!(noun.etymologies, vec!["from Latin ‘as’"]);
+
+    let adjective = &ace.lexical_entries[1];
+    assert_eq!(adjective.senses[0].definitions, vec!["very good"]);
+    assert!(adjective.senses[0].examples.is_empty());
+    assert_eq!(adjective.senses[0].synonyms, vec!["excellent"]);
+
+    // Lines of the same word in another case add to its entry
+    let entries = local().lookup("bat", "en").unwrap();
+    assert_eq!(entries.len(), 1);
+    let bat = &entries[0];
+    assert_eq!(bat.word, "Bat");
+    assert_eq!(bat.lexical_entries.len(), 2);
+    assert!(bat.lexical_entries[0].senses[0].synonyms.is_empty());
+    assert_eq!(bat.lexical_entries[1].category, "Verb");
+  }
+
+  #[test]
+  fn reports_invalid_lines() {
+    assert_eq!(
+      parse_tsv("ace\tNoun\tcard\nbat\tNoun\n", "en").unwrap_err(),
+      (2, "expected at least 3 columns, found 2".to_string())
+    );
+    assert_eq!(parse_tsv("\tNoun\tcard", "en").unwrap_err().0, 1);
+  }
+
+  #[test]
+  fn looks_up_by_word_and_language() {
+    let local = local();
+    assert_eq!(local.lookup(" Bat ", "en-gb").unwrap().len(), 1);
+    let error = local.lookup("bat", "fr").unwrap_err();
+    assert_eq!(error.kind(), &ErrorKind::NotFound);
+    let error = local.lookup("cat", "en").unwrap_err();
+    assert_eq!(error.kind(), &ErrorKind::NotFound);
+
+    assert_eq!(local.words("en"), vec!["ace", "bat"]);
+    assert!(local.words("fr").is_empty());
+  }
+
+  #[test]
+  fn opens_tsv_and_json_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let tsv = dir.path().join("words.tsv");
+    fs::write(&tsv, WORDS).unwrap();
+    let json = dir.path().join("words.json");
+    let entries = local().lookup("ace", "en").unwrap();
+    fs::write(&json, serde_json::to_string(&entries).unwrap()).unwrap();
+
+    let from_tsv = Local::open(&tsv, "en").unwrap();
+    assert_eq!(from_tsv.lookup("ace", "en").unwrap(), entries);
+    // The language of JSON entries is their own
+    let from_json = Local::open(&json, "fr").unwrap();
+    assert_eq!(from_json.lookup("ace", "en").unwrap(), entries);
+
+    let error = Local::open(&dir.path().join("missing.tsv"), "en").unwrap_err();
+    assert_eq!(error.kind(), &ErrorKind::Unavailable);
+    fs::write(&json, "[{").unwrap();
+    let error = Local::open(&json, "en").unwrap_err();
+    assert_eq!(error.kind(), &ErrorKind::Unavailable);
+  }
+
+  #[test]
+  fn opens_the_shipped_word_list() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/words.tsv");
+    let local = Local::open(&path, "en").unwrap();
+    let entries = local.lookup("cache", "en").unwrap();
+    assert_eq!(entries[0].lexical_entries.len(), 2);
+  }
+}
@@ -1,17 +1,221 @@
 use std::env;
+use std::error::Error;
+use std::fmt::Display;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use arguments::{Command, View};
+use cache::{Cache, CachedProvider};
+use config::{Config, Source};
+use history::{History, Recorded};
+use provider::DictionaryProvider;
+use suggest::Suggester;
 
 mod arguments;
+mod batch;
+mod cache;
 mod config;
+mod history;
+mod local;
+mod output;
+mod oxford;
+mod provider;
+mod repl;
+mod review;
+mod suggest;
 
 fn main() {
     let args = arguments::Arguments::new(env::args());
     match args {
         Ok(args) => run(args),
-        Err(e) => println!("{}", e.kind().as_str()),
+        Err(e) => println!("{}\n\n{}", e, arguments::USAGE),
     }
 }
 
 // Will run the console command, if proper number of args are passed
 fn run(args: arguments::Arguments) {
-    let config = config::Config::new();
+    let flags: Vec<(&str, &str)> = [("lang", args.lang()), ("provider", args.provider())]
+        .iter()
+        .filter_map(|(key, value)| value.map(|value| (*key, value)))
+        .collect();
+    let config = match Config::new(args.config().map(Path::new), &flags) {
+        Ok(config) => config,
+        Err(e) => fail(e),
+    };
+    let cache = Cache::from_config(&config);
+    match args.command() {
+        Command::Repl => match provider(&config, cache, &args) {
+            Ok(provider) => {
+                let lang = config.lang();
+                let session = repl::Session::new(provider, lang, View::Define, args.limit());
+                let history = config.data_dir().join("repl_history");
+                if let Err(e) = repl::run(session, &history) {
+                    println!("{}", e);
+                }
+            }
+            Err(e) => fail(e),
+        },
+        Command::Lookup { view, word } => match provider(&config, cache, &args) {
+            Ok(provider) => {
+                let lang = config.lang();
+                let mut suggester = Suggester::new();
+                lookup(&provider, &mut suggester, *view, word, lang, args.limit());
+            }
+            Err(e) => fail(e),
+        },
+        Command::Batch { file, format, jobs } => match provider(&config, cache, &args) {
+            Ok(provider) => {
+                let lang = config.lang();
+                let words = match file {
+                    Some(file) => {
+                        fs::File::open(file).and_then(|f| batch::read_words(io::BufReader::new(f)))
+                    }
+                    None => batch::read_words(io::stdin().lock()),
+                };
+                match words {
+                    Ok(words) => {
+                        let outcomes = batch::lookup_all(&provider, &words, lang, *jobs);
+                        for outcome in &outcomes {
+                            if let Err(e) = &outcome.result {
+                                eprintln!("{}: {}", outcome.word, e);
+                            }
+                        }
+                        print!("{}", batch::render(*format, &outcomes, args.limit()));
+                    }
+                    Err(e) => fail(format!("Could not read the words: {}", e)),
+                }
+            }
+            Err(e) => fail(e),
+        },
+        Command::Review => match History::open(&history_path(&config)) {
+            Ok(mut history) => {
+                let stdin = io::stdin();
+                let now = cache::now();
+                if let Err(e) =
+                    review::review(&mut history, now, args.limit(), stdin.lock(), io::stdout())
+                {
+                    println!("{}", e);
+                }
+            }
+            Err(e) => println!("Could not read the history: {}", e),
+        },
+        Command::Export { file } => match History::open(&history_path(&config)) {
+            Ok(history) => {
+                let deck = review::anki(&history);
+                match file {
+                    Some(file) => match fs::write(file, deck) {
+                        Ok(()) => println!("Exported {} words to {}", history.cards().len(), file),
+                        Err(e) => println!("Could not write {}: {}", file, e),
+                    },
+                    None => print!("{}", deck),
+                }
+            }
+            Err(e) => println!("Could not read the history: {}", e),
+        },
+        Command::ConfigShow => print!("{}", config.show()),
+        Command::CacheStats => match cache.stats() {
+            Ok(stats) => println!(
+                "{} entries ({} stale), {:.1} KiB in {}",
+                stats.entries,
+                stats.stale,
+                stats.bytes as f64 / 1024.0,
+                cache.dir().display()
+            ),
+            Err(e) => println!("Could not read the cache: {}", e),
+        },
+        Command::CacheClear => match cache.clear() {
+            Ok(count) => println!("Removed {} entries from {}", count, cache.dir().display()),
+            Err(e) => println!("Could not clear the cache: {}", e),
+        },
+    }
+}
+
+/// Reports an error that stops the command before it got anywhere, and
+/// exits with a failure status so scripts can tell.
+fn fail(error: impl Display) -> ! {
+    eprintln!("{}", error);
+    process::exit(1)
+}
+
+/// Any provider, shareable between the threads of a batch.
+type Provider = Box<dyn DictionaryProvider + Send + Sync>;
+
+/// The provider `config` asks for, recording lookups in the history unless
+/// `args` run a batch. Only the Oxford API is cached, since the local
+/// dictionary is on disk already.
+fn provider(
+    config: &Config,
+    cache: Cache,
+    args: &arguments::Arguments,
+) -> Result<Provider, Box<dyn Error>> {
+    let offline = args.offline();
+    let provider: Provider = match config.source() {
+        Source::Oxford => {
+            // Offline, the API is never asked, so it can do without credentials
+            let oxford = match oxford::Oxford::from_config(config) {
+                Err(_) if offline => oxford::Oxford::new(config.api_url(), "", ""),
+                Err(e) if e.kind() == &config::ErrorKind::MissingCredentials => {
+                    return Err(format!("{}, or `--provider local` to do without", e).into());
+                }
+                oxford => oxford?,
+            };
+            Box::new(CachedProvider::new(oxford, cache, offline))
+        }
+        Source::Local => {
+            let local = local::Local::open(config.dictionary_file(), config.lang())?;
+            Box::new(local)
+        }
+    };
+    // A glossary's words weren't looked up to be learned, and aren't worth
+    // rewriting the history for after every one of them
+    if let Command::Batch { .. } = args.command() {
+        return Ok(provider);
+    }
+    match History::open(&history_path(config)) {
+        Ok(history) => Ok(Box::new(Recorded::new(provider, history))),
+        Err(e) => {
+            eprintln!("warning: lookups won't be recorded: {}", e);
+            Ok(provider)
+        }
+    }
+}
+
+/// Where the words looked up are kept for review.
+fn history_path(config: &Config) -> PathBuf {
+    config.data_dir().join("history.json")
+}
+
+fn lookup(
+    provider: &dyn DictionaryProvider,
+    suggester: &mut Suggester,
+    view: View,
+    word: &str,
+    lang: &str,
+    limit: Option<usize>,
+) {
+    let answer = output::lookup(provider, suggester, view, word, lang, limit);
+    print!("{}", answer.text);
+    if let Some(pick) = pick(&answer.suggestions) {
+        println!();
+        lookup(provider, suggester, view, &pick, lang, limit);
+    }
+}
+
+/// Asks which of `suggestions` to look up instead, when there's someone at
+/// the terminal to answer.
+fn pick(suggestions: &[String]) -> Option<String> {
+    if suggestions.is_empty() || !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return None;
+    }
+    print!(
+        "Look up which one? [1-{}, Enter to skip] ",
+        suggestions.len()
+    );
+    io::stdout().flush().ok()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).ok()?;
+    let choice: usize = answer.trim().parse().ok()?;
+    suggestions.get(choice.checked_sub(1)?).cloned()
 }
@@ -0,0 +1,349 @@
+use std::fmt::Write;
+
+use crate::arguments::View;
+use crate::provider::{DictionaryProvider, Entry, ErrorKind, Sense};
+use crate::suggest::Suggester;
+
+/// How many spellings to suggest for a word that isn't found.
+const SUGGESTIONS: usize = 5;
+
+/// What a lookup comes to, ready to print.
+pub struct Answer {
+  pub text: String,
+  /// Whether the word was found, even if without anything to show.
+  pub found: bool,
+  /// Spellings to offer, if it wasn't.
+  pub suggestions: Vec<String>,
+}
+
+/// Looks `word` up and renders what `view` shows of it, or why it couldn't
+/// be, with suggestions from `suggester` if it wasn't found.
+pub fn lookup<P>(
+  provider: &P,
+  suggester: &mut Suggester,
+  view: View,
+  word: &str,
+  lang: &str,
+  limit: Option<usize>,
+) -> Answer
+where
+  P: DictionaryProvider + ?Sized,
+{
+  let error = match provider.lookup(word, lang) {
+    Ok(entries) => {
+      return Answer {
+        text: render(view, word, &entries, limit),
+        found: true,
+        suggestions: vec![],
+      }
+    }
+    Err(e) => e,
+  };
+  let mut text = match error.kind() {
+    ErrorKind::NotFound => format!("No entry found for \"{}\"\n", word),
+    _ => format!("{} ({}): {}\n", word, provider.name(), error),
+  };
+  let suggestions = match error.kind() {
+    ErrorKind::NotFound | ErrorKind::NotCached => {
+      suggester.suggestions(provider, word, lang, SUGGESTIONS)
+    }
+    _ => vec![],
+  };
+  if !suggestions.is_empty() {
+    text.push_str(&self::suggestions(&suggestions));
+  }
+  Answer {
+    text,
+    found: false,
+    suggestions,
+  }
+}
+
+/**
+ * Renders what `view` shows of `entries` for the terminal, with at most
+ * `limit` senses, synonyms, antonyms, examples or etymologies.
+ */
+pub fn render(view: View, word: &str, entries: &[Entry], limit: Option<usize>) -> String {
+  let limit = limit.unwrap_or(usize::MAX);
+  let text = match view {
+    View::Define => define(entries, limit),
+    View::Synonyms => list(entries, limit, |sense| &sense.synonyms),
+    View::Antonyms => list(entries, limit, |sense| &sense.antonyms),
+    View::Examples => list(entries, limit, |sense| &sense.examples),
+    View::Etymology => etymology(entries, limit),
+  };
+  if text.is_empty() {
+    format!("No {} found for \"{}\"\n", view.as_str(), word)
+  } else {
+    text
+  }
+}
+
+/// Numbered `suggestions`, for a word that wasn't found.
+pub fn suggestions(suggestions: &[String]) -> String {
+  let mut text = String::from("Did you mean:\n");
+  for (i, suggestion) in suggestions.iter().enumerate() {
+    writeln!(text, "  {}. {}", i + 1, suggestion).unwrap();
+  }
+  text
+}
+
+fn define(entries: &[Entry], limit: usize) -> String {
+  let mut text = String::new();
+  let mut count = 0;
+  for entry in entries {
+    for lexical_entry in &entry.lexical_entries {
+      if count == limit {
+        return text;
+      }
+      let spellings: Vec<String> = lexical_entry
+        .pronunciations
+        .iter()
+        .filter_map(|p| p.spelling.as_ref())
+        .map(|spelling| format!("/{}/", spelling))
+        .collect();
+      let heading = format!(
+        "{} ({}) {}",
+        entry.word,
+        lexical_entry.category.to_lowercase(),
+        spellings.join(" ")
+      );
+      if !text.is_empty() {
+        text.push('\n');
+      }
+      writeln!(text, "{}", heading.trim_end()).unwrap();
+      for (i, sense) in lexical_entry.senses.iter().take(limit - count).enumerate() {
+        define_sense(&mut text, sense, &format!("{}.", i + 1), 1);
+        count += 1;
+      }
+    }
+  }
+  text
+}
+
+fn define_sense(text: &mut String, sense: &Sense, label: &str, depth: usize) {
+  let indent = "  ".repeat(depth);
+  for definition in &sense.definitions {
+    writeln!(text, "{}{} {}", indent, label, definition).unwrap();
+  }
+  for example in &sense.examples {
+    writeln!(text, "{}   \"{}\"", indent, example).unwrap();
+  }
+  for subsense in &sense.subsenses {
+    define_sense(text, subsense, "-", depth + 1);
+  }
+}
+
+/// What `field` holds across every sense and subsense, once each, as a line
+/// per part of speech.
+fn list<F>(entries: &[Entry], limit: usize, field: F) -> String
+where
+  F: Fn(&Sense) -> &Vec<String>,
+{
+  let mut text = String::new();
+  let mut count = 0;
+  for entry in entries {
+    for lexical_entry in &entry.lexical_entries {
+      let mut items = Vec::new();
+      collect(&lexical_entry.senses, &field, &mut items);
+      items.truncate(limit - count);
+      if items.is_empty() {
+        continue;
+      }
+      count += items.len();
+      writeln!(
+        text,
+        "{} ({})",
+        entry.word,
+        lexical_entry.category.to_lowercase()
+      )
+      .unwrap();
+      for item in items {
+        writeln!(text, "  - {}", item).unwrap();
+      }
+    }
+  }
+  text
+}
+
+fn collect<'a, F>(senses: &'a [Sense], field: &F, items: &mut Vec<&'a str>)
+where
+  F: Fn(&Sense) -> &Vec<String>,
+{
+  for sense in senses {
+    for item in field(sense) {
+      if !items.contains(&item.as_str()) {
+        items.push(item);
+      }
+    }
+    collect(&sense.subsenses, field, items);
+  }
+}
+
+fn etymology(entries: &[Entry], limit: usize) -> String {
+  let mut etymologies: Vec<(&str, &str)> = Vec::new();
+  for entry in entries {
+    for lexical_entry in &entry.lexical_entries {
+      for etymology in &lexical_entry.etymologies {
+        // Shared by every part of speech of the same word
+        if !etymologies.iter().any(|(_, e)| e == etymology) {
+          etymologies.push((&entry.word, etymology));
+        }
+      }
+    }
+  }
+  let mut text = String::new();
+  for (word, etymology) in etymologies.into_iter().take(limit) {
+    writeln!(text, "{}: {}", word, etymology).unwrap();
+  }
+  text
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::provider::{LexicalEntry, Pronunciation};
+
+  fn sense(definition: &str, examples: &[&str], synonyms: &[&str], subsenses: Vec<Sense>) -> Sense {
+    Sense {
+      definitions: vec![definition.to_string()],
+      examples: examples.iter().map(|e| e.to_string()).collect(),
+      subsenses,
+      synonyms: synonyms.iter().map(|s| s.to_string()).collect(),
+      antonyms: vec![],
+    }
+  }
+
+  fn ace() -> Vec<Entry> {
+    vec![Entry {
+      word: "ace".to_string(),
+      language: "en".to_string(),
+      lexical_entries: vec![
+        LexicalEntry {
+          category: "Noun".to_string(),
+          pronunciations: vec![Pronunciation {
+            notation: Some("IPA".to_string()),
+            spelling: Some("eɪs".to_string()),
+            dialects: vec![],
+            audio_url: None,
+          }],
+          senses: vec![
+            sense(
+              "a playing card with a single spot",
+              &["the ace of hearts"],
+              &["one"],
+              vec![sense(
+                "an expert",
+                &["a flying ace"],
+                &["expert", "one"],
+                vec![],
+              )],
+            ),
+            sense("a serve that can't be returned", &[], &[], vec![]),
+          ],
+          etymologies: vec!["from Latin as ‘unity’".to_string()],
+        },
+        LexicalEntry {
+          category: "Adjective".to_string(),
+          pronunciations: vec![],
+          senses: vec![sense(
+            "very good",
+            &["an ace skier"],
+            &["excellent"],
+            vec![],
+          )],
+          etymologies: vec!["from Latin as ‘unity’".to_string()],
+        },
+      ],
+    }]
+  }
+
+  #[test]
+  fn renders_definitions() {
+    assert_eq!(
+      render(View::Define, "ace", &ace(), None),
+      "ace (noun) /eɪs/\n\
+       \x20 1. a playing card with a single spot\n\
+       \x20    \"the ace of hearts\"\n\
+       \x20   - an expert\n\
+       \x20      \"a flying ace\"\n\
+       \x20 2. a serve that can't be returned\n\
+       \n\
+       ace (adjective)\n\
+       \x20 1. very good\n\
+       \x20    \"an ace skier\"\n"
+    );
+    assert_eq!(
+      render(View::Define, "ace", &ace(), Some(1)),
+      "ace (noun) /eɪs/\n\
+       \x20 1. a playing card with a single spot\n\
+       \x20    \"the ace of hearts\"\n\
+       \x20   - an expert\n\
+       \x20      \"a flying ace\"\n"
+    );
+  }
+
+  #[test]
+  fn renders_synonyms_and_examples() {
+    assert_eq!(
+      render(View::Synonyms, "ace", &ace(), None),
+      "ace (noun)\n  - one\n  - expert\nace (adjective)\n  - excellent\n"
+    );
+    assert_eq!(
+      render(View::Synonyms, "ace", &ace(), Some(2)),
+      "ace (noun)\n  - one\n  - expert\n"
+    );
+    assert_eq!(
+      render(View::Examples, "ace", &ace(), Some(3)),
+      "ace (noun)\n  - the ace of hearts\n  - a flying ace\nace (adjective)\n  - an ace skier\n"
+    );
+  }
+
+  #[test]
+  fn renders_antonyms() {
+    let mut entries = ace();
+    entries[0].lexical_entries[1].senses[0].antonyms = vec!["poor".to_string()];
+    assert_eq!(
+      render(View::Antonyms, "ace", &entries, None),
+      "ace (adjective)\n  - poor\n"
+    );
+    assert_eq!(
+      render(View::Antonyms, "ace", &ace(), None),
+      "No antonyms found for \"ace\"\n"
+    );
+  }
+
+  #[test]
+  fn renders_etymologies() {
+    assert_eq!(
+      render(View::Etymology, "ace", &ace(), None),
+      "ace: from Latin as ‘unity’\n"
+    );
+  }
+
+  #[test]
+  fn says_when_there_is_nothing_to_show() {
+    let mut entries = ace();
+    entries[0]
+      .lexical_entries
+      .iter_mut()
+      .for_each(|e| e.etymologies.clear());
+    assert_eq!(
+      render(View::Etymology, "ace", &entries, None),
+      "No etymology found for \"ace\"\n"
+    );
+    assert_eq!(
+      render(View::Synonyms, "ace", &[], None),
+      "No synonyms found for \"ace\"\n"
+    );
+  }
+
+  #[test]
+  fn numbers_suggestions() {
+    let suggestions = vec!["boo".to_string(), "book".to_string()];
+    assert_eq!(
+      super::suggestions(&suggestions),
+      "Did you mean:\n  1. boo\n  2. book\n"
+    );
+  }
+}
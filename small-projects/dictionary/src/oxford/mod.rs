@@ -0,0 +1,251 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::config::{self, Config};
+use crate::provider::{
+  DictionaryProvider, Entry, Error, ErrorKind, LexicalEntry, Pronunciation, Result, Sense,
+};
+
+#[cfg(test)]
+mod tests;
+
+/**
+ * Client for the entries endpoint of the Oxford Dictionaries API (v2).
+ */
+pub struct Oxford {
+  api_url: String,
+  app_id: String,
+  api_key: String,
+  agent: ureq::Agent,
+}
+
+impl Oxford {
+  pub fn new(api_url: &str, app_id: &str, api_key: &str) -> Self {
+    Oxford {
+      api_url: api_url.trim_end_matches('/').to_string(),
+      app_id: app_id.to_string(),
+      api_key: api_key.to_string(),
+      agent: ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(15))
+        .build(),
+    }
+  }
+
+  /// The API `config` points at, if it has credentials for it.
+  pub fn from_config(config: &Config) -> config::Result<Self> {
+    let (app_id, api_key) = config.credentials()?;
+    Ok(Oxford::new(config.api_url(), app_id, api_key))
+  }
+
+  /// Address of the entries for `word`, e.g. `.../entries/en-gb/ice_cream`.
+  fn url(&self, word: &str, lang: &str) -> String {
+    let word_id = word.trim().to_lowercase().replace(' ', "_");
+    format!("{}/{}/{}", self.api_url, encode(lang), encode(&word_id))
+  }
+}
+
+impl DictionaryProvider for Oxford {
+  fn name(&self) -> &str {
+    "oxford"
+  }
+
+  fn lookup(&self, word: &str, lang: &str) -> Result<Vec<Entry>> {
+    if self.app_id.is_empty() || self.api_key.is_empty() {
+      return Err(Error::new(ErrorKind::Unauthorized));
+    }
+
+    let response = self
+      .agent
+      .get(&self.url(word, lang))
+      .set("Accept", "application/json")
+      .set("app_id", &self.app_id)
+      .set("app_key", &self.api_key)
+      .query("strictMatch", "false")
+      .call();
+    let body = match response {
+      Ok(response) => response
+        .into_string()
+        .map_err(|e| Error::with_detail(ErrorKind::Network, e.to_string()))?,
+      Err(ureq::Error::Status(404, _)) => return Err(Error::new(ErrorKind::NotFound)),
+      Err(ureq::Error::Status(401, _)) | Err(ureq::Error::Status(403, _)) => {
+        return Err(Error::new(ErrorKind::Unauthorized))
+      }
+      Err(ureq::Error::Status(status, _)) => {
+        return Err(Error::with_detail(
+          ErrorKind::Status(status),
+          status.to_string(),
+        ))
+      }
+      Err(ureq::Error::Transport(e)) => {
+        return Err(Error::with_detail(ErrorKind::Network, e.to_string()))
+      }
+    };
+
+    let response: RawResponse = serde_json::from_str(&body)
+      .map_err(|e| Error::with_detail(ErrorKind::InvalidResponse, e.to_string()))?;
+    let entries: Vec<Entry> = response.results.into_iter().map(Entry::from).collect();
+    if entries.is_empty() {
+      return Err(Error::new(ErrorKind::NotFound));
+    }
+    Ok(entries)
+  }
+}
+
+/// Percent-encodes `segment` for use as a single path segment.
+fn encode(segment: &str) -> String {
+  let mut encoded = String::new();
+  for byte in segment.bytes() {
+    match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+        encoded.push(byte as char)
+      }
+      _ => encoded.push_str(&format!("%{:02X}", byte)),
+    }
+  }
+  encoded
+}
+
+/**
+ * Breakdown #2:
+ *
+ * - The API nests pronunciations and senses a level deeper than we want
+ *   them: `results[].lexicalEntries[].entries[].senses[]`. We deserialize
+ *   into these `Raw*` structs that mirror the JSON, and then flatten them
+ *   into the types of `provider`, which is all the rest of the crate sees.
+ * - `#[serde(default)]` makes missing arrays empty, since the API leaves
+ *   out whatever it has nothing for.
+ */
+#[derive(Deserialize)]
+struct RawResponse {
+  #[serde(default)]
+  results: Vec<RawHeadword>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawHeadword {
+  word: String,
+  language: String,
+  #[serde(default)]
+  lexical_entries: Vec<RawLexicalEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawLexicalEntry {
+  lexical_category: RawCategory,
+  #[serde(default)]
+  entries: Vec<RawEntry>,
+  #[serde(default)]
+  pronunciations: Vec<RawPronunciation>,
+}
+
+#[derive(Deserialize)]
+struct RawCategory {
+  text: String,
+}
+
+#[derive(Deserialize)]
+struct RawEntry {
+  #[serde(default)]
+  etymologies: Vec<String>,
+  #[serde(default)]
+  pronunciations: Vec<RawPronunciation>,
+  #[serde(default)]
+  senses: Vec<RawSense>,
+}
+
+#[derive(Deserialize)]
+struct RawSense {
+  #[serde(default)]
+  definitions: Vec<String>,
+  #[serde(default)]
+  examples: Vec<RawText>,
+  #[serde(default)]
+  subsenses: Vec<RawSense>,
+  #[serde(default)]
+  synonyms: Vec<RawText>,
+  #[serde(default)]
+  antonyms: Vec<RawText>,
+}
+
+/// An example, a synonym or an antonym, which come with more than their text.
+#[derive(Deserialize)]
+struct RawText {
+  text: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawPronunciation {
+  audio_file: Option<String>,
+  #[serde(default)]
+  dialects: Vec<String>,
+  phonetic_notation: Option<String>,
+  phonetic_spelling: Option<String>,
+}
+
+impl From<RawHeadword> for Entry {
+  fn from(raw: RawHeadword) -> Self {
+    Entry {
+      word: raw.word,
+      language: raw.language,
+      lexical_entries: raw
+        .lexical_entries
+        .into_iter()
+        .map(LexicalEntry::from)
+        .collect(),
+    }
+  }
+}
+
+impl From<RawLexicalEntry> for LexicalEntry {
+  fn from(raw: RawLexicalEntry) -> Self {
+    let mut pronunciations: Vec<Pronunciation> = raw
+      .pronunciations
+      .into_iter()
+      .map(Pronunciation::from)
+      .collect();
+    let mut senses = Vec::new();
+    let mut etymologies = Vec::new();
+    for entry in raw.entries {
+      for pronunciation in entry.pronunciations.into_iter().map(Pronunciation::from) {
+        if !pronunciations.contains(&pronunciation) {
+          pronunciations.push(pronunciation);
+        }
+      }
+      senses.extend(entry.senses.into_iter().map(Sense::from));
+      etymologies.extend(entry.etymologies);
+    }
+    LexicalEntry {
+      category: raw.lexical_category.text,
+      pronunciations,
+      senses,
+      etymologies,
+    }
+  }
+}
+
+impl From<RawSense> for Sense {
+  fn from(raw: RawSense) -> Self {
+    Sense {
+      definitions: raw.definitions,
+      examples: raw.examples.into_iter().map(|e| e.text).collect(),
+      subsenses: raw.subsenses.into_iter().map(Sense::from).collect(),
+      synonyms: raw.synonyms.into_iter().map(|s| s.text).collect(),
+      antonyms: raw.antonyms.into_iter().map(|a| a.text).collect(),
+    }
+  }
+}
+
+impl From<RawPronunciation> for Pronunciation {
+  fn from(raw: RawPronunciation) -> Self {
+    Pronunciation {
+      notation: raw.phonetic_notation,
+      spelling: raw.phonetic_spelling,
+      dialects: raw.dialects,
+      audio_url: raw.audio_file,
+    }
+  }
+}
@@ -0,0 +1,202 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use super::*;
+
+const ACE: &str = r#"{
+  "id": "ace",
+  "metadata": { "provider": "Oxford University Press" },
+  "results": [{
+    "id": "ace",
+    "language": "en-gb",
+    "type": "headword",
+    "word": "ace",
+    "lexicalEntries": [{
+      "language": "en-gb",
+      "lexicalCategory": { "id": "noun", "text": "Noun" },
+      "text": "ace",
+      "entries": [{
+        "etymologies": ["Middle English (denoting the ‘one’ on dice), from Latin as ‘unity’"],
+        "pronunciations": [{
+          "audioFile": "https://audio.example/ace.mp3",
+          "dialects": ["British English"],
+          "phoneticNotation": "IPA",
+          "phoneticSpelling": "eɪs"
+        }],
+        "senses": [{
+          "id": "m_en_gbus0005680.006",
+          "definitions": ["a playing card with a single spot on it"],
+          "examples": [{ "text": "the ace of diamonds" }],
+          "subsenses": [{
+            "definitions": ["a person who excels at a particular activity"],
+            "examples": [{ "text": "a motorcycle ace" }],
+            "synonyms": [
+              { "language": "en", "text": "expert" },
+              { "language": "en", "text": "master" }
+            ],
+            "antonyms": [{ "language": "en", "text": "amateur" }]
+          }]
+        }, {
+          "definitions": ["(in tennis) a service that an opponent is unable to return"]
+        }]
+      }]
+    }, {
+      "language": "en-gb",
+      "lexicalCategory": { "id": "adjective", "text": "Adjective" },
+      "text": "ace",
+      "pronunciations": [{ "phoneticNotation": "IPA", "phoneticSpelling": "eɪs" }],
+      "entries": [{ "senses": [{ "definitions": ["very good"] }] }]
+    }]
+  }],
+  "word": "ace"
+}"#;
+
+/// Serves a single request with `status` and `body`, and sends the head of
+/// the request it got (request line and headers) down the returned channel.
+fn serve_once(status: &str, body: &'static str) -> (String, Receiver<String>) {
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let url = format!("http://{}/api/v2/entries", listener.local_addr().unwrap());
+  let status = status.to_string();
+  let (sender, receiver) = mpsc::channel();
+
+  thread::spawn(move || {
+    let (stream, _) = listener.accept().unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut head = String::new();
+    loop {
+      let mut line = String::new();
+      reader.read_line(&mut line).unwrap();
+      if line.trim_end().is_empty() {
+        break;
+      }
+      head.push_str(&line);
+    }
+    let response = format!(
+      "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+      status,
+      body.len(),
+      body
+    );
+    reader.get_mut().write_all(response.as_bytes()).unwrap();
+    sender.send(head).unwrap();
+  });
+  (url, receiver)
+}
+
+fn oxford(url: &str) -> Oxford {
+  Oxford::new(url, "my-id", "my-key")
+}
+
+#[test]
+fn sends_the_word_language_and_credentials() {
+  let (url, head) = serve_once("200 OK", ACE);
+  oxford(&url).lookup("Ice Cream", "en-gb").unwrap();
+
+  let head = head.recv().unwrap();
+  let request_line = head.lines().next().unwrap();
+  assert_eq!(
+    request_line,
+    "GET /api/v2/entries/en-gb/ice_cream?strictMatch=false HTTP/1.1"
+  );
+  let headers: Vec<String> = head.lines().skip(1).map(|l| l.to_lowercase()).collect();
+  assert!(headers.contains(&"app_id: my-id".to_string()), "{}", head);
+  assert!(headers.contains(&"app_key: my-key".to_string()), "{}", head);
+}
+
+#[test]
+fn parses_entries() {
+  let (url, _) = serve_once("200 OK", ACE);
+  let entries = oxford(&url).lookup("ace", "en-gb").unwrap();
+
+  assert_eq!(entries.len(), 1);
+  let entry = &entries[0];
+  assert_eq!(entry.word, "ace");
+  assert_eq!(entry.language, "en-gb");
+  assert_eq!(entry.lexical_entries.len(), 2);
+
+  let noun = &entry.lexical_entries[0];
+  assert_eq!(noun.category, "Noun");
+  assert_eq!(
+    noun.pronunciations,
+    vec![Pronunciation {
+      notation: Some("IPA".to_string()),
+      spelling: Some("eɪs".to_string()),
+      dialects: vec!["British English".to_string()],
+      audio_url: Some("https://audio.example/ace.mp3".to_string()),
+    }]
+  );
+  assert_eq!(noun.senses.len(), 2);
+  assert_eq!(
+    noun.senses[0],
+    Sense {
+      definitions: vec!["a playing card with a single spot on it".to_string()],
+      examples: vec!["the ace of diamonds".to_string()],
+      subsenses: vec![Sense {
+        definitions: vec!["a person who excels at a particular activity".to_string()],
+        examples: vec!["a motorcycle ace".to_string()],
+        subsenses: vec![],
+        synonyms: vec!["expert".to_string(), "master".to_string()],
+        antonyms: vec!["amateur".to_string()],
+      }],
+      synonyms: vec![],
+      antonyms: vec![],
+    }
+  );
+  assert_eq!(
+    noun.etymologies,
+    vec!["Middle English (denoting the ‘one’ on dice), from Latin as ‘unity’"]
+  );
+  assert!(noun.senses[1].examples.is_empty());
+
+  let adjective = &entry.lexical_entries[1];
+  assert_eq!(adjective.category, "Adjective");
+  assert_eq!(adjective.pronunciations[0].spelling.as_deref(), Some("eɪs"));
+  assert_eq!(adjective.pronunciations[0].audio_url, None);
+  assert_eq!(adjective.senses[0].definitions, vec!["very good"]);
+  assert!(adjective.etymologies.is_empty());
+}
+
+#[test]
+fn reports_errors_by_kind() {
+  let cases = [
+    (
+      "404 Not Found",
+      r#"{"error": "No entry found"}"#,
+      ErrorKind::NotFound,
+    ),
+    ("200 OK", r#"{"results": []}"#, ErrorKind::NotFound),
+    (
+      "403 Forbidden",
+      r#"{"error": "Authentication failed"}"#,
+      ErrorKind::Unauthorized,
+    ),
+    ("500 Internal Server Error", "{}", ErrorKind::Status(500)),
+    ("200 OK", "<html>", ErrorKind::InvalidResponse),
+  ];
+  for (status, body, kind) in cases.iter() {
+    let (url, _) = serve_once(status, body);
+    let error = oxford(&url).lookup("ace", "en-gb").unwrap_err();
+    assert_eq!(error.kind(), kind, "{}", status);
+  }
+}
+
+#[test]
+fn needs_credentials() {
+  let error = Oxford::new("http://127.0.0.1:9", "", "")
+    .lookup("ace", "en")
+    .unwrap_err();
+  assert_eq!(error.kind(), &ErrorKind::Unauthorized);
+}
+
+#[test]
+fn reports_unreachable_servers() {
+  // Nothing listens once the listener is dropped
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let url = format!("http://{}", listener.local_addr().unwrap());
+  drop(listener);
+
+  let error = oxford(&url).lookup("ace", "en").unwrap_err();
+  assert_eq!(error.kind(), &ErrorKind::Network);
+}
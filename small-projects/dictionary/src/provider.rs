@@ -0,0 +1,145 @@
+use std::{fmt, result};
+
+use serde::{Deserialize, Serialize};
+
+pub type Result<T> = result::Result<T, Error>;
+
+/**
+ * Anything that can look words up, e.g. the Oxford Dictionaries API.
+ */
+pub trait DictionaryProvider {
+  /// Name shown to the user, e.g. in error messages.
+  fn name(&self) -> &str;
+
+  /// Every entry for `word` in the language `lang` (like `en` or `es`).
+  fn lookup(&self, word: &str, lang: &str) -> Result<Vec<Entry>>;
+
+  /// Words known in `lang` without asking anyone, to suggest when a lookup
+  /// finds nothing.
+  fn words(&self, _lang: &str) -> Vec<String> {
+    Vec::new()
+  }
+}
+
+impl<P: DictionaryProvider + ?Sized> DictionaryProvider for Box<P> {
+  fn name(&self) -> &str {
+    (**self).name()
+  }
+
+  fn lookup(&self, word: &str, lang: &str) -> Result<Vec<Entry>> {
+    (**self).lookup(word, lang)
+  }
+
+  fn words(&self, lang: &str) -> Vec<String> {
+    (**self).words(lang)
+  }
+}
+
+/// A headword, with its meanings grouped by part of speech.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+  pub word: String,
+  pub language: String,
+  pub lexical_entries: Vec<LexicalEntry>,
+}
+
+/// The word used as one part of speech (its lexical category).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LexicalEntry {
+  /// Like "Noun" or "Verb".
+  pub category: String,
+  pub pronunciations: Vec<Pronunciation>,
+  pub senses: Vec<Sense>,
+  /// Where the word comes from. Missing from entries cached before it was.
+  #[serde(default)]
+  pub etymologies: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sense {
+  pub definitions: Vec<String>,
+  pub examples: Vec<String>,
+  /// Narrower meanings of this sense.
+  pub subsenses: Vec<Sense>,
+  #[serde(default)]
+  pub synonyms: Vec<String>,
+  #[serde(default)]
+  pub antonyms: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pronunciation {
+  /// Like "IPA".
+  pub notation: Option<String>,
+  pub spelling: Option<String>,
+  /// Like "British English".
+  pub dialects: Vec<String>,
+  pub audio_url: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Error {
+  kind: ErrorKind,
+  detail: Option<String>,
+}
+
+impl Error {
+  pub fn new(kind: ErrorKind) -> Self {
+    Error { kind, detail: None }
+  }
+
+  /// Error with more to say than its kind, like the reason a request failed.
+  pub fn with_detail(kind: ErrorKind, detail: impl Into<String>) -> Self {
+    Error {
+      kind,
+      detail: Some(detail.into()),
+    }
+  }
+
+  pub fn kind(&self) -> &ErrorKind {
+    &self.kind
+  }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match &self.detail {
+      Some(detail) => write!(f, "{}: {}", self.kind.as_str(), detail),
+      None => write!(f, "{}", self.kind.as_str()),
+    }
+  }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Debug, PartialEq)]
+pub enum ErrorKind {
+  /// The dictionary has no entry for the word.
+  NotFound,
+  /// The credentials were missing or rejected.
+  Unauthorized,
+  /// The dictionary answered with an unexpected HTTP status.
+  Status(u16),
+  /// The dictionary could not be reached.
+  Network,
+  /// The dictionary's answer could not be understood.
+  InvalidResponse,
+  /// Offline, and the word was never looked up before.
+  NotCached,
+  /// The local dictionary could not be loaded.
+  Unavailable,
+}
+
+impl ErrorKind {
+  pub fn as_str(&self) -> &str {
+    match *self {
+      ErrorKind::NotFound => "no entry found",
+      ErrorKind::Unauthorized => "invalid or missing credentials (APP_ID and API_KEY)",
+      ErrorKind::Status(_) => "unexpected response from the dictionary",
+      ErrorKind::Network => "could not reach the dictionary",
+      ErrorKind::InvalidResponse => "could not read the dictionary's response",
+      ErrorKind::NotCached => "not in the cache, and offline",
+      ErrorKind::Unavailable => "could not load the local dictionary",
+    }
+  }
+}
@@ -0,0 +1,347 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+use crate::arguments::View;
+use crate::output;
+use crate::provider::DictionaryProvider;
+use crate::suggest::Suggester;
+
+const HELP: &str = "\
+Type a word to look it up, or the number of a suggestion to look that up.
+
+  :define, :syn, :ant        show definitions, synonyms, antonyms,
+  :ex, :ety                  examples or etymology
+  :lang <code>               look words up in another language, like :lang fr
+  :limit <n>, :limit off     show at most n senses, synonyms, etc.
+  :help                      show this
+  :quit                      leave (or Ctrl-D)
+";
+
+const COMMANDS: [&str; 14] = [
+  ":define",
+  ":syn",
+  ":synonyms",
+  ":ant",
+  ":antonyms",
+  ":ex",
+  ":examples",
+  ":ety",
+  ":etymology",
+  ":lang",
+  ":limit",
+  ":help",
+  ":quit",
+  ":q",
+];
+
+/// What a line typed into the REPL comes to.
+#[derive(Debug, PartialEq)]
+pub enum Reply {
+  Text(String),
+  Quit,
+}
+
+/**
+ * The state of a REPL: how words are looked up, and what it knows to
+ * complete. Kept apart from the line editor, so it can be driven by tests.
+ */
+pub struct Session<P> {
+  provider: P,
+  lang: String,
+  view: View,
+  limit: Option<usize>,
+  /// Offered for the last word that wasn't found, to pick by number.
+  suggestions: Vec<String>,
+  suggester: Suggester,
+  /// Words to complete: the provider's, and the ones looked up since.
+  words: BTreeSet<String>,
+}
+
+impl<P: DictionaryProvider> Session<P> {
+  pub fn new(provider: P, lang: &str, view: View, limit: Option<usize>) -> Self {
+    let words = provider.words(lang).into_iter().collect();
+    Session {
+      provider,
+      lang: lang.to_string(),
+      view,
+      limit,
+      suggestions: Vec::new(),
+      suggester: Suggester::new(),
+      words,
+    }
+  }
+
+  pub fn prompt(&self) -> String {
+    format!("{} ({})> ", self.view.as_str(), self.lang)
+  }
+
+  pub fn eval(&mut self, line: &str) -> Reply {
+    let line = line.trim();
+    if line.is_empty() {
+      return Reply::Text(String::new());
+    }
+    if let Some(command) = line.strip_prefix(':') {
+      return self.command(command);
+    }
+    if let Ok(choice) = line.parse::<usize>() {
+      return match choice.checked_sub(1).and_then(|i| self.suggestions.get(i)) {
+        Some(word) => {
+          let word = word.clone();
+          self.lookup(&word)
+        }
+        None => Reply::Text(format!("No suggestion {}\n", choice)),
+      };
+    }
+    self.lookup(line)
+  }
+
+  fn lookup(&mut self, word: &str) -> Reply {
+    let answer = output::lookup(
+      &self.provider,
+      &mut self.suggester,
+      self.view,
+      word,
+      &self.lang,
+      self.limit,
+    );
+    if answer.found {
+      self.words.insert(word.to_lowercase());
+      self.suggester.insert(&self.lang, word);
+    }
+    self.suggestions = answer.suggestions;
+    Reply::Text(answer.text)
+  }
+
+  fn command(&mut self, command: &str) -> Reply {
+    let mut parts = command.split_whitespace();
+    let name = parts.next().unwrap_or("");
+    let argument = parts.next();
+    let view = match name {
+      "define" => Some(View::Define),
+      "syn" | "synonyms" => Some(View::Synonyms),
+      "ant" | "antonyms" => Some(View::Antonyms),
+      "ex" | "examples" => Some(View::Examples),
+      "ety" | "etymology" => Some(View::Etymology),
+      _ => None,
+    };
+    if let Some(view) = view {
+      self.view = view;
+      return Reply::Text(format!("Showing {}\n", view.as_str()));
+    }
+
+    let text = match (name, argument) {
+      ("lang", Some(lang)) => {
+        self.lang = lang.to_string();
+        self.suggestions.clear();
+        self.words.extend(self.provider.words(lang));
+        format!("Looking words up in {}\n", lang)
+      }
+      ("lang", None) => format!("Looking words up in {}\n", self.lang),
+      ("limit", Some("off")) => {
+        self.limit = None;
+        "Showing everything\n".to_string()
+      }
+      ("limit", Some(limit)) => match limit.parse() {
+        Ok(limit) if limit > 0 => {
+          self.limit = Some(limit);
+          format!("Showing at most {}\n", limit)
+        }
+        _ => format!(
+          "Invalid limit `{}`, expected a number above 0 or `off`\n",
+          limit
+        ),
+      },
+      ("limit", None) => match self.limit {
+        Some(limit) => format!("Showing at most {}\n", limit),
+        None => "Showing everything\n".to_string(),
+      },
+      ("help", _) => HELP.to_string(),
+      ("quit", _) | ("q", _) => return Reply::Quit,
+      _ => format!("Unknown command `:{}`, try :help\n", name),
+    };
+    Reply::Text(text)
+  }
+}
+
+impl<P> Completer for Session<P> {
+  type Candidate = String;
+
+  /// Completes commands after `:`, and otherwise known words.
+  fn complete(
+    &self,
+    line: &str,
+    pos: usize,
+    _: &Context<'_>,
+  ) -> rustyline::Result<(usize, Vec<String>)> {
+    let line = &line[..pos];
+    if line.starts_with(':') && !line.contains(' ') {
+      let commands = COMMANDS.iter().filter(|c| c.starts_with(line));
+      return Ok((0, commands.map(|c| c.to_string()).collect()));
+    }
+    if line.starts_with(':') {
+      return Ok((pos, Vec::new()));
+    }
+    // Words may have spaces, like "ice cream", so the whole line is one
+    let prefix = line.trim_start().to_lowercase();
+    let start = line.len() - line.trim_start().len();
+    let words = self
+      .words
+      .range(prefix.clone()..)
+      .take_while(|word| word.starts_with(&prefix))
+      .cloned()
+      .collect();
+    Ok((start, words))
+  }
+}
+
+impl<P> Hinter for Session<P> {
+  type Hint = String;
+}
+
+impl<P> Highlighter for Session<P> {}
+
+impl<P> Validator for Session<P> {}
+
+impl<P> Helper for Session<P> {}
+
+/// Reads and answers lines until `:quit` or the end of input, keeping the
+/// lines in `history` between runs.
+pub fn run<P: DictionaryProvider>(session: Session<P>, history: &Path) -> rustyline::Result<()> {
+  let mut editor: Editor<Session<P>, DefaultHistory> = Editor::new()?;
+  if let Err(e) = editor.load_history(history) {
+    if !matches!(&e, ReadlineError::Io(e) if e.kind() == std::io::ErrorKind::NotFound) {
+      eprintln!("warning: could not read {}: {}", history.display(), e);
+    }
+  }
+  println!("Type a word to look it up, :help for more, Ctrl-D to leave.");
+  editor.set_helper(Some(session));
+
+  loop {
+    let prompt = editor.helper().unwrap().prompt();
+    let line = match editor.readline(&prompt) {
+      Ok(line) => line,
+      // Ctrl-C drops the line, like in a shell
+      Err(ReadlineError::Interrupted) => continue,
+      Err(ReadlineError::Eof) => break,
+      Err(e) => return Err(e),
+    };
+    if !line.trim().is_empty() {
+      editor.add_history_entry(line.as_str())?;
+    }
+    match editor.helper_mut().unwrap().eval(&line) {
+      Reply::Text(text) => print!("{}", text),
+      Reply::Quit => break,
+    }
+  }
+
+  if let Some(dir) = history.parent() {
+    std::fs::create_dir_all(dir)?;
+  }
+  editor.save_history(history)
+}
+
+#[cfg(test)]
+mod tests {
+  use rustyline::history::MemHistory;
+
+  use super::*;
+  use crate::local::Local;
+
+  fn session() -> Session<Local> {
+    let words = "ace\tNoun\ta playing card\tthe ace of hearts\tone\n\
+                 ace\tAdjective\tvery good\t\texcellent, first-rate\n\
+                 acorn\tNoun\tthe fruit of the oak\n\
+                 ice cream\tNoun\ta frozen dessert\n\
+                 bat\tNoun\ta flying mammal\n";
+    let local = Local::new(crate::local::parse_tsv(words, "en").unwrap());
+    Session::new(local, "en", View::Define, None)
+  }
+
+  fn text(reply: Reply) -> String {
+    match reply {
+      Reply::Text(text) => text,
+      Reply::Quit => panic!("quit"),
+    }
+  }
+
+  fn complete(session: &Session<Local>, line: &str) -> (usize, Vec<String>) {
+    let history = MemHistory::new();
+    let context = Context::new(&history);
+    session.complete(line, line.len(), &context).unwrap()
+  }
+
+  #[test]
+  fn looks_words_up_in_the_current_mode() {
+    let mut session = session();
+    assert_eq!(session.prompt(), "definitions (en)> ");
+    assert!(text(session.eval("ace")).contains("1. a playing card"));
+
+    assert_eq!(text(session.eval(":syn")), "Showing synonyms\n");
+    assert_eq!(session.prompt(), "synonyms (en)> ");
+    assert_eq!(
+      text(session.eval("ace")),
+      "ace (noun)\n  - one\nace (adjective)\n  - excellent\n  - first-rate\n"
+    );
+    assert_eq!(text(session.eval(":limit 1")), "Showing at most 1\n");
+    assert_eq!(text(session.eval("  ace ")), "ace (noun)\n  - one\n");
+    assert_eq!(text(session.eval(":limit off")), "Showing everything\n");
+    assert_eq!(text(session.eval(":ant")), "Showing antonyms\n");
+    assert_eq!(text(session.eval("ace")), "No antonyms found for \"ace\"\n");
+    assert_eq!(text(session.eval(":syn")), "Showing synonyms\n");
+
+    assert_eq!(text(session.eval(":lang fr")), "Looking words up in fr\n");
+    assert_eq!(session.prompt(), "synonyms (fr)> ");
+    assert_eq!(text(session.eval("ace")), "No entry found for \"ace\"\n");
+    assert_eq!(session.eval(":quit"), Reply::Quit);
+  }
+
+  #[test]
+  fn picks_suggestions_by_number() {
+    let mut session = session();
+    assert_eq!(
+      text(session.eval("bart")),
+      "No entry found for \"bart\"\nDid you mean:\n  1. bat\n"
+    );
+    assert!(text(session.eval("1")).starts_with("bat (noun)\n"));
+    assert_eq!(text(session.eval("2")), "No suggestion 2\n");
+  }
+
+  #[test]
+  fn rejects_unknown_commands_and_values() {
+    let mut session = session();
+    assert_eq!(
+      text(session.eval(":frobnicate")),
+      "Unknown command `:frobnicate`, try :help\n"
+    );
+    assert!(text(session.eval(":limit 0")).starts_with("Invalid limit `0`"));
+    assert_eq!(text(session.eval(":lang")), "Looking words up in en\n");
+    assert_eq!(text(session.eval("")), "");
+  }
+
+  #[test]
+  fn completes_commands_and_words() {
+    let session = session();
+    assert_eq!(
+      complete(&session, ":l"),
+      (0, vec![":lang".to_string(), ":limit".to_string()])
+    );
+    assert_eq!(complete(&session, ":lang f"), (7, vec![]));
+    assert_eq!(
+      complete(&session, "ac"),
+      (0, vec!["ace".to_string(), "acorn".to_string()])
+    );
+    assert_eq!(
+      complete(&session, " Ice c"),
+      (1, vec!["ice cream".to_string()])
+    );
+    assert_eq!(complete(&session, "z"), (0, vec![]));
+  }
+}
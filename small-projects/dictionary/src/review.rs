@@ -0,0 +1,189 @@
+use std::io::{self, BufRead, Write};
+
+use crate::history::{Card, History};
+
+/**
+ * Quizzes the user on the cards due at `now`, at most `limit` of them:
+ * shows a word, then what it means once they're ready, and asks how well
+ * they remembered it. The history is saved after every answer.
+ */
+pub fn review<R, W>(
+  history: &mut History,
+  now: u64,
+  limit: Option<usize>,
+  mut input: R,
+  mut output: W,
+) -> io::Result<()>
+where
+  R: BufRead,
+  W: Write,
+{
+  let mut due = history.due(now);
+  due.truncate(limit.unwrap_or(usize::MAX));
+  if due.is_empty() {
+    writeln!(output, "Nothing to review")?;
+    return Ok(());
+  }
+
+  let total = due.len();
+  for (n, index) in due.into_iter().enumerate() {
+    let card = &history.cards()[index];
+    write!(
+      output,
+      "\n{} ({} of {}), press Enter to see what it means ",
+      card.word,
+      n + 1,
+      total
+    )?;
+    output.flush()?;
+    if read_line(&mut input)?.is_none() {
+      return Ok(());
+    }
+    write!(output, "{}", answer(card))?;
+
+    let quality = loop {
+      write!(
+        output,
+        "How well did you remember it? 0 (not at all) to 5 (perfectly), q to stop: "
+      )?;
+      output.flush()?;
+      match read_line(&mut input)?.as_deref() {
+        None | Some("q") => return Ok(()),
+        Some(answer) => match answer.parse::<u8>() {
+          Ok(quality) if quality <= 5 => break quality,
+          _ => continue,
+        },
+      }
+    };
+    history.review(index, quality, now);
+    history.save()?;
+    let interval = history.cards()[index].interval;
+    let days = if interval == 1 { "day" } else { "days" };
+    writeln!(output, "Next review in {} {}", interval, days)?;
+  }
+  Ok(())
+}
+
+/// The trimmed next line, or `None` at the end of input.
+fn read_line(input: &mut impl BufRead) -> io::Result<Option<String>> {
+  let mut line = String::new();
+  if input.read_line(&mut line)? == 0 {
+    return Ok(None);
+  }
+  Ok(Some(line.trim().to_string()))
+}
+
+fn answer(card: &Card) -> String {
+  let mut text = String::new();
+  for definition in &card.definitions {
+    text.push_str(&format!("  {}\n", definition));
+  }
+  for example in &card.examples {
+    text.push_str(&format!("    \"{}\"\n", example));
+  }
+  text
+}
+
+/**
+ * Every card as an Anki deck: tab-separated front (the word), back (its
+ * definitions and examples, in HTML) and tags, with the header lines Anki
+ * reads its import settings from.
+ */
+pub fn anki(history: &History) -> String {
+  let mut deck = String::from("#separator:tab\n#html:true\n#columns:Front\tBack\tTags\n");
+  for card in history.cards() {
+    let mut back: Vec<String> = card.definitions.iter().map(|d| html(d)).collect();
+    back.extend(card.examples.iter().map(|e| format!("<i>{}</i>", html(e))));
+    deck.push_str(&format!(
+      "{}\t{}\tdictionary lang::{}\n",
+      html(&card.word),
+      back.join("<br>"),
+      card.lang.replace(char::is_whitespace, "_")
+    ));
+  }
+  deck
+}
+
+/// `text` escaped for HTML, and without the tabs and line breaks that
+/// would split it into other fields or notes.
+fn html(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace(['\t', '\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::Path;
+
+  use super::*;
+  use crate::local::{parse_tsv, Local};
+  use crate::provider::DictionaryProvider;
+
+  const NOW: u64 = 1_600_000_000;
+
+  fn history(path: &Path) -> History {
+    let words = "ace\tNoun\ta playing card\tthe <ace> of hearts\n\
+                 bat\tNoun\ta flying mammal\n\
+                 cat\tNoun\ta small\tdomesticated feline\n";
+    let local = Local::new(parse_tsv(words, "en").unwrap());
+    let mut history = History::open(path).unwrap();
+    for word in ["ace", "bat", "cat"].iter() {
+      history.record(word, "en", &local.lookup(word, "en").unwrap(), NOW);
+    }
+    history
+  }
+
+  fn run(history: &mut History, limit: Option<usize>, input: &str) -> String {
+    let mut output = Vec::new();
+    review(history, NOW, limit, input.as_bytes(), &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+  }
+
+  #[test]
+  fn reviews_due_cards() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("history.json");
+    let mut history = history(&path);
+
+    let output = run(&mut history, Some(2), "\n5\n\nsix\n9\n2\n");
+    assert!(output.contains("ace (1 of 2), press Enter"), "{}", output);
+    assert!(output.contains("  (noun) a playing card\n    \"the <ace> of hearts\"\n"));
+    assert!(output.contains("bat (2 of 2)"));
+    assert_eq!(output.matches("Next review in 1 day\n").count(), 2);
+    assert!(!output.contains("cat"));
+
+    // Saved as it went
+    let saved = History::open(&path).unwrap();
+    assert_eq!(saved.cards()[0].repetitions, 1);
+    assert_eq!(saved.cards()[1].repetitions, 0);
+    assert_eq!(saved.due(NOW), vec![2]);
+  }
+
+  #[test]
+  fn stops_when_asked() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut history = history(&dir.path().join("history.json"));
+    let output = run(&mut history, None, "\nq\n");
+    assert!(!output.contains("Next review"));
+    assert_eq!(history.due(NOW).len(), 3);
+
+    run(&mut history, None, "\n4\n\n4\n\n4\n");
+    assert_eq!(run(&mut history, None, ""), "Nothing to review\n");
+  }
+
+  #[test]
+  fn exports_an_anki_deck() {
+    let dir = tempfile::tempdir().unwrap();
+    let history = history(&dir.path().join("history.json"));
+    assert_eq!(
+      anki(&history),
+      "#separator:tab\n#html:true\n#columns:Front\tBack\tTags\n\
+       ace\t(noun) a playing card<br><i>the &lt;ace&gt; of hearts</i>\tdictionary lang::en\n\
+       bat\t(noun) a flying mammal\tdictionary lang::en\n\
+       cat\t(noun) a small<br><i>domesticated feline</i>\tdictionary lang::en\n"
+    );
+  }
+}
@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::iter::FromIterator;
+
+use crate::provider::DictionaryProvider;
+
+/**
+ * Breakdown #4:
+ *
+ * - A BK-tree stores words so that the ones within some edit distance of a
+ *   query can be found without comparing against all of them. Every child
+ *   hangs off its parent by their distance `d`.
+ * - Edit distance is a metric, so by the triangle inequality a word within
+ *   `max` of the query can only be below children whose `d` is within
+ *   `max` of the parent's own distance to the query. The rest are skipped.
+ */
+#[derive(Debug, Default)]
+pub struct BkTree {
+  nodes: Vec<Node>,
+}
+
+#[derive(Debug)]
+struct Node {
+  word: String,
+  /// `(distance, index into nodes)` of each child.
+  children: Vec<(usize, usize)>,
+}
+
+impl BkTree {
+  pub fn new() -> Self {
+    BkTree::default()
+  }
+
+  /// Adds `word`, unless it's in the tree already.
+  pub fn insert(&mut self, word: &str) {
+    if self.nodes.is_empty() {
+      self.nodes.push(Node::new(word));
+      return;
+    }
+    let mut current = 0;
+    loop {
+      let distance = levenshtein(&self.nodes[current].word, word);
+      if distance == 0 {
+        return;
+      }
+      let child = self.nodes[current]
+        .children
+        .iter()
+        .find(|(d, _)| *d == distance)
+        .map(|(_, child)| *child);
+      match child {
+        Some(child) => current = child,
+        None => {
+          let index = self.nodes.len();
+          self.nodes.push(Node::new(word));
+          self.nodes[current].children.push((distance, index));
+          return;
+        }
+      }
+    }
+  }
+
+  /// Words at most `max` edits away from `word`, closest first and then
+  /// alphabetically.
+  pub fn find(&self, word: &str, max: usize) -> Vec<(&str, usize)> {
+    let mut found = Vec::new();
+    let mut pending = if self.nodes.is_empty() {
+      vec![]
+    } else {
+      vec![0]
+    };
+    while let Some(index) = pending.pop() {
+      let node = &self.nodes[index];
+      let distance = levenshtein(&node.word, word);
+      if distance <= max {
+        found.push((node.word.as_str(), distance));
+      }
+      for (d, child) in &node.children {
+        if distance.saturating_sub(max) <= *d && *d <= distance + max {
+          pending.push(*child);
+        }
+      }
+    }
+    found.sort_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(b.0)));
+    found
+  }
+
+  /// Up to `limit` words in the tree that `word` may be a misspelling of,
+  /// best first. Longer words are allowed more mistakes.
+  pub fn suggestions(&self, word: &str, limit: usize) -> Vec<String> {
+    let word = word.trim().to_lowercase();
+    let max = match word.chars().count() {
+      0..=2 => 0,
+      3..=5 => 1,
+      _ => 2,
+    };
+    self
+      .find(&word, max)
+      .into_iter()
+      .filter(|(_, distance)| *distance > 0)
+      .take(limit)
+      .map(|(word, _)| word.to_string())
+      .collect()
+  }
+}
+
+impl Node {
+  fn new(word: &str) -> Self {
+    Node {
+      word: word.to_string(),
+      children: Vec::new(),
+    }
+  }
+}
+
+impl<S: AsRef<str>> FromIterator<S> for BkTree {
+  /// A tree of `words`, lowercased.
+  fn from_iter<I: IntoIterator<Item = S>>(words: I) -> Self {
+    let mut tree = BkTree::new();
+    for word in words {
+      tree.insert(&word.as_ref().to_lowercase());
+    }
+    tree
+  }
+}
+
+/**
+ * Suggestions from the words a provider knows, with a tree per language
+ * built the first time that language needs one, rather than for every
+ * word that isn't found.
+ */
+#[derive(Debug, Default)]
+pub struct Suggester {
+  trees: HashMap<String, BkTree>,
+}
+
+impl Suggester {
+  pub fn new() -> Self {
+    Suggester::default()
+  }
+
+  /// Up to `limit` words `provider` knows in `lang` that `word` may be a
+  /// misspelling of, best first.
+  pub fn suggestions<P>(
+    &mut self,
+    provider: &P,
+    word: &str,
+    lang: &str,
+    limit: usize,
+  ) -> Vec<String>
+  where
+    P: DictionaryProvider + ?Sized,
+  {
+    self
+      .trees
+      .entry(lang.to_string())
+      .or_insert_with(|| provider.words(lang).into_iter().collect())
+      .suggestions(word, limit)
+  }
+
+  /// Adds `word`, found since, to what's suggested in `lang`.
+  pub fn insert(&mut self, lang: &str, word: &str) {
+    if let Some(tree) = self.trees.get_mut(lang) {
+      tree.insert(&word.to_lowercase());
+    }
+  }
+}
+
+/// How many characters have to be inserted, removed or replaced to turn
+/// `a` into `b`.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+  let b: Vec<char> = b.chars().collect();
+  // Distances from the prefix of `a` seen so far to every prefix of `b`
+  let mut row: Vec<usize> = (0..=b.len()).collect();
+  for (i, a) in a.chars().enumerate() {
+    let mut diagonal = row[0];
+    row[0] = i + 1;
+    for (j, b) in b.iter().enumerate() {
+      let replaced = diagonal + if a == *b { 0 } else { 1 };
+      diagonal = row[j + 1];
+      row[j + 1] = replaced.min(row[j] + 1).min(diagonal + 1);
+    }
+  }
+  row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+  use std::cell::Cell;
+
+  use super::*;
+  use crate::provider::{Entry, Error, ErrorKind, Result};
+
+  const WORDS: [&str; 12] = [
+    "book",
+    "books",
+    "boo",
+    "boon",
+    "cook",
+    "cake",
+    "cape",
+    "cart",
+    "hello",
+    "help",
+    "shell",
+    "dictionary",
+  ];
+
+  #[test]
+  fn measures_edit_distance() {
+    assert_eq!(levenshtein("", ""), 0);
+    assert_eq!(levenshtein("", "abc"), 3);
+    assert_eq!(levenshtein("abc", ""), 3);
+    assert_eq!(levenshtein("kitten", "sitting"), 3);
+    assert_eq!(levenshtein("flaw", "lawn"), 2);
+    assert_eq!(levenshtein("teh", "the"), 2);
+    assert_eq!(levenshtein("café", "cafe"), 1);
+  }
+
+  #[test]
+  fn finds_the_same_words_as_a_linear_scan() {
+    let mut tree = BkTree::new();
+    for word in WORDS.iter() {
+      tree.insert(word);
+    }
+    tree.insert("book");
+
+    for query in ["book", "bok", "caek", "hepl", "xyz", "", "dictonary"].iter() {
+      for max in 0..4 {
+        let mut expected: Vec<(&str, usize)> = WORDS
+          .iter()
+          .map(|word| (*word, levenshtein(word, query)))
+          .filter(|(_, distance)| *distance <= max)
+          .collect();
+        expected.sort_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(b.0)));
+        assert_eq!(tree.find(query, max), expected, "{} within {}", query, max);
+      }
+    }
+  }
+
+  fn suggestions<I, S>(word: &str, words: I, limit: usize) -> Vec<String>
+  where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+  {
+    words
+      .into_iter()
+      .collect::<BkTree>()
+      .suggestions(word, limit)
+  }
+
+  #[test]
+  fn ranks_suggestions() {
+    assert_eq!(suggestions("Bok", WORDS.iter(), 5), vec!["boo", "book"]);
+    assert_eq!(suggestions("bok", WORDS.iter(), 1), vec!["boo"]);
+    assert_eq!(
+      suggestions("boooks", WORDS.iter(), 5),
+      vec!["books", "book"]
+    );
+    assert_eq!(
+      suggestions("dictonery", WORDS.iter(), 5),
+      vec!["dictionary"]
+    );
+    // Too far off, or not misspelt at all
+    assert!(suggestions("zebra", WORDS.iter(), 5).is_empty());
+    assert!(suggestions("book", vec!["book"], 5).is_empty());
+    assert!(suggestions("ok", WORDS.iter(), 5).is_empty());
+  }
+
+  /// Knows the words above, and counts how often it's asked for them.
+  struct Words(Cell<usize>);
+
+  impl DictionaryProvider for Words {
+    fn name(&self) -> &str {
+      "words"
+    }
+
+    fn lookup(&self, _word: &str, _lang: &str) -> Result<Vec<Entry>> {
+      Err(Error::new(ErrorKind::NotFound))
+    }
+
+    fn words(&self, lang: &str) -> Vec<String> {
+      self.0.set(self.0.get() + 1);
+      match lang {
+        "en" => WORDS.iter().map(|w| w.to_string()).collect(),
+        _ => vec![],
+      }
+    }
+  }
+
+  #[test]
+  fn builds_a_tree_once_per_language() {
+    let provider = Words(Cell::new(0));
+    let mut suggester = Suggester::new();
+    assert_eq!(
+      suggester.suggestions(&provider, "bok", "en", 5),
+      vec!["boo", "book"]
+    );
+    assert_eq!(
+      suggester.suggestions(&provider, "dictionry", "en", 5),
+      vec!["dictionary"]
+    );
+    assert_eq!(provider.0.get(), 1);
+
+    assert!(suggester.suggestions(&provider, "bok", "fr", 5).is_empty());
+    suggester.insert("fr", "Bon");
+    assert_eq!(
+      suggester.suggestions(&provider, "bok", "fr", 5),
+      vec!["bon"]
+    );
+    assert_eq!(provider.0.get(), 2);
+  }
+}
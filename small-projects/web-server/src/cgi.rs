@@ -0,0 +1,358 @@
+//! CGI/1.1 (RFC 3875): runs the scripts in a directory, a process per
+//! request, and turns what they print into the response.
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, BufReader, Read, Take, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    http::{read_headers, Body, Request, RequestBody, Response},
+    proxy::HOP_BY_HOP,
+};
+
+/// `PATH` scripts get when the server has none.
+const DEFAULT_PATH: &str = "/usr/local/bin:/usr/bin:/bin";
+
+pub struct Cgi {
+    dir: PathBuf,
+    /// Longest a script may run, output included.
+    timeout: Duration,
+}
+
+impl Cgi {
+    pub fn new(dir: impl Into<PathBuf>, timeout: Duration) -> Cgi {
+        Cgi {
+            dir: dir.into(),
+            timeout,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Runs the script `path`, the decoded and normalized request path,
+    /// names below `route`, e.g. for the route `/cgi-bin`,
+    /// `/cgi-bin/hello.sh/extra` runs `hello.sh` with `/extra` as its
+    /// `PATH_INFO`.
+    pub fn handle(
+        &self,
+        route: &str,
+        path: &str,
+        request: &Request,
+        body: &mut RequestBody,
+    ) -> Response {
+        let rest = path[route.len()..].trim_start_matches('/');
+        let (name, path_info) = match rest.find('/') {
+            Some(slash) => rest.split_at(slash),
+            None => (rest, ""),
+        };
+        if !is_plain_name(name) {
+            return Response::error(404);
+        }
+        let script = self.dir.join(name);
+        if !script.is_file() {
+            return Response::error(404);
+        }
+        if !is_executable(&script) {
+            return Response::error(403);
+        }
+        // Scripts need the length of their input up front, which a chunked
+        // body doesn't tell
+        if body.is_chunked() {
+            return Response::error(411);
+        }
+
+        let script_name = format!("{}/{}", route, name);
+        let mut command = Command::new(&script);
+        command
+            .env_clear()
+            .envs(variables(request, &script_name, path_info))
+            .current_dir(&self.dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit());
+
+        match Process::spawn(command, self.timeout) {
+            Ok((process, stdin, stdout)) => respond(process, stdin, stdout, body, &script),
+            Err(e) => {
+                eprintln!("Failed to run {}: {}", script.display(), e);
+                Response::error(500)
+            }
+        }
+    }
+}
+
+/// The meta-variables of RFC 3875, section 4.1, plus a `HTTP_*` variable
+/// per request header.
+fn variables(request: &Request, script_name: &str, path_info: &str) -> BTreeMap<String, String> {
+    let mut variables = BTreeMap::new();
+    let mut set = |name: &str, value: &str| {
+        variables.insert(name.to_string(), value.to_string());
+    };
+
+    let host = request.host().unwrap_or("localhost");
+    let default_port = if request.tls { "443" } else { "80" };
+    let port = request
+        .headers
+        .get("Host")
+        .and_then(|header| header.get(host.len()..))
+        .and_then(|rest| rest.strip_prefix(':'))
+        .unwrap_or(default_port);
+
+    set("GATEWAY_INTERFACE", "CGI/1.1");
+    set(
+        "SERVER_SOFTWARE",
+        concat!("web-server/", env!("CARGO_PKG_VERSION")),
+    );
+    set("SERVER_PROTOCOL", &request.version);
+    set("SERVER_NAME", host);
+    set("SERVER_PORT", port);
+    set("REQUEST_METHOD", &request.method);
+    set("REQUEST_URI", &request.target);
+    set("SCRIPT_NAME", script_name);
+    set("QUERY_STRING", request.query().unwrap_or(""));
+    if !path_info.is_empty() {
+        set("PATH_INFO", path_info);
+    }
+    if let Some(addr) = request.remote_addr {
+        set("REMOTE_ADDR", &addr.ip().to_string());
+        set("REMOTE_PORT", &addr.port().to_string());
+    }
+    if request.content_length() > 0 {
+        set("CONTENT_LENGTH", &request.content_length().to_string());
+    }
+    if let Some(content_type) = request.headers.get("Content-Type") {
+        set("CONTENT_TYPE", content_type);
+    }
+    if request.tls {
+        set("HTTPS", "on");
+    }
+    let path = std::env::var("PATH").unwrap_or_else(|_| DEFAULT_PATH.to_string());
+    set("PATH", &path);
+
+    for (name, value) in request.headers.iter() {
+        // Already passed on above. `Proxy` would become `HTTP_PROXY`, which
+        // plenty of programs take as the proxy to use (httpoxy)
+        let skip = ["Content-Length", "Content-Type", "Proxy"]
+            .iter()
+            .any(|skipped| name.eq_ignore_ascii_case(skipped));
+        if skip || !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-') {
+            continue;
+        }
+        let name = format!("HTTP_{}", name.to_ascii_uppercase().replace('-', "_"));
+        variables
+            .entry(name)
+            .and_modify(|values: &mut String| {
+                values.push_str(", ");
+                values.push_str(value);
+            })
+            .or_insert_with(|| value.to_string());
+    }
+    variables
+}
+
+/// Feeds the request body to the script and reads its head, which is
+/// turned into the response. The body of the response is streamed from
+/// the script while it's written.
+fn respond(
+    process: Process,
+    stdin: ChildStdin,
+    stdout: ChildStdout,
+    body: &mut RequestBody,
+    script: &Path,
+) -> Response {
+    // Read on another thread while the body is written, since a script
+    // may well answer before it has read all of its input
+    let head = thread::spawn(move || {
+        let mut reader = BufReader::new(stdout);
+        (read_headers(&mut reader), reader)
+    });
+    let fed = feed(body, stdin);
+    if let Err(e) = fed {
+        eprintln!("Failed to read the body for {}: {}", script.display(), e);
+        drop(process);
+        let _ = head.join();
+        return Response::error(500);
+    }
+    let (head, reader) = head.join().expect("reading a CGI head panicked");
+
+    let mut headers = match head {
+        Ok(headers) => headers,
+        Err(_) if process.timed_out() => {
+            eprintln!("{} timed out", script.display());
+            return Response::error(504);
+        }
+        Err(e) => {
+            eprintln!("{} sent an invalid head: {}", script.display(), e);
+            return Response::error(502);
+        }
+    };
+    let status = match headers.get("Status") {
+        Some(status) => match status.split(' ').next().and_then(|code| code.parse().ok()) {
+            Some(code) if (100..600).contains(&code) => code,
+            _ => {
+                eprintln!("{} sent an invalid status: {}", script.display(), status);
+                return Response::error(502);
+            }
+        },
+        // A redirect, although without a status of its own
+        None if headers.contains("Location") => 302,
+        None => 200,
+    };
+    let length = match headers.get("Content-Length").map(str::parse) {
+        Some(Ok(length)) => Some(length),
+        Some(Err(_)) => {
+            eprintln!("{} sent an invalid content-length", script.display());
+            return Response::error(502);
+        }
+        None => None,
+    };
+    headers.remove("Status");
+    for name in HOP_BY_HOP.iter() {
+        headers.remove(name);
+    }
+
+    let output = Output {
+        reader: reader.take(length.unwrap_or(u64::MAX)),
+        length,
+        process,
+    };
+    Response {
+        status,
+        headers,
+        body: Body::Reader {
+            reader: Box::new(output),
+            length,
+        },
+    }
+}
+
+/// Copies the request body to the script's standard input, then closes it.
+/// Fails only if the body can't be read: a script that exits without
+/// reading all of it is no reason to.
+fn feed(body: &mut RequestBody, mut stdin: ChildStdin) -> io::Result<()> {
+    let mut buffer = [0; 8 * 1024];
+    let mut writing = true;
+    loop {
+        let read = body.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(());
+        }
+        writing = writing && stdin.write_all(&buffer[..read]).is_ok();
+    }
+}
+
+/// A running script, killed once it runs out of time or once nothing reads
+/// its output anymore, e.g. because the client disconnected.
+struct Process {
+    child: Arc<Mutex<Child>>,
+    timed_out: Arc<AtomicBool>,
+    /// Dropped to call off the watchdog.
+    _done: mpsc::Sender<()>,
+}
+
+impl Process {
+    fn spawn(
+        mut command: Command,
+        timeout: Duration,
+    ) -> io::Result<(Process, ChildStdin, ChildStdout)> {
+        let mut child = command.spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let child = Arc::new(Mutex::new(child));
+        let timed_out = Arc::new(AtomicBool::new(false));
+
+        let (done, watchdog) = mpsc::channel::<()>();
+        {
+            let child = Arc::clone(&child);
+            let timed_out = Arc::clone(&timed_out);
+            thread::spawn(move || {
+                if let Err(RecvTimeoutError::Timeout) = watchdog.recv_timeout(timeout) {
+                    timed_out.store(true, Ordering::SeqCst);
+                    let _ = child.lock().unwrap().kill();
+                }
+            });
+        }
+        let process = Process {
+            child,
+            timed_out,
+            _done: done,
+        };
+        Ok((process, stdin, stdout))
+    }
+
+    fn timed_out(&self) -> bool {
+        self.timed_out.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let mut child = self.child.lock().unwrap();
+        if let Ok(None) = child.try_wait() {
+            let _ = child.kill();
+        }
+        // Reaped, so it doesn't linger as a zombie
+        let _ = child.wait();
+    }
+}
+
+/// Body of a CGI response, keeping the script alive while it's read.
+struct Output {
+    reader: Take<BufReader<ChildStdout>>,
+    length: Option<u64>,
+    process: Process,
+}
+
+impl Read for Output {
+    /// Fails rather than ending early if the script was killed, or stopped
+    /// short of its `Content-Length`, so the client can tell the response
+    /// is incomplete.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        if read == 0 && !buf.is_empty() {
+            if self.process.timed_out() {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "CGI script timed out",
+                ));
+            }
+            if self.length.is_some() && self.reader.limit() > 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "CGI script output ended before its content-length",
+                ));
+            }
+        }
+        Ok(read)
+    }
+}
+
+/// Whether `name` is a file right in the scripts directory, and no hidden
+/// one.
+fn is_plain_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && !name.contains(['/', '\\'])
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    fs::metadata(path).is_ok()
+}
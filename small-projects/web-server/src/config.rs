@@ -0,0 +1,813 @@
+//! Server configuration, read from a TOML file (see `web-server.toml`).
+//!
+//! Relative paths are resolved against the directory of the configuration
+//! file: document roots and certificates directly, and the files a site refers to (index,
+//! 404 page, files of `file` and `sleep` routes, upload and CGI directories) against
+//! that site's root.
+use std::{
+    collections::HashSet,
+    error, fmt, fs,
+    net::ToSocketAddrs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    cgi::Cgi,
+    metrics::Cidr,
+    proxy::Proxy,
+    router::{Handler, Route, Router, Sites},
+    server,
+    tls::{Certificates, TlsError},
+    upload::Upload,
+};
+
+/// Configuration file looked up in the working directory when none is given.
+pub const DEFAULT_PATH: &str = "web-server.toml";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Addresses to accept connections on, as `host:port`.
+    #[serde(default = "default_listen")]
+    pub listen: Vec<String>,
+    /// Number of threads handling connections.
+    #[serde(default = "default_workers")]
+    pub workers: usize,
+    #[serde(default)]
+    pub timeouts: Timeouts,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    /// HTTPS listeners, and the default site's certificate.
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    /// Document root of the default site, serving every request whose
+    /// `Host` doesn't match one of the virtual hosts.
+    #[serde(default = "default_root")]
+    pub root: PathBuf,
+    #[serde(default = "default_index")]
+    pub index: String,
+    /// Page served, relative to the root, when nothing else matched.
+    #[serde(default = "default_not_found")]
+    pub not_found: Option<String>,
+    /// Whether directories without an index are listed.
+    #[serde(default)]
+    pub listing: bool,
+    #[serde(default)]
+    pub routes: Vec<RouteConfig>,
+    #[serde(default)]
+    pub vhosts: Vec<VirtualHost>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Timeouts {
+    /// Longest a client may take to send the head of a request (request
+    /// line and headers), in seconds.
+    #[serde(default = "default_header_timeout", deserialize_with = "seconds")]
+    pub header: Duration,
+    /// Longest a client may go without sending any of a request body, in
+    /// seconds.
+    #[serde(default = "default_timeout", deserialize_with = "seconds")]
+    pub body: Duration,
+    /// Longest a single write to a client may block, in seconds.
+    #[serde(default = "default_timeout", deserialize_with = "seconds")]
+    pub write: Duration,
+    /// How long a kept-alive connection may wait for its next request, in
+    /// seconds.
+    #[serde(default = "default_idle_timeout", deserialize_with = "seconds")]
+    pub idle: Duration,
+    /// Slowest a client may send a request or take a response, in bytes
+    /// per second: each byte buys `1 / min_rate` seconds on top of `body`
+    /// for request bodies, and of `write` for responses. Heads get a second
+    /// before it applies. 0 turns the check off.
+    #[serde(default = "default_min_rate")]
+    pub min_rate: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// Addresses to accept HTTPS connections on, as `host:port`.
+    pub listen: Vec<String>,
+    /// PEM certificate chain, presented unless a virtual host with its own
+    /// certificate was asked for.
+    pub cert: PathBuf,
+    /// PEM private key of `cert`.
+    pub key: PathBuf,
+    /// How often to check the certificate files for changes, in seconds.
+    #[serde(default = "default_reload_interval", deserialize_with = "seconds")]
+    pub reload_interval: Duration,
+}
+
+/// Certificate of a virtual host, presented to clients asking for one of
+/// its names.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CertificateFiles {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+/// The Prometheus metrics endpoint, served for every site.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MetricsConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_metrics_path")]
+    pub path: String,
+    /// Client addresses or CIDR blocks (`10.0.0.0/8`) allowed to read the
+    /// metrics; everyone else gets `403 Forbidden`. Loopback by default.
+    #[serde(default = "default_metrics_allow")]
+    pub allow: Vec<String>,
+}
+
+impl MetricsConfig {
+    /// The allow list, skipping invalid entries (which `validate` reports).
+    pub fn allowed(&self) -> Vec<Cidr> {
+        self.allow
+            .iter()
+            .filter_map(|entry| entry.parse().ok())
+            .collect()
+    }
+}
+
+/// Site served for requests whose `Host` is `host` or one of the `aliases`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VirtualHost {
+    pub host: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub root: PathBuf,
+    #[serde(default = "default_index")]
+    pub index: String,
+    #[serde(default)]
+    pub not_found: Option<String>,
+    #[serde(default)]
+    pub listing: bool,
+    #[serde(default)]
+    pub tls: Option<CertificateFiles>,
+    #[serde(default)]
+    pub routes: Vec<RouteConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum RouteConfig {
+    /// Serves a single file for exactly `path`.
+    File { path: String, file: PathBuf },
+    /// Like `file`, but only after sleeping for `seconds`.
+    Sleep {
+        path: String,
+        file: PathBuf,
+        seconds: u64,
+    },
+    /// Forwards `path` and everything below it to the upstreams.
+    Proxy {
+        path: String,
+        upstreams: Vec<String>,
+    },
+    /// Saves files POSTed to `path` as `multipart/form-data` into `dir`.
+    Upload {
+        path: String,
+        dir: PathBuf,
+        /// Largest file accepted, in bytes.
+        #[serde(default = "default_max_file_size")]
+        max_file_size: u64,
+        /// Largest total size of the files in one request, in bytes.
+        #[serde(default)]
+        max_request_size: Option<u64>,
+    },
+    /// Runs the scripts in `dir` for `path/<script>` and below, as CGI.
+    Cgi {
+        path: String,
+        dir: PathBuf,
+        /// Longest a script may run, in seconds.
+        #[serde(default = "default_timeout", deserialize_with = "seconds")]
+        timeout: Duration,
+    },
+}
+
+impl RouteConfig {
+    pub fn path(&self) -> &str {
+        match self {
+            RouteConfig::File { path, .. }
+            | RouteConfig::Sleep { path, .. }
+            | RouteConfig::Proxy { path, .. }
+            | RouteConfig::Upload { path, .. }
+            | RouteConfig::Cgi { path, .. } => path,
+        }
+    }
+
+    fn to_route(&self, root: &Path) -> Route {
+        match self {
+            RouteConfig::File { path, file } => Route::exact(path, Handler::File(root.join(file))),
+            RouteConfig::Sleep {
+                path,
+                file,
+                seconds,
+            } => Route::exact(
+                path,
+                Handler::Sleep(Duration::from_secs(*seconds), root.join(file)),
+            ),
+            RouteConfig::Proxy { path, upstreams } => {
+                Route::prefix(path, Handler::Proxy(Proxy::new(upstreams.clone())))
+            }
+            RouteConfig::Upload {
+                path,
+                dir,
+                max_file_size,
+                max_request_size,
+            } => {
+                let mut upload = Upload::new(root.join(dir), *max_file_size);
+                if let Some(limit) = max_request_size {
+                    upload = upload.max_request_size(*limit);
+                }
+                Route::exact(path, Handler::Upload(upload))
+            }
+            RouteConfig::Cgi { path, dir, timeout } => {
+                Route::prefix(path, Handler::Cgi(Cgi::new(root.join(dir), *timeout)))
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    /// Every problem found while validating, one message each.
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, e) => write!(f, "cannot read {}: {}", path.display(), e),
+            ConfigError::Parse(path, e) => write!(f, "cannot parse {}: {}", path.display(), e),
+            ConfigError::Invalid(problems) => {
+                write!(f, "invalid configuration:")?;
+                for problem in problems {
+                    write!(f, "\n  - {}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl error::Error for ConfigError {}
+
+impl Config {
+    /// Reads, parses and validates the configuration file at `path`.
+    pub fn load(path: &Path) -> Result<Config, ConfigError> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| ConfigError::Read(path.to_path_buf(), e))?;
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        Config::parse(&contents, base).map_err(|e| match e {
+            ConfigError::Parse(_, e) => ConfigError::Parse(path.to_path_buf(), e),
+            e => e,
+        })
+    }
+
+    /// Parses and validates a configuration, resolving relative paths
+    /// against `base`.
+    pub fn parse(contents: &str, base: &Path) -> Result<Config, ConfigError> {
+        let mut config: Config =
+            toml::from_str(contents).map_err(|e| ConfigError::Parse(PathBuf::new(), e))?;
+        config.root = base.join(&config.root);
+        if let Some(tls) = config.tls.as_mut() {
+            tls.cert = base.join(&tls.cert);
+            tls.key = base.join(&tls.key);
+        }
+        for vhost in config.vhosts.iter_mut() {
+            vhost.root = base.join(&vhost.root);
+            if let Some(tls) = vhost.tls.as_mut() {
+                tls.cert = base.join(&tls.cert);
+                tls.key = base.join(&tls.key);
+            }
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+
+        if self.listen.is_empty() {
+            problems.push("listen: at least one address is required".to_string());
+        }
+        for (i, address) in self.listen.iter().enumerate() {
+            if let Err(e) = address.to_socket_addrs() {
+                problems.push(format!(
+                    "listen[{}]: `{}` is not a valid address ({})",
+                    i, address, e
+                ));
+            }
+        }
+        if self.workers == 0 {
+            problems.push("workers: must be at least 1".to_string());
+        }
+        let timeouts = [
+            ("header", self.timeouts.header),
+            ("body", self.timeouts.body),
+            ("write", self.timeouts.write),
+            ("idle", self.timeouts.idle),
+        ];
+        for (name, timeout) in timeouts.iter() {
+            if *timeout == Duration::ZERO {
+                problems.push(format!("timeouts.{}: must be at least 1 second", name));
+            }
+        }
+        if !self.metrics.path.starts_with('/') {
+            problems.push(format!(
+                "metrics.path: `{}` must start with `/`",
+                self.metrics.path
+            ));
+        } else if [server::HEALTHZ, server::READYZ].contains(&self.metrics.path.as_str()) {
+            problems.push(format!(
+                "metrics.path: `{}` is taken by the health checks",
+                self.metrics.path
+            ));
+        }
+        for (i, entry) in self.metrics.allow.iter().enumerate() {
+            if let Err(e) = entry.parse::<Cidr>() {
+                problems.push(format!("metrics.allow[{}]: {}", i, e));
+            }
+        }
+        if let Some(tls) = &self.tls {
+            if tls.listen.is_empty() {
+                problems.push("tls.listen: at least one address is required".to_string());
+            }
+            for (i, address) in tls.listen.iter().enumerate() {
+                if let Err(e) = address.to_socket_addrs() {
+                    problems.push(format!(
+                        "tls.listen[{}]: `{}` is not a valid address ({})",
+                        i, address, e
+                    ));
+                }
+            }
+            if tls.reload_interval == Duration::ZERO {
+                problems.push("tls.reload_interval: must be at least 1 second".to_string());
+            }
+            if let Err(e) = Certificates::new(&tls.cert, &tls.key) {
+                problems.push(format!("tls: {}", e));
+            }
+        }
+
+        validate_site(
+            "",
+            &self.root,
+            &self.index,
+            &self.not_found,
+            &self.routes,
+            &mut problems,
+        );
+
+        let mut hosts = HashSet::new();
+        for (i, vhost) in self.vhosts.iter().enumerate() {
+            let context = format!("vhosts[{}].", i);
+            for name in std::iter::once(&vhost.host).chain(vhost.aliases.iter()) {
+                if name.is_empty()
+                    || name.contains(|c: char| c == ':' || c == '/' || c.is_whitespace())
+                {
+                    problems.push(format!(
+                        "{}host: `{}` is not a valid host name",
+                        context, name
+                    ));
+                } else if !hosts.insert(name.to_ascii_lowercase()) {
+                    problems.push(format!(
+                        "{}host: `{}` is used by more than one virtual host",
+                        context, name
+                    ));
+                }
+            }
+            if let Some(files) = &vhost.tls {
+                if self.tls.is_none() {
+                    problems.push(format!(
+                        "{}tls: needs a `[tls]` table with the HTTPS listeners",
+                        context
+                    ));
+                } else if let Err(e) = Certificates::new(&files.cert, &files.key) {
+                    problems.push(format!("{}tls: {}", context, e));
+                }
+            }
+            validate_site(
+                &context,
+                &vhost.root,
+                &vhost.index,
+                &vhost.not_found,
+                &vhost.routes,
+                &mut problems,
+            );
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+
+    /// Loads the certificates of the default site and the virtual hosts,
+    /// if HTTPS is configured.
+    pub fn certificates(&self) -> Result<Option<Certificates>, TlsError> {
+        let tls = match &self.tls {
+            Some(tls) => tls,
+            None => return Ok(None),
+        };
+        let mut certificates = Certificates::new(&tls.cert, &tls.key)?;
+        for vhost in &self.vhosts {
+            if let Some(files) = &vhost.tls {
+                let names = std::iter::once(&vhost.host).chain(vhost.aliases.iter());
+                certificates = certificates.site(names, &files.cert, &files.key)?;
+            }
+        }
+        Ok(Some(certificates))
+    }
+
+    /// Builds the routers for the default site and the virtual hosts.
+    pub fn sites(&self) -> Sites {
+        let mut sites = Sites::new(build_router(
+            &self.root,
+            &self.index,
+            &self.not_found,
+            self.listing,
+            &self.routes,
+        ));
+        for vhost in &self.vhosts {
+            let router = build_router(
+                &vhost.root,
+                &vhost.index,
+                &vhost.not_found,
+                vhost.listing,
+                &vhost.routes,
+            );
+            let names = std::iter::once(&vhost.host).chain(vhost.aliases.iter());
+            sites = sites.virtual_host(names, router);
+        }
+        sites
+    }
+}
+
+/// Same defaults as the book: `127.0.0.1:7878`, four workers, and the
+/// pages in `public/`.
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            listen: default_listen(),
+            workers: default_workers(),
+            timeouts: Timeouts::default(),
+            metrics: MetricsConfig::default(),
+            tls: None,
+            root: default_root(),
+            index: default_index(),
+            not_found: default_not_found(),
+            listing: false,
+            routes: Vec::new(),
+            vhosts: Vec::new(),
+        }
+    }
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        MetricsConfig {
+            enabled: true,
+            path: default_metrics_path(),
+            allow: default_metrics_allow(),
+        }
+    }
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Timeouts {
+            header: default_header_timeout(),
+            body: default_timeout(),
+            write: default_timeout(),
+            idle: default_idle_timeout(),
+            min_rate: default_min_rate(),
+        }
+    }
+}
+
+fn validate_site(
+    context: &str,
+    root: &Path,
+    index: &str,
+    not_found: &Option<String>,
+    routes: &[RouteConfig],
+    problems: &mut Vec<String>,
+) {
+    if !root.is_dir() {
+        problems.push(format!(
+            "{}root: `{}` is not a directory",
+            context,
+            root.display()
+        ));
+        return;
+    }
+    if index.is_empty() || index.contains('/') {
+        problems.push(format!(
+            "{}index: `{}` must be a plain file name",
+            context, index
+        ));
+    }
+    if let Some(page) = not_found {
+        if !root.join(page).is_file() {
+            problems.push(format!(
+                "{}not_found: `{}` does not exist in `{}`",
+                context,
+                page,
+                root.display()
+            ));
+        }
+    }
+
+    let mut paths = HashSet::new();
+    for (i, route) in routes.iter().enumerate() {
+        let context = format!("{}routes[{}]", context, i);
+        let path = route.path();
+        if !path.starts_with('/') {
+            problems.push(format!("{}.path: `{}` must start with `/`", context, path));
+        } else if !paths.insert(path) {
+            problems.push(format!(
+                "{}.path: `{}` is routed more than once",
+                context, path
+            ));
+        }
+
+        match route {
+            RouteConfig::File { file, .. } | RouteConfig::Sleep { file, .. } => {
+                if !root.join(file).is_file() {
+                    problems.push(format!(
+                        "{}.file: `{}` does not exist in `{}`",
+                        context,
+                        file.display(),
+                        root.display()
+                    ));
+                }
+            }
+            RouteConfig::Proxy { upstreams, .. } => {
+                if upstreams.is_empty() {
+                    problems.push(format!(
+                        "{}.upstreams: at least one upstream is required",
+                        context
+                    ));
+                }
+                for (j, upstream) in upstreams.iter().enumerate() {
+                    if let Err(e) = upstream.to_socket_addrs() {
+                        problems.push(format!(
+                            "{}.upstreams[{}]: `{}` is not a valid host:port ({})",
+                            context, j, upstream, e
+                        ));
+                    }
+                }
+            }
+            RouteConfig::Upload {
+                dir, max_file_size, ..
+            } => {
+                if !root.join(dir).is_dir() {
+                    problems.push(format!(
+                        "{}.dir: `{}` is not a directory in `{}`",
+                        context,
+                        dir.display(),
+                        root.display()
+                    ));
+                }
+                if *max_file_size == 0 {
+                    problems.push(format!("{}.max_file_size: must be at least 1", context));
+                }
+            }
+            RouteConfig::Cgi { dir, timeout, .. } => {
+                if !root.join(dir).is_dir() {
+                    problems.push(format!(
+                        "{}.dir: `{}` is not a directory in `{}`",
+                        context,
+                        dir.display(),
+                        root.display()
+                    ));
+                }
+                if *timeout == Duration::ZERO {
+                    problems.push(format!("{}.timeout: must be at least 1 second", context));
+                }
+            }
+        }
+    }
+}
+
+fn build_router(
+    root: &Path,
+    index: &str,
+    not_found: &Option<String>,
+    listing: bool,
+    routes: &[RouteConfig],
+) -> Router {
+    let mut router = Router::new(root).index(index).listing(listing);
+    if let Some(page) = not_found {
+        router = router.not_found(page);
+    }
+    for route in routes {
+        router = router.route(route.to_route(root));
+    }
+    router
+}
+
+fn seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    u64::deserialize(deserializer).map(Duration::from_secs)
+}
+
+fn default_listen() -> Vec<String> {
+    vec!["127.0.0.1:7878".to_string()]
+}
+
+fn default_workers() -> usize {
+    4
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+
+fn default_header_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_idle_timeout() -> Duration {
+    Duration::from_secs(5)
+}
+
+fn default_min_rate() -> u64 {
+    500
+}
+
+fn default_reload_interval() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_metrics_path() -> String {
+    "/metrics".to_string()
+}
+
+fn default_metrics_allow() -> Vec<String> {
+    vec!["127.0.0.0/8".to_string(), "::1".to_string()]
+}
+
+fn default_root() -> PathBuf {
+    PathBuf::from("public")
+}
+
+fn default_index() -> String {
+    "index.html".to_string()
+}
+
+fn default_not_found() -> Option<String> {
+    Some("404.html".to_string())
+}
+
+/// 10 MiB.
+fn default_max_file_size() -> u64 {
+    10 * 1024 * 1024
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str) -> Result<Config, ConfigError> {
+        Config::parse(contents, Path::new(env!("CARGO_MANIFEST_DIR")))
+    }
+
+    fn problems(contents: &str) -> Vec<String> {
+        match parse(contents) {
+            Err(ConfigError::Invalid(problems)) => problems,
+            other => panic!("expected validation errors, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn empty_file_uses_defaults() {
+        let config = parse("").unwrap();
+        assert_eq!(config.listen, vec!["127.0.0.1:7878"]);
+        assert_eq!(config.workers, 4);
+        assert_eq!(config.timeouts.header, Duration::from_secs(10));
+        assert_eq!(config.timeouts.body, Duration::from_secs(30));
+        assert!(config.root.ends_with("public"));
+    }
+
+    #[test]
+    fn loads_the_shipped_configuration() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(DEFAULT_PATH);
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.routes[0].path(), "/sleep");
+    }
+
+    #[test]
+    fn parses_routes_and_virtual_hosts() {
+        let config = parse(
+            r#"
+            listen = ["127.0.0.1:8080", "[::1]:8080"]
+            workers = 8
+            timeouts = { header = 5, write = 10, min_rate = 0 }
+
+            [[routes]]
+            kind = "sleep"
+            path = "/sleep"
+            file = "index.html"
+            seconds = 1
+
+            [[vhosts]]
+            host = "docs.local"
+            aliases = ["www.docs.local"]
+            root = "public"
+
+            [[vhosts.routes]]
+            kind = "proxy"
+            path = "/api"
+            upstreams = ["127.0.0.1:3000", "localhost:3001"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.listen.len(), 2);
+        assert_eq!(config.timeouts.write, Duration::from_secs(10));
+        assert_eq!(config.timeouts.min_rate, 0);
+        assert_eq!(config.routes[0].path(), "/sleep");
+        assert_eq!(config.vhosts[0].aliases, vec!["www.docs.local"]);
+        assert!(matches!(
+            &config.vhosts[0].routes[0],
+            RouteConfig::Proxy { upstreams, .. } if upstreams.len() == 2
+        ));
+    }
+
+    #[test]
+    fn reports_unknown_keys_and_kinds() {
+        let error = parse("workerz = 4").unwrap_err().to_string();
+        assert!(error.contains("unknown field `workerz`"), "{}", error);
+
+        let error = parse("[[routes]]\nkind = \"teleport\"\npath = \"/\"")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("unknown variant `teleport`"), "{}", error);
+    }
+
+    #[test]
+    fn reports_every_invalid_value() {
+        let problems = problems(
+            r#"
+            listen = ["localhost"]
+            workers = 0
+            timeouts = { body = 0 }
+            metrics = { path = "/healthz", allow = ["::1", "10.0.0.0/40"] }
+
+            [[routes]]
+            kind = "proxy"
+            path = "api"
+            upstreams = []
+
+            [[routes]]
+            kind = "cgi"
+            path = "/cgi-bin"
+            dir = "cgi-bin"
+            timeout = 0
+
+            [[vhosts]]
+            host = "a.local"
+            root = "missing"
+
+            [[vhosts]]
+            host = "A.local"
+            root = "public"
+            not_found = "missing.html"
+            "#,
+        );
+
+        let expected = [
+            "listen[0]: `localhost` is not a valid address",
+            "workers: must be at least 1",
+            "timeouts.body: must be at least 1 second",
+            "metrics.path: `/healthz` is taken by the health checks",
+            "metrics.allow[1]: `40` is not a prefix length from 0 to 32",
+            "routes[0].path: `api` must start with `/`",
+            "routes[0].upstreams: at least one upstream is required",
+            "routes[1].dir: `cgi-bin` is not a directory",
+            "routes[1].timeout: must be at least 1 second",
+            "vhosts[0].root: ",
+            "vhosts[1].host: `A.local` is used by more than one virtual host",
+            "vhosts[1].not_found: `missing.html` does not exist",
+        ];
+        assert_eq!(problems.len(), expected.len(), "{:#?}", problems);
+        for (problem, expected) in problems.iter().zip(expected.iter()) {
+            assert!(problem.starts_with(expected), "{} != {}", problem, expected);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Static files served from a document root.
+use std::{
+    fs::File,
+    io,
+    path::{Component, Path},
+};
+
+use crate::http::{percent_decode, Body, Response};
+
+/// Decodes a request path and drops its `.` segments, e.g. `/a/./b%20c/`
+/// becomes `/a/b c/`. Paths that would go above the root (`..`) or can't be
+/// decoded give `None`.
+pub fn normalize(request_path: &str) -> Option<String> {
+    let decoded = percent_decode(request_path)?;
+    if decoded.contains('\0') || decoded.contains('\\') {
+        return None;
+    }
+
+    let mut path = String::new();
+    for component in Path::new(decoded.trim_start_matches('/')).components() {
+        match component {
+            Component::Normal(segment) => {
+                path.push('/');
+                path.push_str(segment.to_str()?);
+            }
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    if path.is_empty() || decoded.ends_with('/') {
+        path.push('/');
+    }
+    Some(path)
+}
+
+/// Streams `path` from disk as the body of a `status` response.
+pub fn serve(path: &Path, status: u16) -> io::Result<Response> {
+    let file = File::open(path)?;
+    let length = file.metadata()?.len();
+    Ok(Response::new(status)
+        .with_header("Content-Type", content_type(path))
+        .with_body(Body::Reader {
+            reader: Box::new(file),
+            length: Some(length),
+        }))
+}
+
+pub fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "txt" | "md" => "text/plain; charset=utf-8",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "ico" => "image/x-icon",
+        "webp" => "image/webp",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_request_paths() {
+        assert_eq!(normalize("/"), Some("/".to_string()));
+        assert_eq!(normalize(""), Some("/".to_string()));
+        assert_eq!(normalize("/./a//b%2Dc/"), Some("/a/b-c/".to_string()));
+        assert_eq!(normalize("/a/."), Some("/a".to_string()));
+        assert_eq!(normalize("/a/../b"), None);
+        assert_eq!(normalize("/a%5Cb"), None);
+        assert_eq!(
+            normalize("/docs/a%20b.html"),
+            Some("/docs/a b.html".to_string())
+        );
+        assert_eq!(normalize("/../etc/passwd"), None);
+        assert_eq!(normalize("/docs/%2e%2e/%2e%2e/etc"), None);
+        assert_eq!(normalize("/bad%zz"), None);
+    }
+}
@@ -0,0 +1,275 @@
+//! Timeouts for each phase of a client connection, so a client can't hold
+//! on to a worker thread by going quiet, by trickling in a request a few
+//! bytes at a time (slowloris), or by taking its response just as slowly.
+use std::{
+    cell::Cell,
+    io::{self, ErrorKind, Read, Write},
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+
+use crate::config::Timeouts;
+
+/// Time a request head gets before `min_rate` applies to it too. Heads
+/// usually arrive in a single packet, so this is plenty.
+const HEAD_GRACE: Duration = Duration::from_secs(1);
+
+/// What a connection is waiting for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// The next request on a kept-alive connection.
+    Idle,
+    /// The rest of a request's head.
+    Head,
+    /// The request body, while the request is handled.
+    Body,
+    /// The client to take the response.
+    Response,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Idle,
+    Head {
+        started: Instant,
+        received: u64,
+    },
+    Body {
+        started: Instant,
+        received: u64,
+    },
+    /// `rated` responses have to be taken at `min_rate`.
+    Response {
+        started: Instant,
+        sent: u64,
+        rated: bool,
+    },
+}
+
+/// Keeps track of a connection's phase and of when the client runs out of
+/// time in it.
+#[derive(Debug)]
+pub struct Guard {
+    timeouts: Timeouts,
+    state: Cell<State>,
+    timed_out: Cell<Option<Phase>>,
+}
+
+impl Guard {
+    /// Guard for a fresh connection, whose client has `timeouts.header` to
+    /// send its first request head.
+    pub fn new(timeouts: Timeouts) -> Guard {
+        let guard = Guard {
+            timeouts,
+            state: Cell::new(State::Idle),
+            timed_out: Cell::new(None),
+        };
+        guard.start_head();
+        guard
+    }
+
+    /// Waits up to `timeouts.idle` for another request. The header timeout
+    /// starts with its first byte.
+    pub fn idle(&self) {
+        self.state.set(State::Idle);
+    }
+
+    pub fn start_body(&self) {
+        self.state.set(State::Body {
+            started: Instant::now(),
+            received: 0,
+        });
+    }
+
+    /// Starts a response, which the client has to take at `min_rate`
+    /// after `timeouts.write`.
+    pub fn start_response(&self) {
+        self.respond(true);
+    }
+
+    /// Starts a response streamed by a handler, like server-sent events,
+    /// which go on for as long as the handler likes and so aren't held to
+    /// `min_rate`. Every write still has `timeouts.write`.
+    pub fn start_stream(&self) {
+        self.respond(false);
+    }
+
+    fn respond(&self, rated: bool) {
+        self.state.set(State::Response {
+            started: Instant::now(),
+            sent: 0,
+            rated,
+        });
+    }
+
+    /// Phase the client ran out of time in, if it did.
+    pub fn timed_out(&self) -> Option<Phase> {
+        self.timed_out.get()
+    }
+
+    fn start_head(&self) {
+        self.state.set(State::Head {
+            started: Instant::now(),
+            received: 0,
+        });
+    }
+
+    fn phase(&self) -> Phase {
+        match self.state.get() {
+            State::Idle => Phase::Idle,
+            State::Head { .. } => Phase::Head,
+            State::Body { .. } => Phase::Body,
+            State::Response { .. } => Phase::Response,
+        }
+    }
+
+    /// When the client runs out of time if it keeps to `min_rate` after
+    /// `grace`: every byte buys `1 / min_rate` seconds more. `None` if the
+    /// check is off.
+    fn rate_deadline(&self, started: Instant, grace: Duration, bytes: u64) -> Option<Instant> {
+        if self.timeouts.min_rate == 0 {
+            return None;
+        }
+        let earned = bytes as f64 / self.timeouts.min_rate as f64;
+        Some(started + grace + Duration::from_secs_f64(earned))
+    }
+
+    /// How long the next read may block, or `None` if the time is up.
+    fn read_timeout(&self) -> Option<Duration> {
+        let now = Instant::now();
+        let timeout = match self.state.get() {
+            State::Idle => self.timeouts.idle,
+            // The whole head has to arrive in time, however it's split up,
+            // and past the grace period at `min_rate`
+            State::Head { started, received } => {
+                let mut deadline = started + self.timeouts.header;
+                let grace = HEAD_GRACE.min(self.timeouts.header);
+                if let Some(rated) = self.rate_deadline(started, grace, received) {
+                    deadline = deadline.min(rated);
+                }
+                deadline.checked_duration_since(now)?
+            }
+            State::Body { started, received } => {
+                let mut timeout = self.timeouts.body;
+                if let Some(deadline) = self.rate_deadline(started, self.timeouts.body, received) {
+                    timeout = timeout.min(deadline.checked_duration_since(now)?);
+                }
+                timeout
+            }
+            State::Response { .. } => self.timeouts.body,
+        };
+        Some(timeout).filter(|timeout| !timeout.is_zero())
+    }
+
+    /// How long the next write may block, or `None` if the time is up.
+    fn write_timeout(&self) -> Option<Duration> {
+        let mut timeout = self.timeouts.write;
+        if let State::Response {
+            started,
+            sent,
+            rated: true,
+        } = self.state.get()
+        {
+            if let Some(deadline) = self.rate_deadline(started, self.timeouts.write, sent) {
+                timeout = timeout.min(deadline.checked_duration_since(Instant::now())?);
+            }
+        }
+        Some(timeout).filter(|timeout| !timeout.is_zero())
+    }
+
+    fn received(&self, bytes: usize) {
+        match self.state.get() {
+            State::Idle if bytes > 0 => self.start_head(),
+            State::Head { started, received } => self.state.set(State::Head {
+                started,
+                received: received + bytes as u64,
+            }),
+            State::Body { started, received } => self.state.set(State::Body {
+                started,
+                received: received + bytes as u64,
+            }),
+            _ => {}
+        }
+    }
+
+    fn sent(&self, bytes: usize) {
+        if let State::Response {
+            started,
+            sent,
+            rated,
+        } = self.state.get()
+        {
+            self.state.set(State::Response {
+                started,
+                sent: sent + bytes as u64,
+                rated,
+            });
+        }
+    }
+
+    fn expire(&self) -> io::Error {
+        let phase = self.phase();
+        self.timed_out.set(Some(phase));
+        io::Error::new(
+            ErrorKind::TimedOut,
+            format!("client timed out ({:?})", phase),
+        )
+    }
+}
+
+/// A client connection whose reads and writes time out as its `Guard` says.
+pub struct GuardedStream<'a> {
+    stream: &'a TcpStream,
+    guard: &'a Guard,
+}
+
+impl<'a> GuardedStream<'a> {
+    pub fn new(stream: &'a TcpStream, guard: &'a Guard) -> GuardedStream<'a> {
+        GuardedStream { stream, guard }
+    }
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    // Which of the two a socket timeout shows up as depends on the platform
+    e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut
+}
+
+impl Read for GuardedStream<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let timeout = self
+            .guard
+            .read_timeout()
+            .ok_or_else(|| self.guard.expire())?;
+        self.stream.set_read_timeout(Some(timeout))?;
+        match self.stream.read(buf) {
+            Ok(read) => {
+                self.guard.received(read);
+                Ok(read)
+            }
+            Err(e) if is_timeout(&e) => Err(self.guard.expire()),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl Write for GuardedStream<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let timeout = self
+            .guard
+            .write_timeout()
+            .ok_or_else(|| self.guard.expire())?;
+        self.stream.set_write_timeout(Some(timeout))?;
+        match self.stream.write(buf) {
+            Ok(written) => {
+                self.guard.sent(written);
+                Ok(written)
+            }
+            Err(e) if is_timeout(&e) => Err(self.guard.expire()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
@@ -0,0 +1,55 @@
+use std::io::{self, BufRead, Read, Take};
+
+use super::{ChunkedReader, Headers, Request};
+
+enum Kind<'a> {
+    Empty,
+    Fixed(Take<&'a mut dyn BufRead>),
+    Chunked(ChunkedReader<&'a mut dyn BufRead>),
+}
+
+/// Body of a request, read straight from the connection: either
+/// `Content-Length` bytes or a decoded chunked body.
+pub struct RequestBody<'a> {
+    kind: Kind<'a>,
+}
+
+impl<'a> RequestBody<'a> {
+    pub fn new(request: &Request, reader: &'a mut dyn BufRead) -> RequestBody<'a> {
+        let kind = if request.is_chunked() {
+            Kind::Chunked(ChunkedReader::new(reader))
+        } else {
+            match request.content_length() {
+                0 => Kind::Empty,
+                length => Kind::Fixed(reader.take(length)),
+            }
+        };
+        RequestBody { kind }
+    }
+
+    pub fn empty() -> RequestBody<'static> {
+        RequestBody { kind: Kind::Empty }
+    }
+
+    pub fn is_chunked(&self) -> bool {
+        matches!(self.kind, Kind::Chunked(_))
+    }
+
+    /// Trailers of a chunked body, once it was read to the end.
+    pub fn trailers(&self) -> Option<&Headers> {
+        match &self.kind {
+            Kind::Chunked(reader) => reader.trailers(),
+            _ => None,
+        }
+    }
+}
+
+impl Read for RequestBody<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.kind {
+            Kind::Empty => Ok(0),
+            Kind::Fixed(reader) => reader.read(buf),
+            Kind::Chunked(reader) => reader.read(buf),
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! `Transfer-Encoding: chunked` (RFC 7230, 4.1): a body sent as a series of
+//! size-prefixed chunks, ended by an empty chunk and optional trailers.
+use std::io::{self, BufRead, Read, Write};
+
+use super::{read_headers, read_line, Headers, ParseError};
+
+/// Chunk sizes beyond 16 hex digits would overflow a `u64`.
+const MAX_SIZE_DIGITS: usize = 16;
+
+enum State {
+    /// Expecting a chunk-size line.
+    Size,
+    /// Inside a chunk, with this many bytes left.
+    Data(u64),
+    Done,
+}
+
+/// Decodes a chunked body. Reads return the chunk data only; the trailers
+/// become available once the reader returned EOF.
+pub struct ChunkedReader<R> {
+    inner: R,
+    state: State,
+    trailers: Option<Headers>,
+}
+
+impl<R: BufRead> ChunkedReader<R> {
+    pub fn new(inner: R) -> Self {
+        ChunkedReader {
+            inner,
+            state: State::Size,
+            trailers: None,
+        }
+    }
+
+    /// Trailer fields sent after the last chunk, once the body was read
+    /// to the end.
+    pub fn trailers(&self) -> Option<&Headers> {
+        self.trailers.as_ref()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn read_size(&mut self) -> Result<u64, ParseError> {
+        let line = read_line(&mut self.inner)?.ok_or(ParseError::Closed)?;
+        // Chunk extensions (`;name=value`) carry nothing we use
+        let size = line.split(';').next().unwrap_or("").trim();
+        if size.is_empty() || size.len() > MAX_SIZE_DIGITS {
+            return Err(ParseError::Malformed("invalid chunk size"));
+        }
+        u64::from_str_radix(size, 16).map_err(|_| ParseError::Malformed("invalid chunk size"))
+    }
+}
+
+impl<R: BufRead> Read for ChunkedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match self.state {
+                State::Done => return Ok(0),
+                State::Size => match self.read_size()? {
+                    0 => {
+                        self.trailers = Some(read_headers(&mut self.inner)?);
+                        self.state = State::Done;
+                    }
+                    size => self.state = State::Data(size),
+                },
+                State::Data(0) => {
+                    match read_line(&mut self.inner)? {
+                        Some(line) if line.is_empty() => {}
+                        _ => return Err(ParseError::Malformed("missing CRLF after chunk").into()),
+                    }
+                    self.state = State::Size;
+                }
+                State::Data(remaining) => {
+                    if buf.is_empty() {
+                        return Ok(0);
+                    }
+                    let max = remaining.min(buf.len() as u64) as usize;
+                    let read = self.inner.read(&mut buf[..max])?;
+                    if read == 0 {
+                        return Err(ParseError::Closed.into());
+                    }
+                    self.state = State::Data(remaining - read as u64);
+                    return Ok(read);
+                }
+            }
+        }
+    }
+}
+
+/// Encodes everything written to it as chunks. Call `finish` to send the
+/// last chunk; dropping the writer leaves the body unterminated.
+pub struct ChunkedWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> ChunkedWriter<W> {
+    pub fn new(inner: W) -> Self {
+        ChunkedWriter { inner }
+    }
+
+    /// Writes the last chunk and the trailers, if any.
+    pub fn finish(mut self, trailers: Option<&Headers>) -> io::Result<W> {
+        self.inner.write_all(b"0\r\n")?;
+        for (name, value) in trailers.into_iter().flat_map(Headers::iter) {
+            write!(self.inner, "{}: {}\r\n", name, value)?;
+        }
+        self.inner.write_all(b"\r\n")?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for ChunkedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // An empty chunk would end the body
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        write!(self.inner, "{:X}\r\n", buf.len())?;
+        self.inner.write_all(buf)?;
+        self.inner.write_all(b"\r\n")?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(raw: &str) -> io::Result<(String, Option<Headers>)> {
+        let mut reader = ChunkedReader::new(raw.as_bytes());
+        let mut body = String::new();
+        reader.read_to_string(&mut body)?;
+        Ok((body, reader.trailers().cloned()))
+    }
+
+    #[test]
+    fn decodes_chunks_and_trailers() {
+        let (body, trailers) =
+            decode("4\r\nWiki\r\n6;ext=1\r\npedia \r\nE\r\nin \r\n\r\nchunks.\r\n0\r\nExpires: never\r\n\r\n")
+                .unwrap();
+        assert_eq!(body, "Wikipedia in \r\n\r\nchunks.");
+        assert_eq!(trailers.unwrap().get("expires"), Some("never"));
+
+        let (body, trailers) = decode("0\r\n\r\n").unwrap();
+        assert_eq!(body, "");
+        assert!(trailers.unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_broken_chunks() {
+        assert!(decode("zz\r\nhello\r\n0\r\n\r\n").is_err());
+        assert!(decode("5\r\nhello0\r\n\r\n").is_err());
+        assert!(decode("5\r\nhel").is_err());
+        assert!(decode("5\r\nhello\r\n").is_err());
+        assert!(decode("11111111111111111\r\n").is_err());
+    }
+
+    #[test]
+    fn encodes_what_it_decodes() {
+        let mut writer = ChunkedWriter::new(Vec::new());
+        writer.write_all(b"hello ").unwrap();
+        writer.write_all(b"").unwrap();
+        writer.write_all(b"world").unwrap();
+        let mut trailers = Headers::new();
+        trailers.append("Checksum", "abc");
+        let encoded = writer.finish(Some(&trailers)).unwrap();
+        assert_eq!(
+            encoded,
+            b"6\r\nhello \r\n5\r\nworld\r\n0\r\nChecksum: abc\r\n\r\n".to_vec()
+        );
+
+        let (body, trailers) = decode(std::str::from_utf8(&encoded).unwrap()).unwrap();
+        assert_eq!(body, "hello world");
+        assert_eq!(trailers.unwrap().get("Checksum"), Some("abc"));
+    }
+}
@@ -0,0 +1,150 @@
+//! Just enough HTTP/1.1 to parse requests and write responses by hand,
+//! continuing where the book's `buffer.starts_with(get)` left off.
+use std::{
+    error, fmt,
+    io::{self, BufRead, Read},
+};
+
+mod body;
+mod chunked;
+mod headers;
+mod request;
+mod response;
+pub mod sse;
+
+pub use body::RequestBody;
+pub use chunked::{ChunkedReader, ChunkedWriter};
+pub use headers::Headers;
+pub use request::Request;
+pub use response::{reason_phrase, Body, Response};
+
+/// Longest start line or header line we are willing to buffer.
+const MAX_LINE_LENGTH: u64 = 8 * 1024;
+/// Most header fields accepted in a single message.
+const MAX_HEADERS: usize = 100;
+
+#[derive(Debug)]
+pub enum ParseError {
+    /// The peer closed the connection before sending anything.
+    Closed,
+    Io(io::Error),
+    Malformed(&'static str),
+    /// A line, or the number of headers, went over the limits above.
+    TooLarge,
+    /// Valid HTTP we don't handle, like transfer codings other than chunked.
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Closed => write!(f, "connection closed"),
+            ParseError::Io(e) => write!(f, "{}", e),
+            ParseError::Malformed(reason) => write!(f, "malformed message: {}", reason),
+            ParseError::TooLarge => write!(f, "message head too large"),
+            ParseError::Unsupported(what) => write!(f, "unsupported: {}", what),
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+impl From<io::Error> for ParseError {
+    fn from(e: io::Error) -> Self {
+        ParseError::Io(e)
+    }
+}
+
+/// Parse errors in the middle of a body surface through `Read`, so they
+/// have to become I/O errors.
+impl From<ParseError> for io::Error {
+    fn from(e: ParseError) -> Self {
+        match e {
+            ParseError::Io(e) => e,
+            ParseError::Closed => io::Error::new(io::ErrorKind::UnexpectedEof, e.to_string()),
+            e => io::Error::new(io::ErrorKind::InvalidData, e.to_string()),
+        }
+    }
+}
+
+/// Reads a message head, i.e. the start line and the header fields up to
+/// the empty line. Shared by request parsing and by the proxy, which has to
+/// parse the responses coming back from upstream servers.
+pub(crate) fn read_head<R: BufRead>(reader: &mut R) -> Result<(String, Headers), ParseError> {
+    let mut start_line = read_line(reader)?.ok_or(ParseError::Closed)?;
+    // Robust servers ignore empty lines before the request line (RFC 7230, 3.5)
+    while start_line.is_empty() {
+        start_line = read_line(reader)?.ok_or(ParseError::Closed)?;
+    }
+    Ok((start_line, read_headers(reader)?))
+}
+
+/// Reads header fields up to the empty line, also used for the trailers
+/// at the end of a chunked body.
+pub(crate) fn read_headers<R: BufRead>(reader: &mut R) -> Result<Headers, ParseError> {
+    let mut headers = Headers::new();
+    loop {
+        let line = read_line(reader)?.ok_or(ParseError::Malformed("unexpected end of headers"))?;
+        if line.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(ParseError::TooLarge);
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or(ParseError::Malformed("header without a colon"))?;
+        if name.is_empty() || name.ends_with(char::is_whitespace) {
+            return Err(ParseError::Malformed("invalid header name"));
+        }
+        headers.append(name, value.trim());
+    }
+    Ok(headers)
+}
+
+/// Decodes `%XX` escapes, as used in request paths. Returns `None` for
+/// broken escapes or if the result isn't UTF-8.
+pub fn percent_decode(input: &str) -> Option<String> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = input.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Reads a single line without its `\r\n` (or bare `\n`) terminator.
+/// Returns `None` if the reader is already at EOF.
+pub(crate) fn read_line<R: BufRead>(reader: &mut R) -> Result<Option<String>, ParseError> {
+    let mut line = Vec::new();
+    reader
+        .by_ref()
+        .take(MAX_LINE_LENGTH + 1)
+        .read_until(b'\n', &mut line)?;
+
+    if line.is_empty() {
+        return Ok(None);
+    }
+    if !line.ends_with(b"\n") {
+        return if line.len() as u64 > MAX_LINE_LENGTH {
+            Err(ParseError::TooLarge)
+        } else {
+            Err(ParseError::Malformed("unexpected end of line"))
+        };
+    }
+    line.pop();
+    if line.ends_with(b"\r") {
+        line.pop();
+    }
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|_| ParseError::Malformed("non UTF-8 message head"))
+}
@@ -0,0 +1,193 @@
+use std::{io::BufRead, net::SocketAddr};
+
+use super::{percent_decode, read_head, Headers, ParseError};
+
+/// Head of an incoming request. The body is left in the connection, so
+/// handlers can stream it instead of buffering it up front.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    /// Request target as sent by the client, i.e. path plus query string.
+    pub target: String,
+    pub version: String,
+    pub headers: Headers,
+    pub remote_addr: Option<SocketAddr>,
+    /// Whether the request came in over HTTPS.
+    pub tls: bool,
+}
+
+impl Request {
+    pub fn read_from<R: BufRead>(reader: &mut R) -> Result<Request, ParseError> {
+        let (request_line, headers) = read_head(reader)?;
+
+        let mut parts = request_line.split(' ');
+        let (method, target, version) =
+            match (parts.next(), parts.next(), parts.next(), parts.next()) {
+                (Some(method), Some(target), Some(version), None) => (method, target, version),
+                _ => return Err(ParseError::Malformed("invalid request line")),
+            };
+        if method.is_empty() || !method.bytes().all(|b| b.is_ascii_uppercase()) {
+            return Err(ParseError::Malformed("invalid method"));
+        }
+        if !target.starts_with('/') && target != "*" {
+            return Err(ParseError::Malformed("invalid request target"));
+        }
+        if version != "HTTP/1.1" && version != "HTTP/1.0" {
+            return Err(ParseError::Malformed("unsupported HTTP version"));
+        }
+
+        if headers.contains("Transfer-Encoding") {
+            // A body with both framings is a request smuggling attempt
+            // waiting to happen (RFC 7230, 3.3.3)
+            if headers.contains("Content-Length") {
+                return Err(ParseError::Malformed(
+                    "both transfer-encoding and content-length",
+                ));
+            }
+            let codings: Vec<&str> = headers
+                .iter()
+                .filter(|(name, _)| name.eq_ignore_ascii_case("Transfer-Encoding"))
+                .flat_map(|(_, value)| value.split(','))
+                .map(str::trim)
+                .collect();
+            if codings.len() != 1 || !codings[0].eq_ignore_ascii_case("chunked") {
+                return Err(ParseError::Unsupported(
+                    "transfer coding other than chunked",
+                ));
+            }
+        }
+        if let Some(length) = headers.get("Content-Length") {
+            if length.parse::<u64>().is_err() {
+                return Err(ParseError::Malformed("invalid content-length"));
+            }
+        }
+
+        Ok(Request {
+            method: method.to_string(),
+            target: target.to_string(),
+            version: version.to_string(),
+            headers,
+            remote_addr: None,
+            tls: false,
+        })
+    }
+
+    /// Path part of the target, without the query string.
+    pub fn path(&self) -> &str {
+        match self.target.split_once('?') {
+            Some((path, _)) => path,
+            None => &self.target,
+        }
+    }
+
+    pub fn query(&self) -> Option<&str> {
+        self.target.split_once('?').map(|(_, query)| query)
+    }
+
+    /// Decoded value of the first `name` parameter in the query string.
+    pub fn query_param(&self, name: &str) -> Option<String> {
+        self.query()?
+            .split('&')
+            .filter_map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                let key = percent_decode(&key.replace('+', " "))?;
+                Some((key, value))
+            })
+            .find(|(key, _)| key == name)
+            .and_then(|(_, value)| percent_decode(&value.replace('+', " ")))
+    }
+
+    /// Host the request was sent to, without the port.
+    pub fn host(&self) -> Option<&str> {
+        let host = self.headers.get("Host")?;
+        let end = if host.starts_with('[') {
+            host.find(']').map_or(host.len(), |i| i + 1)
+        } else {
+            host.find(':').unwrap_or(host.len())
+        };
+        Some(&host[..end])
+    }
+
+    /// Whether the body is sent with `Transfer-Encoding: chunked`.
+    pub fn is_chunked(&self) -> bool {
+        self.headers.has_token("Transfer-Encoding", "chunked")
+    }
+
+    /// Declared `Content-Length`, zero without one (or for chunked bodies).
+    pub fn content_length(&self) -> u64 {
+        self.headers
+            .get("Content-Length")
+            .and_then(|length| length.parse().ok())
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(raw: &str) -> Result<Request, ParseError> {
+        Request::read_from(&mut raw.as_bytes())
+    }
+
+    #[test]
+    fn parses_request_head() {
+        let request = parse(
+            "GET /index.html?lang=en HTTP/1.1\r\nHost: localhost:7878\r\nAccept: */*\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path(), "/index.html");
+        assert_eq!(request.query(), Some("lang=en"));
+        assert_eq!(request.host(), Some("localhost"));
+        assert_eq!(request.headers.get("accept"), Some("*/*"));
+        assert_eq!(request.content_length(), 0);
+    }
+
+    #[test]
+    fn decodes_query_parameters() {
+        let request = parse("GET /files/?sort=size&q=a+b%21&flag HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(request.query_param("sort").as_deref(), Some("size"));
+        assert_eq!(request.query_param("q").as_deref(), Some("a b!"));
+        assert_eq!(request.query_param("flag").as_deref(), Some(""));
+        assert_eq!(request.query_param("order"), None);
+    }
+
+    #[test]
+    fn rejects_malformed_requests() {
+        assert!(matches!(parse(""), Err(ParseError::Closed)));
+        assert!(matches!(
+            parse("GET /\r\n\r\n"),
+            Err(ParseError::Malformed(_))
+        ));
+        assert!(matches!(
+            parse("GET / HTTP/1.1\r\nHost localhost\r\n\r\n"),
+            Err(ParseError::Malformed(_))
+        ));
+        assert!(matches!(
+            parse("GET / HTTP/1.1\r\nContent-Length: ten\r\n\r\n"),
+            Err(ParseError::Malformed(_))
+        ));
+        assert!(matches!(
+            parse("GET / HTTP/1.1\r\nHost: a\r\n"),
+            Err(ParseError::Malformed(_))
+        ));
+
+        assert!(matches!(
+            parse("POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nContent-Length: 4\r\n\r\n"),
+            Err(ParseError::Malformed(_))
+        ));
+        assert!(matches!(
+            parse("POST / HTTP/1.1\r\nTransfer-Encoding: gzip, chunked\r\n\r\n"),
+            Err(ParseError::Unsupported(_))
+        ));
+        assert!(
+            parse("POST / HTTP/1.1\r\nTransfer-Encoding: Chunked\r\n\r\n")
+                .unwrap()
+                .is_chunked()
+        );
+
+        let long_line = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(9000));
+        assert!(matches!(parse(&long_line), Err(ParseError::TooLarge)));
+    }
+}
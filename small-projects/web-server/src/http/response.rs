@@ -0,0 +1,238 @@
+use std::{
+    fmt,
+    io::{self, Read, Write},
+};
+
+use super::{ChunkedWriter, Headers, Request};
+
+/// Produces a body incrementally, by writing to the connection.
+pub type StreamFn = Box<dyn FnOnce(&mut dyn Write) -> io::Result<()> + Send>;
+
+pub enum Body {
+    Empty,
+    Bytes(Vec<u8>),
+    /// Body copied straight from a reader, e.g. a file or an upstream
+    /// connection. Exactly `length` bytes are sent, whatever else the reader
+    /// has; without a `length` it runs until the reader hits EOF.
+    Reader {
+        reader: Box<dyn Read + Send>,
+        length: Option<u64>,
+    },
+    /// Body written piece by piece by a handler, e.g. server-sent events.
+    Stream(StreamFn),
+}
+
+impl Body {
+    pub fn length(&self) -> Option<u64> {
+        match self {
+            Body::Empty => Some(0),
+            Body::Bytes(bytes) => Some(bytes.len() as u64),
+            Body::Reader { length, .. } => *length,
+            Body::Stream(_) => None,
+        }
+    }
+}
+
+impl fmt::Debug for Body {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Body::Empty => write!(f, "Body::Empty"),
+            Body::Bytes(bytes) => write!(f, "Body::Bytes({} bytes)", bytes.len()),
+            Body::Reader { length, .. } => write!(f, "Body::Reader({:?})", length),
+            Body::Stream(_) => write!(f, "Body::Stream"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Headers,
+    pub body: Body,
+}
+
+impl Response {
+    pub fn new(status: u16) -> Response {
+        Response {
+            status,
+            headers: Headers::new(),
+            body: Body::Empty,
+        }
+    }
+
+    pub fn html(status: u16, contents: impl Into<Vec<u8>>) -> Response {
+        Response::new(status)
+            .with_header("Content-Type", "text/html; charset=utf-8")
+            .with_body(Body::Bytes(contents.into()))
+    }
+
+    pub fn text(status: u16, contents: impl Into<Vec<u8>>) -> Response {
+        Response::new(status)
+            .with_header("Content-Type", "text/plain; charset=utf-8")
+            .with_body(Body::Bytes(contents.into()))
+    }
+
+    /// Plain text response whose body is just the status line, e.g. `502 Bad Gateway`.
+    pub fn error(status: u16) -> Response {
+        Response::text(status, format!("{} {}\n", status, reason_phrase(status)))
+    }
+
+    pub fn with_header(mut self, name: &str, value: impl Into<String>) -> Response {
+        self.headers.set(name, value);
+        self
+    }
+
+    pub fn with_body(mut self, body: Body) -> Response {
+        self.body = body;
+        self
+    }
+
+    /// Serializes the response to `request`, or to a request that could not
+    /// be parsed. `Content-Length` is always derived from the body; bodies
+    /// of unknown length are sent chunked to HTTP/1.1 clients.
+    ///
+    /// Returns whether the connection can be kept open for another request:
+    /// only if the caller wants to `keep_alive`, and the body's end doesn't
+    /// have to be marked by closing the connection.
+    pub fn write_to<W: Write>(
+        self,
+        out: &mut W,
+        request: Option<&Request>,
+        keep_alive: bool,
+    ) -> io::Result<bool> {
+        let Response {
+            status,
+            mut headers,
+            body,
+        } = self;
+        let head_only = request.is_some_and(|request| request.method == "HEAD");
+        let chunked = request.is_some_and(|request| request.version == "HTTP/1.1");
+
+        headers.remove("Transfer-Encoding");
+        match body.length() {
+            Some(length) => headers.set("Content-Length", length.to_string()),
+            None if chunked => {
+                headers.remove("Content-Length");
+                headers.set("Transfer-Encoding", "chunked");
+            }
+            // HTTP/1.0 clients read until the connection is closed
+            None => headers.remove("Content-Length"),
+        }
+        let keep_alive = keep_alive
+            && request.is_some()
+            && (body.length().is_some() || chunked || head_only)
+            && !headers.has_token("Connection", "close");
+        match request {
+            Some(request) if keep_alive && request.version == "HTTP/1.0" => {
+                headers.set("Connection", "keep-alive")
+            }
+            // Persistent is the default in HTTP/1.1
+            _ if keep_alive => headers.remove("Connection"),
+            _ => headers.set("Connection", "close"),
+        }
+
+        let mut head = format!("HTTP/1.1 {} {}\r\n", status, reason_phrase(status));
+        for (name, value) in headers.iter() {
+            head.push_str(name);
+            head.push_str(": ");
+            head.push_str(value);
+            head.push_str("\r\n");
+        }
+        head.push_str("\r\n");
+        out.write_all(head.as_bytes())?;
+
+        if !head_only {
+            match body {
+                Body::Empty => {}
+                Body::Bytes(bytes) => out.write_all(&bytes)?,
+                Body::Reader {
+                    reader,
+                    length: Some(length),
+                } => {
+                    // A file can grow after its length was taken: sending
+                    // more than `Content-Length` would break the framing
+                    if io::copy(&mut reader.take(length), out)? < length {
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "body shorter than its content-length",
+                        ));
+                    }
+                }
+                Body::Reader { mut reader, .. } if chunked => {
+                    let mut writer = ChunkedWriter::new(&mut *out);
+                    io::copy(&mut reader, &mut writer)?;
+                    writer.finish(None)?;
+                }
+                Body::Reader { mut reader, .. } => {
+                    io::copy(&mut reader, out)?;
+                }
+                Body::Stream(produce) if chunked => {
+                    let mut writer = ChunkedWriter::new(&mut *out);
+                    produce(&mut writer)?;
+                    writer.finish(None)?;
+                }
+                Body::Stream(produce) => produce(out)?,
+            }
+        }
+        out.flush()?;
+        Ok(keep_alive)
+    }
+}
+
+pub fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        100 => "Continue",
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        414 => "URI Too Long",
+        415 => "Unsupported Media Type",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        505 => "HTTP Version Not Supported",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reader(contents: &'static [u8], length: u64) -> Response {
+        Response::new(200).with_body(Body::Reader {
+            reader: Box::new(contents),
+            length: Some(length),
+        })
+    }
+
+    #[test]
+    fn sends_no_more_than_the_content_length() {
+        let mut out = Vec::new();
+        reader(b"hello, grown", 5)
+            .write_to(&mut out, None, false)
+            .unwrap();
+        assert!(out.ends_with(b"Content-Length: 5\r\nConnection: close\r\n\r\nhello"));
+    }
+
+    #[test]
+    fn fails_on_bodies_shorter_than_the_content_length() {
+        let error = reader(b"hi", 5)
+            .write_to(&mut Vec::new(), None, false)
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,130 @@
+//! Server-Sent Events: a `text/event-stream` response that stays open while
+//! the server pushes events to the browser's `EventSource`.
+//! https://html.spec.whatwg.org/multipage/server-sent-events.html
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+use super::{Body, Response};
+
+#[derive(Debug, Clone, Default)]
+pub struct Event {
+    pub id: Option<String>,
+    /// Event type; browsers dispatch events without one as `message`.
+    pub event: Option<String>,
+    pub data: String,
+    /// How long the browser should wait before reconnecting.
+    pub retry: Option<Duration>,
+}
+
+impl Event {
+    pub fn new(data: impl Into<String>) -> Event {
+        Event {
+            data: data.into(),
+            ..Event::default()
+        }
+    }
+
+    pub fn id(mut self, id: impl Into<String>) -> Event {
+        self.id = Some(id.into());
+        self
+    }
+
+    pub fn event(mut self, event: impl Into<String>) -> Event {
+        self.event = Some(event.into());
+        self
+    }
+
+    pub fn retry(mut self, retry: Duration) -> Event {
+        self.retry = Some(retry);
+        self
+    }
+
+    fn write_to(&self, out: &mut dyn Write) -> io::Result<()> {
+        // Newlines would end a field early, so ids and types can't have any
+        if let Some(id) = &self.id {
+            writeln!(out, "id: {}", id.replace(['\r', '\n'], ""))?;
+        }
+        if let Some(event) = &self.event {
+            writeln!(out, "event: {}", event.replace(['\r', '\n'], ""))?;
+        }
+        if let Some(retry) = self.retry {
+            writeln!(out, "retry: {}", retry.as_millis())?;
+        }
+        // Multi-line data is sent as one `data` field per line
+        for line in self.data.split('\n') {
+            writeln!(out, "data: {}", line.trim_end_matches('\r'))?;
+        }
+        writeln!(out)
+    }
+}
+
+/// Handed to the closure of `Response::event_stream` to push events.
+pub struct EventWriter<'a> {
+    out: &'a mut dyn Write,
+}
+
+impl EventWriter<'_> {
+    /// Sends an event right away. Fails once the client went away.
+    pub fn send(&mut self, event: &Event) -> io::Result<()> {
+        // Formatted up front, so each event goes out as a single chunk
+        let mut buffer = Vec::new();
+        event.write_to(&mut buffer)?;
+        self.out.write_all(&buffer)?;
+        self.out.flush()
+    }
+
+    /// Sends a comment, which clients ignore; handy to keep idle
+    /// connections from timing out.
+    pub fn comment(&mut self, text: &str) -> io::Result<()> {
+        let mut buffer = Vec::new();
+        for line in text.split('\n') {
+            writeln!(buffer, ": {}", line.trim_end_matches('\r'))?;
+        }
+        writeln!(buffer)?;
+        self.out.write_all(&buffer)?;
+        self.out.flush()
+    }
+}
+
+impl Response {
+    /// Streams the events `produce` sends, until it returns.
+    pub fn event_stream<F>(produce: F) -> Response
+    where
+        F: FnOnce(&mut EventWriter) -> io::Result<()> + Send + 'static,
+    {
+        Response::new(200)
+            .with_header("Content-Type", "text/event-stream")
+            .with_header("Cache-Control", "no-cache")
+            .with_body(Body::Stream(Box::new(move |out: &mut dyn Write| {
+                produce(&mut EventWriter { out })
+            })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_events() {
+        let mut out = Vec::new();
+        let mut writer = EventWriter { out: &mut out };
+        writer
+            .send(
+                &Event::new("line one\nline two")
+                    .id("7")
+                    .event("update")
+                    .retry(Duration::from_secs(3)),
+            )
+            .unwrap();
+        writer.comment("ping").unwrap();
+        writer.send(&Event::new("")).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "id: 7\nevent: update\nretry: 3000\ndata: line one\ndata: line two\n\n: ping\n\ndata: \n\n"
+        );
+    }
+}
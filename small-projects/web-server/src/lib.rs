@@ -0,0 +1,14 @@
+pub mod cgi;
+pub mod config;
+pub mod files;
+pub mod guard;
+pub mod http;
+pub mod listing;
+pub mod metrics;
+pub mod multipart;
+pub mod pool;
+pub mod proxy;
+pub mod router;
+pub mod server;
+pub mod tls;
+pub mod upload;
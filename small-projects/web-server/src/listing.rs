@@ -0,0 +1,295 @@
+//! Listing of a directory without an index page, as HTML or JSON.
+//!
+//! `?sort=name|size|mtime` and `?order=asc|desc` pick the order, and
+//! `?format=json` (or `Accept: application/json`) the format.
+use std::{cmp::Ordering, fs, io, path::Path, time::UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::http::{Body, Request, Response};
+
+#[derive(Debug, Serialize)]
+pub struct Entry {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: EntryKind,
+    /// Size in bytes, zero for directories.
+    pub size: u64,
+    /// Last modification, in seconds since the Unix epoch.
+    pub modified: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    File,
+    Directory,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Name,
+    Size,
+    Modified,
+}
+
+impl SortKey {
+    fn parse(value: &str) -> Option<SortKey> {
+        match value {
+            "name" => Some(SortKey::Name),
+            "size" => Some(SortKey::Size),
+            "mtime" => Some(SortKey::Modified),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::Size => "size",
+            SortKey::Modified => "mtime",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Listing<'a> {
+    path: &'a str,
+    entries: &'a [Entry],
+}
+
+/// Reads the entries of `dir`, skipping hidden ones (starting with `.`).
+pub fn read_entries(dir: &Path) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = match entry.file_name().into_string() {
+            Ok(name) if !name.starts_with('.') => name,
+            _ => continue,
+        };
+        // Follows symlinks, so a link to a directory is listed as one
+        let metadata = match fs::metadata(entry.path()) {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        let kind = if metadata.is_dir() {
+            EntryKind::Directory
+        } else {
+            EntryKind::File
+        };
+        entries.push(Entry {
+            name,
+            kind,
+            size: if metadata.is_dir() { 0 } else { metadata.len() },
+            modified: metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |since| since.as_secs()),
+        });
+    }
+    Ok(entries)
+}
+
+/// Sorts by `key`, falling back to the name for equal keys, so the order is
+/// stable between requests.
+pub fn sort_entries(entries: &mut [Entry], key: SortKey, descending: bool) {
+    entries.sort_by(|a, b| {
+        let ordering = match key {
+            SortKey::Name => Ordering::Equal,
+            SortKey::Size => a.size.cmp(&b.size),
+            SortKey::Modified => a.modified.cmp(&b.modified),
+        }
+        .then_with(|| a.name.cmp(&b.name));
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+}
+
+/// Listing of `dir`, which `request` (for a path ending in `/`) points at.
+pub fn respond(request: &Request, dir: &Path) -> Response {
+    let mut entries = match read_entries(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Failed to list {}: {}", dir.display(), e);
+            return Response::error(500);
+        }
+    };
+
+    let key = request
+        .query_param("sort")
+        .and_then(|key| SortKey::parse(&key))
+        .unwrap_or(SortKey::Name);
+    let descending = request.query_param("order").as_deref() == Some("desc");
+    sort_entries(&mut entries, key, descending);
+
+    let path = request.path();
+    if wants_json(request) {
+        let listing = Listing {
+            path,
+            entries: &entries,
+        };
+        let json = serde_json::to_vec_pretty(&listing).expect("listing is always serializable");
+        Response::new(200)
+            .with_header("Content-Type", "application/json")
+            .with_body(Body::Bytes(json))
+    } else {
+        Response::html(200, to_html(path, &entries, key, descending))
+    }
+}
+
+fn wants_json(request: &Request) -> bool {
+    match request.query_param("format").as_deref() {
+        Some("json") => true,
+        Some(_) => false,
+        None => request.headers.get("Accept").is_some_and(|accept| {
+            accept.contains("application/json") && !accept.contains("text/html")
+        }),
+    }
+}
+
+fn to_html(path: &str, entries: &[Entry], key: SortKey, descending: bool) -> String {
+    let title = format!("Index of {}", escape(path));
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n  <head>\n    <meta charset=\"utf-8\">\n    \
+         <title>{0}</title>\n  </head>\n  <body>\n    <h1>{0}</h1>\n    <table>\n      <tr>",
+        title
+    );
+    for (column, label) in [
+        (SortKey::Name, "Name"),
+        (SortKey::Size, "Size"),
+        (SortKey::Modified, "Modified"),
+    ]
+    .iter()
+    {
+        // Clicking the column the listing is sorted by flips the order
+        let order = if *column == key && !descending {
+            "desc"
+        } else {
+            "asc"
+        };
+        html.push_str(&format!(
+            "<th><a href=\"?sort={}&amp;order={}\">{}</a></th>",
+            column.as_str(),
+            order,
+            label
+        ));
+    }
+    html.push_str("</tr>\n");
+
+    if path != "/" {
+        html.push_str("      <tr><td><a href=\"../\">../</a></td><td></td><td></td></tr>\n");
+    }
+    for entry in entries {
+        let suffix = if entry.kind == EntryKind::Directory {
+            "/"
+        } else {
+            ""
+        };
+        html.push_str(&format!(
+            "      <tr><td><a href=\"{}{}\">{}{}</a></td><td>{}</td><td>{}</td></tr>\n",
+            encode_segment(&entry.name),
+            suffix,
+            escape(&entry.name),
+            suffix,
+            if entry.kind == EntryKind::Directory {
+                "-".to_string()
+            } else {
+                entry.size.to_string()
+            },
+            format_time(entry.modified)
+        ));
+    }
+    html.push_str("    </table>\n  </body>\n</html>\n");
+    html
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Percent-encodes everything but unreserved characters (RFC 3986, 2.3).
+fn encode_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// `YYYY-MM-DD HH:MM` in UTC, from seconds since the epoch.
+fn format_time(seconds: u64) -> String {
+    if seconds == 0 {
+        return "-".to_string();
+    }
+    let days = (seconds / 86_400) as i64;
+    let minutes = seconds % 86_400 / 60;
+
+    // Civil from days, http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, size: u64, modified: u64) -> Entry {
+        Entry {
+            name: name.to_string(),
+            kind: EntryKind::File,
+            size,
+            modified,
+        }
+    }
+
+    #[test]
+    fn sorts_by_key_then_name() {
+        let mut entries = vec![entry("b", 10, 3), entry("c", 5, 1), entry("a", 10, 2)];
+
+        sort_entries(&mut entries, SortKey::Size, false);
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["c", "a", "b"]);
+
+        sort_entries(&mut entries, SortKey::Modified, true);
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["b", "a", "c"]);
+    }
+
+    #[test]
+    fn formats_times_and_names() {
+        assert_eq!(format_time(0), "-");
+        assert_eq!(format_time(951_782_400), "2000-02-29 00:00");
+        assert_eq!(format_time(1_700_000_000), "2023-11-14 22:13");
+        assert_eq!(encode_segment("a b&c.txt"), "a%20b%26c.txt");
+        assert_eq!(escape("<a href='x'>"), "&lt;a href=&#39;x&#39;&gt;");
+    }
+}
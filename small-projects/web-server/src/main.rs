@@ -1,25 +1,134 @@
 #![allow(unused)]
-use std::{env, rc::Rc, thread};
+use std::{env, rc::Rc, sync::Arc, thread};
 use std::{fs, time::Duration};
 use std::{
     io::{Read, Write},
     net::{TcpListener, TcpStream},
 };
 
+use std::{path::Path, process};
+
+use web_server::{
+    config::{self, Config, ConfigError},
+    server::Server,
+};
+
+const USAGE: &str = "\
+Usage: web-server [--config <file>] [--tutorial <step>]
+
+Options:
+  --config <file>    Configuration file (default: web-server.toml, if present)
+  --tutorial <step>  Run step 1 to 4 of the book's chapter instead of the server";
+
+struct Options {
+    config: Option<String>,
+    tutorial: Option<u8>,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options {
+            config: None,
+            tutorial: None,
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => {
+                    let path = args.next().ok_or("--config needs a file")?;
+                    options.config = Some(path);
+                }
+                "--tutorial" => {
+                    let step = args.next().ok_or("--tutorial needs a step")?;
+                    match step.parse::<u8>() {
+                        Ok(step @ 1..=4) => options.tutorial = Some(step),
+                        _ => return Err(format!("unknown tutorial step `{}`", step)),
+                    }
+                }
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                }
+                _ => return Err(format!("unexpected argument `{}`", arg)),
+            }
+        }
+        Ok(options)
+    }
+}
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let selected_option = args[1].parse::<u8>().expect("Pass a number as argument");
-    match selected_option {
-        1 => single_threaded_server(),
-        2 => single_threaded_server_2(),
-        3 => single_threaded_server_3(),
-        4 => multi_threaded_server(),
-        _ => {}
+    let options = Options::parse(env::args().skip(1)).unwrap_or_else(|message| {
+        eprintln!("error: {}\n\n{}", message, USAGE);
+        process::exit(2);
+    });
+    let config = load_config(options.config.as_deref()).unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    });
+
+    // The book's steps only ever listen on one address
+    let address = config.listen[0].as_str();
+    match options.tutorial {
+        Some(1) => single_threaded_server(address),
+        Some(2) => single_threaded_server_2(address),
+        Some(3) => single_threaded_server_3(address),
+        Some(_) => multi_threaded_server(address),
+        None => serve(&config),
     }
 }
 
-fn single_threaded_server() {
-    let listener = TcpListener::bind("127.0.0.1:7878").unwrap();
+/// Uses the given file, else `web-server.toml` if there is one, else the
+/// built-in defaults.
+fn load_config(path: Option<&str>) -> Result<Config, ConfigError> {
+    match path {
+        Some(path) => Config::load(Path::new(path)),
+        None if Path::new(config::DEFAULT_PATH).exists() => {
+            Config::load(Path::new(config::DEFAULT_PATH))
+        }
+        None => {
+            let config = Config::default();
+            config.validate()?;
+            Ok(config)
+        }
+    }
+}
+
+/**
+ * Multi Threaded Web Server, with a thread pool, routing and virtual hosts
+ * (see `src/lib.rs`), listening on every configured address.
+ */
+fn serve(config: &Config) {
+    let listeners = bind(&config.listen, "http");
+    let mut server = Server::from_config(config);
+
+    let certificates = config.certificates().unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    });
+    if let (Some(tls), Some(certificates)) = (&config.tls, certificates) {
+        let certificates = Arc::new(certificates);
+        certificates.watch(tls.reload_interval);
+        server = server.https(bind(&tls.listen, "https"), certificates.server_config());
+    }
+
+    server.run(listeners);
+}
+
+fn bind(addresses: &[String], scheme: &str) -> Vec<TcpListener> {
+    addresses
+        .iter()
+        .map(|address| {
+            let listener = TcpListener::bind(address).unwrap_or_else(|e| {
+                eprintln!("error: cannot listen on {}: {}", address, e);
+                process::exit(1);
+            });
+            println!("Listening on {}://{}", scheme, address);
+            listener
+        })
+        .collect()
+}
+
+fn single_threaded_server(address: &str) {
+    let listener = TcpListener::bind(address).unwrap();
 
     for stream in listener.incoming() {
         /**
@@ -42,22 +151,22 @@ fn single_threaded_server() {
     }
 }
 
-fn single_threaded_server_2() {
-    let listener = TcpListener::bind("127.0.0.1:7878").unwrap();
+fn single_threaded_server_2(address: &str) {
+    let listener = TcpListener::bind(address).unwrap();
     for stream in listener.incoming() {
         let mut stream = stream.unwrap();
 
         let mut buffer = [0; 1024];
-        stream.read(&mut buffer).unwrap();
-        println!("Request: {}", String::from_utf8_lossy(&buffer[..]));
+        let read = stream.read(&mut buffer).unwrap();
+        println!("Request: {}", String::from_utf8_lossy(&buffer[..read]));
     }
 }
 
 /**
  * https://doc.rust-lang.org/book/ch20-01-single-threaded.html#writing-a-response
  */
-fn single_threaded_server_3() {
-    let listener = TcpListener::bind("127.0.0.1:7878").unwrap();
+fn single_threaded_server_3(address: &str) {
+    let listener = TcpListener::bind(address).unwrap();
     for stream in listener.incoming() {
         let mut stream = stream.unwrap();
         handle_connection(stream);
@@ -65,12 +174,12 @@ fn single_threaded_server_3() {
 }
 fn handle_connection(mut stream: TcpStream) {
     let mut buffer = [0; 1024];
-    stream.read(&mut buffer).unwrap();
+    let _ = stream.read(&mut buffer).unwrap();
 
     let get = b"GET / HTTP/1.1\r\n";
 
     if buffer.starts_with(get) {
-        let contents = fs::read_to_string("index.html").unwrap();
+        let contents = fs::read_to_string("public/index.html").unwrap();
 
         let response = format!(
             "HTTP/1.1 200 OK\nContent-Length: {}\n\n{}",
@@ -78,12 +187,12 @@ fn handle_connection(mut stream: TcpStream) {
             contents
         );
 
-        stream.write(response.as_bytes()).unwrap();
+        stream.write_all(response.as_bytes()).unwrap();
         stream.flush().unwrap();
     } else {
         // Respond with 404 for any other type of request or GET Routes.
         let status_line = "HTTP/1.1 404 NOT FOUND";
-        let contents = fs::read_to_string("404.html").unwrap();
+        let contents = fs::read_to_string("public/404.html").unwrap();
 
         let response = format!(
             "{}\r\nContent-Length: {}\r\n\r\n{}",
@@ -92,7 +201,7 @@ fn handle_connection(mut stream: TcpStream) {
             contents
         );
 
-        stream.write(response.as_bytes()).unwrap();
+        stream.write_all(response.as_bytes()).unwrap();
         stream.flush().unwrap();
     }
 }
@@ -100,8 +209,8 @@ fn handle_connection(mut stream: TcpStream) {
 /**
  * Multi Threaded Web Server
  */
-fn multi_threaded_server() {
-    let listener = TcpListener::bind("127.0.0.1:7878").unwrap();
+fn multi_threaded_server(address: &str) {
+    let listener = TcpListener::bind(address).unwrap();
     for stream in listener.incoming() {
         let mut stream = stream.unwrap();
         multi_handle_connection_1(stream);
@@ -109,18 +218,18 @@ fn multi_threaded_server() {
 }
 fn multi_handle_connection_1(mut stream: TcpStream) {
     let mut buffer = [0; 1024];
-    stream.read(&mut buffer).unwrap();
+    let _ = stream.read(&mut buffer).unwrap();
 
     let get = b"GET / HTTP/1.1\r\n";
     let sleep = b"GET /sleep HTTP/1.1\r\n";
 
     let (status, status_line, filename) = if buffer.starts_with(get) {
-        (200, "OK", "index.html")
+        (200, "OK", "public/index.html")
     } else if buffer.starts_with(sleep) {
         thread::sleep(Duration::from_secs(5));
-        (200, "OK", "index.html")
+        (200, "OK", "public/index.html")
     } else {
-        (404, "NOT FOUND", "404.html")
+        (404, "NOT FOUND", "public/404.html")
     };
 
     let contents = fs::read_to_string(filename).unwrap();
@@ -136,6 +245,6 @@ fn multi_handle_connection_1(mut stream: TcpStream) {
         println!("Something is Wrong, Status: {status}");
     }
 
-    stream.write(response.as_bytes()).unwrap();
+    stream.write_all(response.as_bytes()).unwrap();
     stream.flush().unwrap();
 }
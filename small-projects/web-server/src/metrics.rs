@@ -0,0 +1,312 @@
+//! Request metrics, exposed in the Prometheus text format:
+//! https://prometheus.io/docs/instrumenting/exposition_formats/
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write},
+    net::IpAddr,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+/// Upper bounds of the latency histogram buckets, in seconds.
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// A value that goes up and down, like the number of open connections.
+#[derive(Debug, Default)]
+pub struct Gauge(AtomicUsize);
+
+impl Gauge {
+    pub fn increment(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn decrement(&self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Counts one towards a gauge for as long as it's alive, so it's taken
+/// back off even when the thread unwinds from a panic.
+#[derive(Debug)]
+pub struct Tracked<'a>(&'a Gauge);
+
+impl<'a> Tracked<'a> {
+    pub fn new(gauge: &'a Gauge) -> Tracked<'a> {
+        gauge.increment();
+        Tracked(gauge)
+    }
+}
+
+impl Drop for Tracked<'_> {
+    fn drop(&mut self) {
+        self.0.decrement();
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Histogram {
+    /// Observations per bucket, not yet cumulative.
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(bucket) = BUCKETS.iter().position(|&bound| seconds <= bound) {
+            self.buckets[bucket] += 1;
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// Requests by route and status code.
+    requests: Mutex<BTreeMap<(String, u16), u64>>,
+    latencies: Mutex<BTreeMap<String, Histogram>>,
+    pub active_connections: Gauge,
+    /// Connections accepted but not picked up by a worker yet, kept up to
+    /// date by the thread pool.
+    pub queue_depth: Arc<Gauge>,
+    pub workers: usize,
+}
+
+impl Metrics {
+    pub fn new(workers: usize) -> Metrics {
+        Metrics {
+            workers,
+            ..Metrics::default()
+        }
+    }
+
+    /// Records a request to `route` (a label, like the path of the route
+    /// that handled it) answered with `status` after `elapsed`.
+    pub fn observe(&self, route: &str, status: u16, elapsed: Duration) {
+        *self
+            .requests
+            .lock()
+            .unwrap()
+            .entry((route.to_string(), status))
+            .or_insert(0) += 1;
+        self.latencies
+            .lock()
+            .unwrap()
+            .entry(route.to_string())
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Every metric, in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        // Writing to a String never fails
+        self.write_to(&mut out).unwrap();
+        out
+    }
+
+    fn write_to(&self, out: &mut String) -> fmt::Result {
+        writeln!(
+            out,
+            "# HELP http_requests_total Requests handled, by route and status code."
+        )?;
+        writeln!(out, "# TYPE http_requests_total counter")?;
+        for ((route, status), count) in self.requests.lock().unwrap().iter() {
+            writeln!(
+                out,
+                "http_requests_total{{route=\"{}\",status=\"{}\"}} {}",
+                escape(route),
+                status,
+                count
+            )?;
+        }
+
+        writeln!(
+            out,
+            "# HELP http_request_duration_seconds Time from reading a request to having written the response."
+        )?;
+        writeln!(out, "# TYPE http_request_duration_seconds histogram")?;
+        // Copied, so requests aren't held up while formatting
+        let latencies = self.latencies.lock().unwrap().clone();
+        for (route, histogram) in &latencies {
+            let route = escape(route);
+            let mut cumulative = 0;
+            for (bound, count) in BUCKETS.iter().zip(histogram.buckets.iter()) {
+                cumulative += count;
+                writeln!(
+                    out,
+                    "http_request_duration_seconds_bucket{{route=\"{}\",le=\"{}\"}} {}",
+                    route, bound, cumulative
+                )?;
+            }
+            writeln!(
+                out,
+                "http_request_duration_seconds_bucket{{route=\"{}\",le=\"+Inf\"}} {}",
+                route, histogram.count
+            )?;
+            writeln!(
+                out,
+                "http_request_duration_seconds_sum{{route=\"{}\"}} {}",
+                route, histogram.sum
+            )?;
+            writeln!(
+                out,
+                "http_request_duration_seconds_count{{route=\"{}\"}} {}",
+                route, histogram.count
+            )?;
+        }
+
+        let gauges = [
+            (
+                "http_connections_active",
+                "Connections currently being served.",
+                self.active_connections.get(),
+            ),
+            (
+                "thread_pool_queue_depth",
+                "Connections waiting for a worker thread.",
+                self.queue_depth.get(),
+            ),
+            (
+                "thread_pool_workers",
+                "Worker threads serving connections.",
+                self.workers,
+            ),
+        ];
+        for (name, help, value) in gauges.iter() {
+            writeln!(out, "# HELP {} {}", name, help)?;
+            writeln!(out, "# TYPE {} gauge", name)?;
+            writeln!(out, "{} {}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Label values are quoted, so quotes, backslashes and newlines are escaped.
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// A block of IP addresses in CIDR notation, like `10.0.0.0/8`. A plain
+/// address is a block of one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    pub fn contains(&self, address: IpAddr) -> bool {
+        // IPv4 clients of a dual-stack socket show up as `::ffff:a.b.c.d`
+        let address = match address {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(address, IpAddr::V4),
+            v4 => v4,
+        };
+        match (self.network, address) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(network) & mask == u32::from(address) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(network) & mask == u128::from(address) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Cidr, String> {
+        let (address, prefix) = match s.split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (s, None),
+        };
+        let network: IpAddr = address
+            .parse()
+            .map_err(|_| format!("`{}` is not an IP address", address))?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse()
+                .ok()
+                .filter(|&prefix| prefix <= max)
+                .ok_or_else(|| format!("`{}` is not a prefix length from 0 to {}", prefix, max))?,
+            None => max,
+        };
+        Ok(Cidr { network, prefix })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_prometheus_text() {
+        let metrics = Metrics::new(4);
+        metrics.observe("/api", 200, Duration::from_millis(20));
+        metrics.observe("/api", 200, Duration::from_secs(20));
+        metrics.observe("static", 404, Duration::from_millis(1));
+        metrics.active_connections.increment();
+
+        let text = metrics.render();
+        for line in [
+            "http_requests_total{route=\"/api\",status=\"200\"} 2",
+            "http_requests_total{route=\"static\",status=\"404\"} 1",
+            "http_request_duration_seconds_bucket{route=\"/api\",le=\"0.01\"} 0",
+            "http_request_duration_seconds_bucket{route=\"/api\",le=\"0.025\"} 1",
+            "http_request_duration_seconds_bucket{route=\"/api\",le=\"10\"} 1",
+            "http_request_duration_seconds_bucket{route=\"/api\",le=\"+Inf\"} 2",
+            "http_request_duration_seconds_sum{route=\"/api\"} 20.02",
+            "http_request_duration_seconds_count{route=\"static\"} 1",
+            "http_connections_active 1",
+            "thread_pool_queue_depth 0",
+            "thread_pool_workers 4",
+        ]
+        .iter()
+        {
+            assert!(
+                text.lines().any(|l| l == *line),
+                "{} not in\n{}",
+                line,
+                text
+            );
+        }
+        assert_eq!(escape("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+
+    #[test]
+    fn matches_addresses_against_blocks() {
+        let block: Cidr = "10.1.0.0/16".parse().unwrap();
+        assert!(block.contains("10.1.200.3".parse().unwrap()));
+        assert!(block.contains("::ffff:10.1.0.1".parse().unwrap()));
+        assert!(!block.contains("10.2.0.1".parse().unwrap()));
+
+        let loopback: Cidr = "::1".parse().unwrap();
+        assert!(loopback.contains("::1".parse().unwrap()));
+        assert!(!loopback.contains("127.0.0.1".parse().unwrap()));
+
+        let everything: Cidr = "0.0.0.0/0".parse().unwrap();
+        assert!(everything.contains("203.0.113.9".parse().unwrap()));
+
+        assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+        assert!("localhost".parse::<Cidr>().is_err());
+    }
+}
@@ -0,0 +1,322 @@
+//! Streaming `multipart/form-data` parser (RFC 7578). Part contents are
+//! copied out as they arrive, so uploads never have to fit in memory.
+use std::{
+    error, fmt,
+    io::{self, Read, Write},
+};
+
+use crate::http::{read_headers, Headers, ParseError};
+
+const READ_SIZE: usize = 64 * 1024;
+/// Part headers are tiny; anything bigger is not a form upload.
+const MAX_PART_HEAD: usize = 16 * 1024;
+
+#[derive(Debug)]
+pub enum MultipartError {
+    Io(io::Error),
+    Malformed(&'static str),
+    /// A part was bigger than the limit it was read with.
+    TooLarge,
+}
+
+impl fmt::Display for MultipartError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MultipartError::Io(e) => write!(f, "{}", e),
+            MultipartError::Malformed(reason) => write!(f, "malformed multipart body: {}", reason),
+            MultipartError::TooLarge => write!(f, "part too large"),
+        }
+    }
+}
+
+impl error::Error for MultipartError {}
+
+impl From<io::Error> for MultipartError {
+    fn from(e: io::Error) -> Self {
+        MultipartError::Io(e)
+    }
+}
+
+/// Boundary of a `multipart/form-data` content type, if it is one.
+pub fn boundary(content_type: &str) -> Option<String> {
+    let mut params = content_type.split(';');
+    if !params
+        .next()?
+        .trim()
+        .eq_ignore_ascii_case("multipart/form-data")
+    {
+        return None;
+    }
+    params
+        .filter_map(|param| param.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("boundary"))
+        .map(|(_, value)| value.trim().trim_matches('"').to_string())
+        .filter(|boundary| !boundary.is_empty() && boundary.len() <= 70)
+}
+
+#[derive(Debug)]
+pub struct Part {
+    pub headers: Headers,
+    /// `name` from the part's `Content-Disposition`.
+    pub name: Option<String>,
+    /// `filename` from the part's `Content-Disposition`, as sent.
+    pub filename: Option<String>,
+}
+
+pub struct Multipart<R> {
+    inner: R,
+    /// `\r\n--boundary`: every delimiter, but the first, is preceded by a
+    /// line break that belongs to it rather than to the part before.
+    delimiter: Vec<u8>,
+    buffer: Vec<u8>,
+    eof: bool,
+    /// Whether the first delimiter, ending the preamble, was found.
+    started: bool,
+    /// Whether the contents of the current part are still to be read.
+    in_part: bool,
+    done: bool,
+}
+
+impl<R: Read> Multipart<R> {
+    pub fn new(inner: R, boundary: &str) -> Self {
+        Multipart {
+            inner,
+            delimiter: format!("\r\n--{}", boundary).into_bytes(),
+            // Lets the first delimiter be found like all the others
+            buffer: b"\r\n".to_vec(),
+            eof: false,
+            started: false,
+            in_part: false,
+            done: false,
+        }
+    }
+
+    /// Moves on to the next part, skipping what is left of the current one.
+    pub fn next_part(&mut self) -> Result<Option<Part>, MultipartError> {
+        if self.done {
+            return Ok(None);
+        }
+        if self.in_part {
+            self.copy_part(&mut io::sink(), u64::MAX)?;
+        } else if !self.started {
+            // The preamble, before the first delimiter, is ignored
+            self.skip_to_delimiter()?;
+            self.started = true;
+        }
+
+        // A delimiter followed by `--` closes the body
+        self.fill_at_least(2)?;
+        if self.buffer.starts_with(b"--") {
+            self.done = true;
+            return Ok(None);
+        }
+
+        let head_end = loop {
+            if let Some(position) = find(&self.buffer, b"\r\n\r\n") {
+                break position + 4;
+            }
+            if self.buffer.len() > MAX_PART_HEAD {
+                return Err(MultipartError::Malformed("part headers too large"));
+            }
+            if !self.fill()? {
+                return Err(MultipartError::Malformed("unexpected end of part headers"));
+            }
+        };
+        let head: Vec<u8> = self.buffer.drain(..head_end).collect();
+        // Whatever padding follows the delimiter ends with the first CRLF
+        let start = find(&head, b"\r\n").map_or(head.len(), |position| position + 2);
+        let headers = read_headers(&mut &head[start..]).map_err(|e| match e {
+            ParseError::Io(e) => MultipartError::Io(e),
+            _ => MultipartError::Malformed("invalid part headers"),
+        })?;
+
+        let disposition = headers.get("Content-Disposition").unwrap_or("");
+        let part = Part {
+            name: disposition_param(disposition, "name"),
+            filename: disposition_param(disposition, "filename"),
+            headers,
+        };
+        self.in_part = true;
+        Ok(Some(part))
+    }
+
+    /// Copies the current part's contents to `out`, failing with
+    /// `TooLarge` once more than `limit` bytes came in.
+    pub fn copy_part(&mut self, out: &mut dyn Write, limit: u64) -> Result<u64, MultipartError> {
+        if !self.in_part {
+            return Ok(0);
+        }
+        let mut written = 0;
+        loop {
+            let (end, found) = match find(&self.buffer, &self.delimiter) {
+                Some(position) => (position, true),
+                // Keep a tail that could be the start of a delimiter
+                None => (
+                    self.buffer.len().saturating_sub(self.delimiter.len() - 1),
+                    false,
+                ),
+            };
+
+            written += end as u64;
+            if written > limit {
+                return Err(MultipartError::TooLarge);
+            }
+            out.write_all(&self.buffer[..end])?;
+            self.buffer.drain(..end);
+
+            if found {
+                self.buffer.drain(..self.delimiter.len());
+                self.in_part = false;
+                return Ok(written);
+            }
+            if !self.fill()? {
+                return Err(MultipartError::Malformed("unexpected end of body"));
+            }
+        }
+    }
+
+    fn skip_to_delimiter(&mut self) -> Result<(), MultipartError> {
+        loop {
+            if let Some(position) = find(&self.buffer, &self.delimiter) {
+                self.buffer.drain(..position + self.delimiter.len());
+                return Ok(());
+            }
+            let keep = self.delimiter.len() - 1;
+            if self.buffer.len() > keep {
+                self.buffer.drain(..self.buffer.len() - keep);
+            }
+            if !self.fill()? {
+                return Err(MultipartError::Malformed("no boundary found"));
+            }
+        }
+    }
+
+    fn fill_at_least(&mut self, count: usize) -> Result<(), MultipartError> {
+        while self.buffer.len() < count {
+            if !self.fill()? {
+                return Err(MultipartError::Malformed("unexpected end of body"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads more of the body into the buffer; `false` at EOF.
+    fn fill(&mut self) -> io::Result<bool> {
+        if self.eof {
+            return Ok(false);
+        }
+        let start = self.buffer.len();
+        self.buffer.resize(start + READ_SIZE, 0);
+        let read = self.inner.read(&mut self.buffer[start..]);
+        // Drops the zeroes past what was read, also when reading failed
+        self.buffer.truncate(start + *read.as_ref().unwrap_or(&0));
+        let read = read?;
+        if read == 0 {
+            self.eof = true;
+        }
+        Ok(read > 0)
+    }
+}
+
+/// Value of `param` in a `Content-Disposition: form-data; name="..."` header.
+fn disposition_param(disposition: &str, param: &str) -> Option<String> {
+    disposition.split(';').skip(1).find_map(|pair| {
+        let (name, value) = pair.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case(param) {
+            return None;
+        }
+        let value = value.trim();
+        let value = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            Some(quoted) => quoted.replace("\\\"", "\"").replace("\\\\", "\\"),
+            None => value.to_string(),
+        };
+        Some(value)
+    })
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hands out the body a few bytes at a time, to hit every buffer edge.
+    struct Trickle<'a>(&'a [u8], usize);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let count = self.1.min(buf.len()).min(self.0.len());
+            buf[..count].copy_from_slice(&self.0[..count]);
+            self.0 = &self.0[count..];
+            Ok(count)
+        }
+    }
+
+    const BODY: &str = "preamble\r\n--XyZ\r\n\
+        Content-Disposition: form-data; name=\"note\"\r\n\r\n\
+        hello\r\n--XyZ\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"a \\\"b\\\".txt\"\r\n\
+        Content-Type: text/plain\r\n\r\n\
+        line one\r\n--Xy-almost\r\nline two\r\n--XyZ--\r\nepilogue";
+
+    #[test]
+    fn parses_parts_whatever_the_read_size() {
+        for step in [1, 2, 3, 7, 64, 4096].iter() {
+            let mut multipart = Multipart::new(Trickle(BODY.as_bytes(), *step), "XyZ");
+
+            let part = multipart.next_part().unwrap().unwrap();
+            assert_eq!(part.name.as_deref(), Some("note"));
+            assert_eq!(part.filename, None);
+            let mut contents = Vec::new();
+            multipart.copy_part(&mut contents, 100).unwrap();
+            assert_eq!(contents, b"hello");
+
+            let part = multipart.next_part().unwrap().unwrap();
+            assert_eq!(part.filename.as_deref(), Some("a \"b\".txt"));
+            assert_eq!(part.headers.get("Content-Type"), Some("text/plain"));
+            let mut contents = Vec::new();
+            multipart.copy_part(&mut contents, 100).unwrap();
+            assert_eq!(contents, b"line one\r\n--Xy-almost\r\nline two");
+
+            assert!(multipart.next_part().unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn enforces_limits_and_framing() {
+        let mut multipart = Multipart::new(BODY.as_bytes(), "XyZ");
+        multipart.next_part().unwrap();
+        assert!(matches!(
+            multipart.copy_part(&mut io::sink(), 4),
+            Err(MultipartError::TooLarge)
+        ));
+
+        let mut multipart = Multipart::new("--XyZ\r\n\r\nunterminated".as_bytes(), "XyZ");
+        multipart.next_part().unwrap();
+        assert!(matches!(
+            multipart.copy_part(&mut io::sink(), 100),
+            Err(MultipartError::Malformed(_))
+        ));
+
+        let mut multipart = Multipart::new("no boundary here".as_bytes(), "XyZ");
+        assert!(multipart.next_part().is_err());
+    }
+
+    #[test]
+    fn reads_boundaries_from_content_types() {
+        assert_eq!(
+            boundary("multipart/form-data; boundary=\"--abc\"").as_deref(),
+            Some("--abc")
+        );
+        assert_eq!(
+            boundary("Multipart/Form-Data;charset=utf-8; boundary=x1").as_deref(),
+            Some("x1")
+        );
+        assert_eq!(boundary("application/json; boundary=x1"), None);
+        assert_eq!(boundary("multipart/form-data"), None);
+    }
+}
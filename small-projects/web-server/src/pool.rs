@@ -0,0 +1,136 @@
+//! Thread pool from the book's final chapter:
+//! https://doc.rust-lang.org/book/ch20-02-multithreaded.html
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+use crate::metrics::Gauge;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+pub struct ThreadPool {
+    workers: Vec<Worker>,
+    sender: Option<mpsc::Sender<Job>>,
+    /// Jobs sent that no worker started on yet.
+    queued: Arc<Gauge>,
+}
+
+impl ThreadPool {
+    /// Create a new ThreadPool.
+    ///
+    /// The size is the number of threads in the pool.
+    ///
+    /// # Panics
+    ///
+    /// The `new` function will panic if the size is zero.
+    pub fn new(size: usize) -> ThreadPool {
+        assert!(size > 0);
+
+        let (sender, receiver) = mpsc::channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let workers = (0..size)
+            .map(|id| Worker::new(id, Arc::clone(&receiver)))
+            .collect();
+
+        ThreadPool {
+            workers,
+            sender: Some(sender),
+            queued: Arc::new(Gauge::default()),
+        }
+    }
+
+    /// Counts the jobs waiting for a worker in `gauge`, e.g. to export it.
+    pub fn with_queue_gauge(mut self, gauge: Arc<Gauge>) -> ThreadPool {
+        self.queued = gauge;
+        self
+    }
+
+    /// Number of jobs waiting for a worker.
+    pub fn queue_depth(&self) -> usize {
+        self.queued.get()
+    }
+
+    pub fn execute<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let queued = Arc::clone(&self.queued);
+        queued.increment();
+        let job = move || {
+            queued.decrement();
+            f()
+        };
+        self.sender
+            .as_ref()
+            .unwrap()
+            .send(Box::new(job))
+            .expect("all pool workers have stopped");
+    }
+}
+
+impl Drop for ThreadPool {
+    /**
+     * Dropping the sender closes the channel, so every worker's `recv`
+     * fails and its loop ends; only then is it safe to join them.
+     */
+    fn drop(&mut self) {
+        drop(self.sender.take());
+
+        for worker in &mut self.workers {
+            if let Some(thread) = worker.thread.take() {
+                if thread.join().is_err() {
+                    eprintln!("A pool worker panicked");
+                }
+            }
+        }
+    }
+}
+
+struct Worker {
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Worker {
+    fn new(id: usize, receiver: Arc<Mutex<mpsc::Receiver<Job>>>) -> Worker {
+        let thread = thread::Builder::new()
+            .name(format!("worker-{}", id))
+            .spawn(move || loop {
+                let message = receiver.lock().unwrap().recv();
+
+                // A panicking job would take the worker down with it, and
+                // the pool would shrink with every one
+                match message {
+                    Ok(job) => {
+                        if panic::catch_unwind(AssertUnwindSafe(job)).is_err() {
+                            eprintln!("A job panicked on worker {}", id);
+                        }
+                    }
+                    Err(_) => break,
+                }
+            })
+            .expect("failed to spawn pool worker");
+
+        Worker {
+            thread: Some(thread),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn survives_panicking_jobs() {
+        let pool = ThreadPool::new(1);
+        pool.execute(|| panic!("job failed"));
+
+        let (sender, receiver) = mpsc::channel();
+        pool.execute(move || sender.send(42).unwrap());
+        assert_eq!(receiver.recv(), Ok(42));
+        drop(pool);
+    }
+}
@@ -0,0 +1,318 @@
+//! Reverse proxy handler, forwarding requests to upstream HTTP servers.
+//!
+//! Upstreams are picked round-robin. An upstream that refuses connections
+//! (or breaks mid exchange) is marked down for `DOWN_COOLDOWN` and the
+//! request is failed over to the next one. A client whose body breaks off
+//! gets a 400, and leaves the upstream alone.
+use std::{
+    io::{self, BufReader, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::http::{
+    read_head, Body, ChunkedReader, ChunkedWriter, Headers, ParseError, Request, RequestBody,
+    Response,
+};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(30);
+const DOWN_COOLDOWN: Duration = Duration::from_secs(10);
+
+/// Headers that only make sense for a single connection, so they are
+/// never forwarded (RFC 7230, 6.1). Chunked bodies are decoded and encoded
+/// again on the other side, so `Transfer-Encoding` is one of them too.
+pub(crate) const HOP_BY_HOP: [&str; 7] = [
+    "Connection",
+    "Keep-Alive",
+    "Proxy-Connection",
+    "TE",
+    "Trailer",
+    "Transfer-Encoding",
+    "Upgrade",
+];
+
+pub struct Proxy {
+    upstreams: Vec<Upstream>,
+    next: AtomicUsize,
+}
+
+struct Upstream {
+    /// `host:port` of the backend, also used as the forwarded `Host`.
+    address: String,
+    down_until: Mutex<Option<Instant>>,
+}
+
+impl Upstream {
+    fn is_down(&self) -> bool {
+        match *self.down_until.lock().unwrap() {
+            Some(until) => Instant::now() < until,
+            None => false,
+        }
+    }
+
+    fn mark_down(&self) {
+        *self.down_until.lock().unwrap() = Some(Instant::now() + DOWN_COOLDOWN);
+    }
+
+    fn mark_up(&self) {
+        *self.down_until.lock().unwrap() = None;
+    }
+
+    fn connect(&self) -> io::Result<TcpStream> {
+        let mut last_error = io::Error::new(io::ErrorKind::NotFound, "address did not resolve");
+        for addr in self.address.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(UPSTREAM_TIMEOUT))?;
+                    stream.set_write_timeout(Some(UPSTREAM_TIMEOUT))?;
+                    return Ok(stream);
+                }
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+}
+
+impl Proxy {
+    /// # Panics
+    ///
+    /// Panics if no upstream is given.
+    pub fn new<I, S>(upstreams: I) -> Proxy
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let upstreams: Vec<Upstream> = upstreams
+            .into_iter()
+            .map(|address| Upstream {
+                address: address.into(),
+                down_until: Mutex::new(None),
+            })
+            .collect();
+        assert!(!upstreams.is_empty(), "a proxy needs at least one upstream");
+
+        Proxy {
+            upstreams,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Upstreams in the order they should be tried for the next request:
+    /// round-robin, but with the ones currently marked down moved to the
+    /// back, so they still get a chance when nothing else is left.
+    fn candidates(&self) -> Vec<&Upstream> {
+        let count = self.upstreams.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut order: Vec<&Upstream> = (0..count)
+            .map(|i| &self.upstreams[(start + i) % count])
+            .collect();
+        order.sort_by_key(|upstream| upstream.is_down());
+        order
+    }
+
+    pub fn forward(&self, request: &Request, body: &mut RequestBody) -> Response {
+        // Once a body has been (partially) sent upstream it is gone, so only
+        // body-less requests can be retried after the connection was made.
+        let retryable = request.content_length() == 0 && !request.is_chunked();
+
+        for upstream in self.candidates() {
+            let stream = match upstream.connect() {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("Upstream {} is unreachable: {}", upstream.address, e);
+                    upstream.mark_down();
+                    continue;
+                }
+            };
+
+            match exchange(stream, upstream, request, body) {
+                Ok(response) => {
+                    upstream.mark_up();
+                    return response;
+                }
+                Err(Failure::Client(e)) => {
+                    eprintln!("Bad request body for {}: {}", upstream.address, e);
+                    return Response::error(400);
+                }
+                Err(Failure::Upstream(e)) => {
+                    eprintln!("Upstream {} failed: {}", upstream.address, e);
+                    upstream.mark_down();
+                    if !retryable {
+                        break;
+                    }
+                }
+            }
+        }
+        Response::error(502)
+    }
+}
+
+/// Which side an exchange failed on. Only upstream failures mark the
+/// upstream down.
+enum Failure {
+    /// The client's body broke off, or wasn't valid chunked encoding.
+    Client(io::Error),
+    Upstream(ParseError),
+}
+
+impl<E: Into<ParseError>> From<E> for Failure {
+    fn from(e: E) -> Self {
+        Failure::Upstream(e.into())
+    }
+}
+
+/// Sends the request upstream and reads back the response head. The body
+/// of the response stays in the upstream connection and is streamed to
+/// the client while the response is written.
+fn exchange(
+    mut stream: TcpStream,
+    upstream: &Upstream,
+    request: &Request,
+    body: &mut RequestBody,
+) -> Result<Response, Failure> {
+    let head = forwarded_head(request, &upstream.address);
+    stream.write_all(head.as_bytes())?;
+    if request.is_chunked() {
+        let mut writer = ChunkedWriter::new(&mut stream);
+        copy_body(body, &mut writer)?;
+        writer.finish(body.trailers())?;
+    } else if copy_body(body, &mut stream)? < request.content_length() {
+        return Err(Failure::Client(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "client body shorter than its content-length",
+        )));
+    }
+    stream.flush()?;
+
+    let mut reader = BufReader::new(stream);
+    // Interim responses, like `100 Continue` or `103 Early Hints`, are for
+    // this connection alone: the body went up already, and the client gets
+    // the final response only
+    let (status, mut headers) = loop {
+        let (status_line, headers) = read_head(&mut reader)?;
+        let status = parse_status_line(&status_line)?;
+        if !(100..200).contains(&status) || status == 101 {
+            break (status, headers);
+        }
+    };
+
+    let chunked = headers.has_token("Transfer-Encoding", "chunked");
+    remove_hop_by_hop(&mut headers);
+    let length = if request.method == "HEAD" || status == 204 || status == 304 {
+        Some(0)
+    } else if chunked {
+        None
+    } else {
+        match headers.get("Content-Length") {
+            Some(length) => Some(
+                length
+                    .parse()
+                    .map_err(|_| ParseError::Malformed("invalid upstream content-length"))?,
+            ),
+            None => None,
+        }
+    };
+
+    let body: Box<dyn Read + Send> = match length {
+        Some(length) => Box::new(reader.take(length)),
+        None if chunked => Box::new(ChunkedReader::new(reader)),
+        None => Box::new(reader),
+    };
+    Ok(Response {
+        status,
+        headers,
+        body: Body::Reader {
+            reader: body,
+            length,
+        },
+    })
+}
+
+/// Like `io::copy`, but telling errors reading the client's body apart from
+/// errors writing to the upstream.
+fn copy_body<W: Write>(body: &mut RequestBody, upstream: &mut W) -> Result<u64, Failure> {
+    let mut buffer = [0; 8 * 1024];
+    let mut copied = 0;
+    loop {
+        let read = match body.read(&mut buffer) {
+            Ok(0) => return Ok(copied),
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(Failure::Client(e)),
+        };
+        upstream.write_all(&buffer[..read])?;
+        copied += read as u64;
+    }
+}
+
+/// Request head as it is sent upstream: `Host` points at the upstream,
+/// the original host and client travel in `X-Forwarded-*` headers, and the
+/// upstream connection is closed once the response has been read.
+fn forwarded_head(request: &Request, upstream: &str) -> String {
+    let mut headers: Headers = request.headers.clone();
+    remove_hop_by_hop(&mut headers);
+    // The body is sent without waiting for the upstream to ask for it
+    headers.remove("Expect");
+
+    if let Some(host) = request.headers.get("Host") {
+        headers.set("X-Forwarded-Host", host);
+    }
+    if let Some(client) = request.remote_addr.map(|addr| addr.ip()) {
+        let forwarded_for = match request.headers.get("X-Forwarded-For") {
+            Some(chain) => format!("{}, {}", chain, client),
+            None => client.to_string(),
+        };
+        headers.set("X-Forwarded-For", forwarded_for);
+    }
+    headers.set(
+        "X-Forwarded-Proto",
+        if request.tls { "https" } else { "http" },
+    );
+    if request.is_chunked() {
+        headers.set("Transfer-Encoding", "chunked");
+    }
+    headers.set("Host", upstream);
+    headers.set("Connection", "close");
+
+    let mut head = format!("{} {} HTTP/1.1\r\n", request.method, request.target);
+    for (name, value) in headers.iter() {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    head
+}
+
+/// Removes the headers that only make sense for a single connection: the
+/// `HOP_BY_HOP` ones, and any that `Connection` names (RFC 9110, 7.6.1).
+fn remove_hop_by_hop(headers: &mut Headers) {
+    let named: Vec<String> = headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("Connection"))
+        .flat_map(|(_, value)| value.split(','))
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+    for name in HOP_BY_HOP
+        .iter()
+        .copied()
+        .chain(named.iter().map(String::as_str))
+    {
+        headers.remove(name);
+    }
+}
+
+fn parse_status_line(line: &str) -> Result<u16, ParseError> {
+    let mut parts = line.splitn(3, ' ');
+    match (parts.next(), parts.next()) {
+        (Some(version), Some(status)) if version.starts_with("HTTP/1.") => status
+            .parse()
+            .map_err(|_| ParseError::Malformed("invalid upstream status")),
+        _ => Err(ParseError::Malformed("invalid upstream status line")),
+    }
+}
@@ -0,0 +1,275 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    cgi::Cgi,
+    files,
+    http::{Request, RequestBody, Response},
+    listing,
+    proxy::Proxy,
+    upload::Upload,
+};
+
+/// What a matched route does with the request.
+pub enum Handler {
+    /// Serves a single file, the way the book serves `index.html`.
+    File(PathBuf),
+    /// Sleeps before serving the file, to simulate a slow request.
+    Sleep(Duration, PathBuf),
+    /// Forwards the request to one of several upstream servers.
+    Proxy(Proxy),
+    /// Saves the files POSTed as `multipart/form-data` into a directory.
+    /// Other requests are served from the document root as usual.
+    Upload(Upload),
+    /// Runs the CGI scripts in a directory.
+    Cgi(Cgi),
+    /// Anything else, written in Rust.
+    Function(HandlerFn),
+}
+
+pub type HandlerFn = Box<dyn Fn(&Request, &mut RequestBody) -> Response + Send + Sync>;
+
+impl Handler {
+    pub fn function<F>(handler: F) -> Handler
+    where
+        F: Fn(&Request, &mut RequestBody) -> Response + Send + Sync + 'static,
+    {
+        Handler::Function(Box::new(handler))
+    }
+}
+
+pub struct Route {
+    path: String,
+    prefix: bool,
+    handler: Handler,
+}
+
+impl Route {
+    /// Route matching only `path` itself.
+    pub fn exact(path: &str, handler: Handler) -> Route {
+        Route {
+            path: path.to_string(),
+            prefix: false,
+            handler,
+        }
+    }
+
+    /// Route matching `path` and everything below it, e.g. `/api` matches
+    /// `/api` and `/api/users`, but not `/apiary`.
+    pub fn prefix(path: &str, handler: Handler) -> Route {
+        Route {
+            path: path.trim_end_matches('/').to_string(),
+            prefix: true,
+            handler,
+        }
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        if !self.prefix {
+            return path == self.path;
+        }
+        match path.strip_prefix(self.path.as_str()) {
+            Some(rest) => rest.is_empty() || rest.starts_with('/'),
+            None => false,
+        }
+    }
+}
+
+/// Routes of a single site. Requests no route matches are served as
+/// static files from the site's document root.
+pub struct Router {
+    routes: Vec<Route>,
+    root: PathBuf,
+    index: String,
+    not_found: Option<String>,
+    listing: bool,
+}
+
+impl Router {
+    pub fn new(root: impl Into<PathBuf>) -> Router {
+        Router {
+            routes: Vec::new(),
+            root: root.into(),
+            index: "index.html".to_string(),
+            not_found: None,
+            listing: false,
+        }
+    }
+
+    /// File served for requests to a directory.
+    pub fn index(mut self, index: &str) -> Router {
+        self.index = index.to_string();
+        self
+    }
+
+    /// Page, relative to the root, served with `404 Not Found`.
+    pub fn not_found(mut self, page: &str) -> Router {
+        self.not_found = Some(page.to_string());
+        self
+    }
+
+    /// Lists the contents of directories without an index file, instead
+    /// of answering `404 Not Found`.
+    pub fn listing(mut self, enabled: bool) -> Router {
+        self.listing = enabled;
+        self
+    }
+
+    /// Adds a route. Routes are tried in the order they were added.
+    pub fn route(mut self, route: Route) -> Router {
+        self.routes.push(route);
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Path of the route `path` is handled by, `None` for static files.
+    pub fn route_path(&self, path: &str) -> Option<&str> {
+        let path = files::normalize(path)?;
+        self.find_route(&path).map(|route| route.path.as_str())
+    }
+
+    /// Route for an already normalized path: matching the raw one would let
+    /// `/./cgi-bin/x` or `/cgi%2Dbin/x` slip past the route to the files.
+    fn find_route(&self, path: &str) -> Option<&Route> {
+        self.routes.iter().find(|route| route.matches(path))
+    }
+
+    pub fn handle(&self, request: &Request, body: &mut RequestBody) -> Response {
+        let path = match files::normalize(request.path()) {
+            Some(path) => path,
+            None => return self.serve_not_found(),
+        };
+        let route = match self.find_route(&path) {
+            Some(route) => route,
+            None => return self.serve_static(request, &path),
+        };
+
+        match &route.handler {
+            Handler::Proxy(proxy) => proxy.forward(request, body),
+            Handler::Function(handler) => handler(request, body),
+            Handler::Upload(upload) if request.method == "POST" => upload.handle(request, body),
+            Handler::Upload(_) => self.serve_static(request, &path),
+            Handler::Cgi(cgi) => cgi.handle(&route.path, &path, request, body),
+            Handler::File(file) => self.serve_file(request, file),
+            Handler::Sleep(duration, file) => {
+                thread::sleep(*duration);
+                self.serve_file(request, file)
+            }
+        }
+    }
+
+    fn serve_file(&self, request: &Request, file: &Path) -> Response {
+        if request.method != "GET" && request.method != "HEAD" {
+            return Response::error(405).with_header("Allow", "GET, HEAD");
+        }
+        files::serve(file, 200).unwrap_or_else(|e| {
+            eprintln!("Failed to read {}: {}", file.display(), e);
+            Response::error(500)
+        })
+    }
+
+    /// Serves the file `path`, normalized, names below the root.
+    fn serve_static(&self, request: &Request, path: &str) -> Response {
+        if request.method != "GET" && request.method != "HEAD" {
+            return Response::error(405).with_header("Allow", "GET, HEAD");
+        }
+        let path = self.root.join(path.trim_start_matches('/'));
+        if self.is_cgi(&path) {
+            return self.serve_not_found();
+        }
+
+        if path.is_dir() {
+            // Without the trailing slash, relative links in the index would
+            // resolve against the parent directory.
+            if !request.path().ends_with('/') {
+                let mut location = format!("{}/", request.path());
+                if let Some(query) = request.query() {
+                    location = format!("{}?{}", location, query);
+                }
+                return Response::error(301).with_header("Location", location);
+            }
+            let index = path.join(&self.index);
+            if index.is_file() {
+                return self.serve_file(request, &index);
+            }
+            if self.listing {
+                return listing::respond(request, &path);
+            }
+        } else if path.is_file() {
+            return self.serve_file(request, &path);
+        }
+        self.serve_not_found()
+    }
+
+    /// Whether `path` is in one of the CGI directories, whose scripts must
+    /// only ever be run, never served as they are.
+    fn is_cgi(&self, path: &Path) -> bool {
+        let path = match path.canonicalize() {
+            Ok(path) => path,
+            Err(_) => return false,
+        };
+        self.routes.iter().any(|route| match &route.handler {
+            Handler::Cgi(cgi) => cgi
+                .dir()
+                .canonicalize()
+                .is_ok_and(|dir| path.starts_with(dir)),
+            _ => false,
+        })
+    }
+
+    fn serve_not_found(&self) -> Response {
+        self.not_found
+            .as_ref()
+            .and_then(|page| files::serve(&self.root.join(page), 404).ok())
+            .unwrap_or_else(|| Response::error(404))
+    }
+}
+
+/// The default site plus the virtual hosts, picked by the `Host` header.
+pub struct Sites {
+    default: Router,
+    virtual_hosts: Vec<Router>,
+    /// Lower-cased host name to index in `virtual_hosts`.
+    hosts: HashMap<String, usize>,
+}
+
+impl Sites {
+    pub fn new(default: Router) -> Sites {
+        Sites {
+            default,
+            virtual_hosts: Vec::new(),
+            hosts: HashMap::new(),
+        }
+    }
+
+    /// Serves requests for any of `names` with `router`.
+    pub fn virtual_host<I, S>(mut self, names: I, router: Router) -> Sites
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let index = self.virtual_hosts.len();
+        self.virtual_hosts.push(router);
+        for name in names {
+            self.hosts.insert(name.as_ref().to_ascii_lowercase(), index);
+        }
+        self
+    }
+
+    /// The default site's router, followed by those of the virtual hosts.
+    pub fn routers(&self) -> impl Iterator<Item = &Router> {
+        std::iter::once(&self.default).chain(self.virtual_hosts.iter())
+    }
+
+    pub fn for_host(&self, host: Option<&str>) -> &Router {
+        host.and_then(|host| self.hosts.get(&host.to_ascii_lowercase()))
+            .map_or(&self.default, |&index| &self.virtual_hosts[index])
+    }
+}
@@ -0,0 +1,473 @@
+use std::{
+    cell::Cell,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+
+use crate::{
+    config::{Config, Timeouts},
+    guard::{Guard, GuardedStream, Phase},
+    http::{Body, ParseError, Request, RequestBody, Response},
+    metrics::{Cidr, Metrics, Tracked},
+    pool::ThreadPool,
+    router::Sites,
+};
+
+/// Liveness check: answers `200 OK` as long as the server handles requests.
+pub const HEALTHZ: &str = "/healthz";
+/// Readiness check: answers `200 OK` once the server accepts connections
+/// and every document root is there, `503 Service Unavailable` otherwise.
+pub const READYZ: &str = "/readyz";
+
+/// Most bytes of a request body the handler didn't read that are skipped to
+/// get to the next request on the connection; it is closed instead beyond.
+const MAX_SKIPPED_BODY: u64 = 64 * 1024;
+
+pub struct Server {
+    shared: Arc<Shared>,
+    workers: usize,
+    timeouts: Timeouts,
+    https_listeners: Vec<(TcpListener, Arc<ServerConfig>)>,
+}
+
+/// Everything the worker threads serve connections with.
+struct Shared {
+    sites: Sites,
+    metrics: Metrics,
+    metrics_endpoint: Option<MetricsEndpoint>,
+    ready: AtomicBool,
+    /// Set on shutdown, so connections aren't kept alive any longer.
+    stopping: AtomicBool,
+}
+
+struct MetricsEndpoint {
+    path: String,
+    allow: Vec<Cidr>,
+}
+
+impl Server {
+    /// Server for `sites`, without a metrics endpoint.
+    pub fn new(sites: Sites, workers: usize, timeouts: Timeouts) -> Server {
+        Server {
+            shared: Arc::new(Shared {
+                sites,
+                metrics: Metrics::new(workers),
+                metrics_endpoint: None,
+                ready: AtomicBool::new(false),
+                stopping: AtomicBool::new(false),
+            }),
+            workers,
+            timeouts,
+            https_listeners: Vec::new(),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Server {
+        let server = Server::new(config.sites(), config.workers, config.timeouts);
+        if config.metrics.enabled {
+            server.metrics_endpoint(&config.metrics.path, config.metrics.allowed())
+        } else {
+            server
+        }
+    }
+
+    /// Serves the metrics at `path`, for every site, to clients whose
+    /// address is in one of the `allow`ed blocks.
+    pub fn metrics_endpoint(mut self, path: &str, allow: Vec<Cidr>) -> Server {
+        let shared = Arc::get_mut(&mut self.shared).expect("server is not running yet");
+        shared.metrics_endpoint = Some(MetricsEndpoint {
+            path: path.to_string(),
+            allow,
+        });
+        self
+    }
+
+    /// Also serves HTTPS, with `tls`, on each of `listeners`.
+    pub fn https(mut self, listeners: Vec<TcpListener>, tls: Arc<ServerConfig>) -> Server {
+        for listener in listeners {
+            self.https_listeners.push((listener, Arc::clone(&tls)));
+        }
+        self
+    }
+
+    /// Accepts connections on every listener, handing them to a pool of
+    /// worker threads, which is where the book's multi threaded server ends
+    /// up. Runs until all listeners fail.
+    pub fn run(self, listeners: Vec<TcpListener>) {
+        match self.start(listeners) {
+            Ok(handle) => handle.wait(),
+            Err(e) => eprintln!("Failed to start the server: {}", e),
+        }
+    }
+
+    /// Listens on `address`, e.g. `127.0.0.1:0` for any free port, and
+    /// serves from background threads. The returned handle tells the
+    /// address actually bound.
+    pub fn bind(self, address: impl ToSocketAddrs) -> io::Result<ServerHandle> {
+        self.start(vec![TcpListener::bind(address)?])
+    }
+
+    /// Serves on every listener from background threads, until the
+    /// returned handle is told to shut down.
+    pub fn start(mut self, listeners: Vec<TcpListener>) -> io::Result<ServerHandle> {
+        let https_listeners = std::mem::take(&mut self.https_listeners);
+        let all_listeners: Vec<_> = listeners
+            .into_iter()
+            .map(|listener| (listener, None))
+            .chain(
+                https_listeners
+                    .into_iter()
+                    .map(|(listener, tls)| (listener, Some(tls))),
+            )
+            .collect();
+        let addresses = all_listeners
+            .iter()
+            .map(|(listener, _)| listener.local_addr())
+            .collect::<io::Result<Vec<_>>>()?;
+
+        // Shared by the acceptors, so the last one to stop joins the workers
+        let pool = ThreadPool::new(self.workers)
+            .with_queue_gauge(Arc::clone(&self.shared.metrics.queue_depth));
+        let pool = Arc::new(pool);
+        let acceptors = all_listeners
+            .into_iter()
+            .map(|(listener, tls)| {
+                let pool = Arc::clone(&pool);
+                let shared = Arc::clone(&self.shared);
+                let timeouts = self.timeouts;
+                thread::spawn(move || accept(listener, tls, pool, shared, timeouts))
+            })
+            .collect();
+        self.shared.ready.store(true, Ordering::SeqCst);
+
+        Ok(ServerHandle {
+            shared: self.shared,
+            addresses,
+            acceptors,
+        })
+    }
+}
+
+/// A running server.
+pub struct ServerHandle {
+    shared: Arc<Shared>,
+    addresses: Vec<SocketAddr>,
+    acceptors: Vec<JoinHandle<()>>,
+}
+
+impl ServerHandle {
+    /// Address of the first listener.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addresses[0]
+    }
+
+    /// Addresses of the plain HTTP listeners, followed by the HTTPS ones.
+    pub fn local_addrs(&self) -> &[SocketAddr] {
+        &self.addresses
+    }
+
+    /// Blocks until every listener failed, or the server was shut down.
+    pub fn wait(self) {
+        for acceptor in self.acceptors {
+            let _ = acceptor.join();
+        }
+        self.shared.ready.store(false, Ordering::SeqCst);
+    }
+
+    /// Stops accepting connections, lets the requests being handled finish
+    /// and returns once they did. Kept-alive connections are closed after
+    /// their current request, or, if idle, once their idle timeout is up.
+    pub fn shutdown(self) {
+        self.shared.ready.store(false, Ordering::SeqCst);
+        self.shared.stopping.store(true, Ordering::SeqCst);
+        // Acceptors only notice once they accept something
+        for address in &self.addresses {
+            let _ = TcpStream::connect(wake_address(*address));
+        }
+        self.wait();
+    }
+}
+
+/// Where to connect to reach a listener, which might be bound to all
+/// interfaces rather than to one address.
+fn wake_address(mut address: SocketAddr) -> SocketAddr {
+    if address.ip().is_unspecified() {
+        let loopback = match address {
+            SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        };
+        address.set_ip(loopback);
+    }
+    address
+}
+
+fn accept(
+    listener: TcpListener,
+    tls: Option<Arc<ServerConfig>>,
+    pool: Arc<ThreadPool>,
+    shared: Arc<Shared>,
+    timeouts: Timeouts,
+) {
+    for stream in listener.incoming() {
+        if shared.stopping.load(Ordering::SeqCst) {
+            break;
+        }
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Failed to accept connection: {}", e);
+                continue;
+            }
+        };
+        let shared = Arc::clone(&shared);
+        let tls = tls.clone();
+        pool.execute(move || {
+            if let Err(e) = serve_tcp(stream, tls, &shared, timeouts) {
+                eprintln!("Connection error: {}", e);
+            }
+        });
+    }
+}
+
+fn serve_tcp(
+    stream: TcpStream,
+    tls: Option<Arc<ServerConfig>>,
+    shared: &Shared,
+    timeouts: Timeouts,
+) -> io::Result<()> {
+    let remote_addr = stream.peer_addr().ok();
+    let guard = Guard::new(timeouts);
+    let guarded = GuardedStream::new(&stream, &guard);
+
+    // Served through a reference, so the connection is only closed once
+    // it's no longer counted as active, and its request as metered.
+    let _active = Tracked::new(&shared.metrics.active_connections);
+    match tls {
+        None => handle_connection(guarded, remote_addr, false, shared, &guard),
+        Some(tls) => serve_tls(guarded, tls, remote_addr, shared, &guard),
+    }
+}
+
+/// Serves the connection through TLS. The handshake happens on the first
+/// read, while reading the request, so it counts against the header timeout.
+fn serve_tls(
+    stream: GuardedStream,
+    tls: Arc<ServerConfig>,
+    remote_addr: Option<SocketAddr>,
+    shared: &Shared,
+    guard: &Guard,
+) -> io::Result<()> {
+    let connection = ServerConnection::new(tls).map_err(io::Error::other)?;
+    let mut stream = StreamOwned::new(connection, stream);
+    handle_connection(&mut stream, remote_addr, true, shared, guard)?;
+    // Tells the client the last response is complete, rather than truncated
+    stream.conn.send_close_notify();
+    stream.flush()
+}
+
+/// Reads requests from `stream` for as long as the client keeps the
+/// connection alive, routes each one to the site its `Host` header asks for
+/// and writes the response.
+///
+/// Generic over the stream, so anything that reads and writes bytes (like a
+/// `TcpStream`) can be served.
+fn handle_connection<S: Read + Write>(
+    stream: S,
+    remote_addr: Option<SocketAddr>,
+    tls: bool,
+    shared: &Shared,
+    guard: &Guard,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    while handle_request(&mut reader, remote_addr, tls, shared, guard)? {
+        if shared.stopping.load(Ordering::SeqCst) {
+            break;
+        }
+        guard.idle();
+    }
+    Ok(())
+}
+
+/// Serves the next request on the connection, and returns whether the
+/// connection stays open for another one.
+fn handle_request<S: Read + Write>(
+    reader: &mut BufReader<S>,
+    remote_addr: Option<SocketAddr>,
+    tls: bool,
+    shared: &Shared,
+    guard: &Guard,
+) -> io::Result<bool> {
+    let mut request = match Request::read_from(reader) {
+        Ok(request) => request,
+        Err(ParseError::Closed) => return Ok(false),
+        // Nothing was asked for, so there is nothing to answer
+        Err(ParseError::Io(_)) if guard.timed_out() == Some(Phase::Idle) => return Ok(false),
+        Err(ParseError::Io(e)) if guard.timed_out().is_none() => return Err(e),
+        Err(e) => {
+            let status = match e {
+                ParseError::Io(_) => 408,
+                ParseError::TooLarge => 431,
+                ParseError::Unsupported(_) => 501,
+                _ => 400,
+            };
+            eprintln!("Rejected request: {}, Status: {}", e, status);
+            shared
+                .metrics
+                .observe("rejected", status, Default::default());
+            guard.start_response();
+            return Response::error(status).write_to(reader.get_mut(), None, false);
+        }
+    };
+    request.remote_addr = remote_addr;
+    request.tls = tls;
+    let started = Instant::now();
+    guard.start_body();
+
+    let router = shared.sites.for_host(request.host());
+    let path = request.path();
+    let awaiting_continue = Cell::new(expects_continue(&request));
+    let mut body_reader = Continue {
+        reader,
+        pending: &awaiting_continue,
+    };
+    let mut body = RequestBody::new(&request, &mut body_reader);
+    // Route labels for the metrics; real paths start with `/`, so `static`
+    // can't clash with one
+    let (route, mut response) = if path == HEALTHZ {
+        (HEALTHZ, Response::text(200, "ok\n"))
+    } else if path == READYZ {
+        (READYZ, shared.readiness())
+    } else if let Some(endpoint) = shared.metrics_endpoint.as_ref().filter(|e| e.path == path) {
+        (
+            endpoint.path.as_str(),
+            shared.serve_metrics(endpoint, &request),
+        )
+    } else {
+        let response = router.handle(&request, &mut body);
+        (router.route_path(path).unwrap_or("static"), response)
+    };
+    // Whatever the handler made of a body that stopped arriving, the
+    // client gets told it was too slow
+    let timed_out = guard.timed_out().is_some();
+    if timed_out {
+        response = Response::error(408);
+    }
+    // The next request starts right after this one's body, so a body the
+    // handler didn't read is skipped; a large one isn't worth waiting for.
+    // A client still waiting to be told to send it may not send it at all,
+    // so that connection is closed instead
+    let body_read = !timed_out
+        && !awaiting_continue.get()
+        && io::copy(
+            &mut body.by_ref().take(MAX_SKIPPED_BODY + 1),
+            &mut io::sink(),
+        )
+        .is_ok_and(|skipped| skipped <= MAX_SKIPPED_BODY);
+    eprintln!(
+        "{} {} -> Status: {}",
+        request.method, request.target, response.status
+    );
+
+    match response.body {
+        Body::Stream(_) => guard.start_stream(),
+        _ => guard.start_response(),
+    }
+    let status = response.status;
+    let keep_alive = body_read && wants_keep_alive(&request);
+    let result = response.write_to(reader.get_mut(), Some(&request), keep_alive);
+    shared.metrics.observe(route, status, started.elapsed());
+    result
+}
+
+/// Whether the client waits for a `100 Continue` before it sends the body.
+fn expects_continue(request: &Request) -> bool {
+    request.version == "HTTP/1.1"
+        && request.headers.has_token("Expect", "100-continue")
+        && (request.content_length() > 0 || request.is_chunked())
+}
+
+/// The connection as request bodies read it: tells a client that expects
+/// it to go ahead with `100 Continue` the first time the body is read, so
+/// it's only sent for handlers that want the body.
+struct Continue<'a, S> {
+    reader: &'a mut BufReader<S>,
+    pending: &'a Cell<bool>,
+}
+
+impl<S: Read + Write> Continue<'_, S> {
+    fn send(&mut self) -> io::Result<()> {
+        if self.pending.replace(false) {
+            let stream = self.reader.get_mut();
+            stream.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+            stream.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl<S: Read + Write> Read for Continue<'_, S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.send()?;
+        self.reader.read(buf)
+    }
+}
+
+impl<S: Read + Write> BufRead for Continue<'_, S> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.send()?;
+        self.reader.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.reader.consume(amount)
+    }
+}
+
+/// Whether the client asked for the connection to stay open, which is the
+/// default from HTTP/1.1 on.
+fn wants_keep_alive(request: &Request) -> bool {
+    if request.headers.has_token("Connection", "close") {
+        false
+    } else if request.version == "HTTP/1.1" {
+        true
+    } else {
+        request.headers.has_token("Connection", "keep-alive")
+    }
+}
+
+impl Shared {
+    fn readiness(&self) -> Response {
+        if !self.ready.load(Ordering::SeqCst) {
+            return Response::text(503, "not accepting connections\n");
+        }
+        if let Some(router) = self.sites.routers().find(|router| !router.root().is_dir()) {
+            return Response::text(
+                503,
+                format!("document root {} is missing\n", router.root().display()),
+            );
+        }
+        Response::text(200, "ready\n")
+    }
+
+    fn serve_metrics(&self, endpoint: &MetricsEndpoint, request: &Request) -> Response {
+        let allowed = request
+            .remote_addr
+            .is_some_and(|addr| endpoint.allow.iter().any(|cidr| cidr.contains(addr.ip())));
+        if !allowed {
+            return Response::error(403);
+        }
+        if request.method != "GET" && request.method != "HEAD" {
+            return Response::error(405).with_header("Allow", "GET, HEAD");
+        }
+        Response::text(200, self.metrics.render())
+            .with_header("Content-Type", "text/plain; version=0.0.4; charset=utf-8")
+    }
+}
@@ -0,0 +1,197 @@
+//! HTTPS: a certificate per site, picked by the server name the client
+//! asks for (SNI), and reloaded whenever its PEM files change on disk.
+use std::{
+    collections::HashMap,
+    error, fmt, fs,
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock, Weak},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use rustls::{
+    crypto::{ring, CryptoProvider},
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+    ServerConfig,
+};
+
+#[derive(Debug)]
+pub enum TlsError {
+    Read(PathBuf, io::Error),
+    /// The certificate file has no PEM certificate in it.
+    NoCertificates(PathBuf),
+    /// The key file has no PEM private key in it.
+    NoKey(PathBuf),
+    /// The key is not usable, or not the certificate's.
+    Key(PathBuf, rustls::Error),
+}
+
+impl fmt::Display for TlsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TlsError::Read(path, e) => write!(f, "cannot read {}: {}", path.display(), e),
+            TlsError::NoCertificates(path) => {
+                write!(f, "no certificate found in {}", path.display())
+            }
+            TlsError::NoKey(path) => write!(f, "no private key found in {}", path.display()),
+            TlsError::Key(path, e) => write!(f, "unusable key {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl error::Error for TlsError {}
+
+/// Certificate chain and private key of a site, as PEM files.
+#[derive(Debug)]
+struct Source {
+    cert: PathBuf,
+    key: PathBuf,
+    /// Modification times the files had when last loaded.
+    modified: (Option<SystemTime>, Option<SystemTime>),
+}
+
+impl Source {
+    fn new(cert: &Path, key: &Path) -> Source {
+        Source {
+            cert: cert.to_path_buf(),
+            key: key.to_path_buf(),
+            modified: (None, None),
+        }
+    }
+
+    fn current_modified(&self) -> (Option<SystemTime>, Option<SystemTime>) {
+        let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+        (modified(&self.cert), modified(&self.key))
+    }
+
+    fn load(&mut self) -> Result<Arc<CertifiedKey>, TlsError> {
+        self.modified = self.current_modified();
+        load_certified_key(&self.cert, &self.key).map(Arc::new)
+    }
+}
+
+/// The certificates of every site; the first one is used for clients that
+/// ask for no, or an unknown, server name.
+#[derive(Debug)]
+pub struct Certificates {
+    sources: RwLock<Vec<Source>>,
+    keys: RwLock<Vec<Arc<CertifiedKey>>>,
+    /// Lower-cased server name to index in `keys`.
+    names: HashMap<String, usize>,
+}
+
+impl Certificates {
+    /// Certificates with `cert` and `key` as the default.
+    pub fn new(cert: &Path, key: &Path) -> Result<Certificates, TlsError> {
+        let mut source = Source::new(cert, key);
+        let key = source.load()?;
+        Ok(Certificates {
+            sources: RwLock::new(vec![source]),
+            keys: RwLock::new(vec![key]),
+            names: HashMap::new(),
+        })
+    }
+
+    /// Presents `cert` to clients asking for any of `names`.
+    pub fn site<I, S>(mut self, names: I, cert: &Path, key: &Path) -> Result<Certificates, TlsError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut source = Source::new(cert, key);
+        let key = source.load()?;
+        let keys = self.keys.get_mut().unwrap();
+        let index = keys.len();
+        keys.push(key);
+        self.sources.get_mut().unwrap().push(source);
+        for name in names {
+            self.names.insert(name.as_ref().to_ascii_lowercase(), index);
+        }
+        Ok(self)
+    }
+
+    /// Loads the certificates whose files changed since they were last
+    /// loaded. A certificate that fails to load, e.g. because only one of
+    /// its files was replaced so far, stays in use until it loads again.
+    pub fn reload(&self) {
+        let mut sources = self.sources.write().unwrap();
+        for (index, source) in sources.iter_mut().enumerate() {
+            if source.current_modified() == source.modified {
+                continue;
+            }
+            match source.load() {
+                Ok(key) => {
+                    self.keys.write().unwrap()[index] = key;
+                    eprintln!("Reloaded certificate {}", source.cert.display());
+                }
+                Err(e) => eprintln!("Keeping the previous certificate: {}", e),
+            }
+        }
+    }
+
+    /// Checks for changed certificates every `interval`, in a background
+    /// thread that ends once the certificates are dropped.
+    pub fn watch(self: &Arc<Self>, interval: Duration) {
+        let certificates: Weak<Certificates> = Arc::downgrade(self);
+        thread::Builder::new()
+            .name("certificate-watcher".to_string())
+            .spawn(move || loop {
+                thread::sleep(interval);
+                match certificates.upgrade() {
+                    Some(certificates) => certificates.reload(),
+                    None => break,
+                }
+            })
+            .expect("failed to spawn certificate watcher");
+    }
+
+    /// `rustls` configuration serving these certificates.
+    pub fn server_config(self: &Arc<Self>) -> Arc<ServerConfig> {
+        let mut config = ServerConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()
+            .expect("ring supports the default protocol versions")
+            .with_no_client_auth()
+            .with_cert_resolver(Arc::clone(self) as Arc<dyn ResolvesServerCert>);
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        Arc::new(config)
+    }
+}
+
+impl ResolvesServerCert for Certificates {
+    fn resolve(&self, client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        let index = client_hello
+            .server_name()
+            .and_then(|name| self.names.get(&name.to_ascii_lowercase()))
+            .copied()
+            .unwrap_or(0);
+        Some(Arc::clone(&self.keys.read().unwrap()[index]))
+    }
+}
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(ring::default_provider())
+}
+
+/// Reads a PEM certificate chain and the private key belonging to it.
+pub fn load_certified_key(cert: &Path, key: &Path) -> Result<CertifiedKey, TlsError> {
+    let open = |path: &Path| {
+        fs::File::open(path)
+            .map(BufReader::new)
+            .map_err(|e| TlsError::Read(path.to_path_buf(), e))
+    };
+
+    let chain = rustls_pemfile::certs(&mut open(cert)?)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| TlsError::Read(cert.to_path_buf(), e))?;
+    if chain.is_empty() {
+        return Err(TlsError::NoCertificates(cert.to_path_buf()));
+    }
+    let private_key = rustls_pemfile::private_key(&mut open(key)?)
+        .map_err(|e| TlsError::Read(key.to_path_buf(), e))?
+        .ok_or_else(|| TlsError::NoKey(key.to_path_buf()))?;
+
+    CertifiedKey::from_der(chain, private_key, &provider())
+        .map_err(|e| TlsError::Key(key.to_path_buf(), e))
+}
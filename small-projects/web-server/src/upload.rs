@@ -0,0 +1,226 @@
+//! Saves the files of a `multipart/form-data` POST into a directory.
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{
+    http::{Body, Request, RequestBody, Response},
+    multipart::{self, Multipart, MultipartError},
+};
+
+/// Longest file name most file systems accept, in bytes.
+const MAX_NAME_LENGTH: usize = 255;
+/// Room left in a name for the `-N` suffix `claim_name` adds when it's
+/// taken, up to `-9999999`.
+const SUFFIX_LENGTH: usize = 8;
+
+pub struct Upload {
+    dir: PathBuf,
+    /// Largest single file accepted, in bytes.
+    max_file_size: u64,
+    /// Largest request body accepted, in bytes.
+    max_request_size: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Saved {
+    name: String,
+    size: u64,
+}
+
+impl Upload {
+    pub fn new(dir: impl Into<PathBuf>, max_file_size: u64) -> Upload {
+        Upload {
+            dir: dir.into(),
+            max_file_size,
+            max_request_size: None,
+        }
+    }
+
+    pub fn max_request_size(mut self, limit: u64) -> Upload {
+        self.max_request_size = Some(limit);
+        self
+    }
+
+    /// Saves every file part of the request and answers with their names,
+    /// as JSON. Either all files are kept, or, when anything went wrong,
+    /// none of them.
+    pub fn handle(&self, request: &Request, body: &mut RequestBody) -> Response {
+        if request.method != "POST" {
+            return Response::error(405).with_header("Allow", "GET, HEAD, POST");
+        }
+        let boundary = match request
+            .headers
+            .get("Content-Type")
+            .and_then(multipart::boundary)
+        {
+            Some(boundary) => boundary,
+            None => return Response::error(415),
+        };
+        let limit = self.max_request_size.unwrap_or(u64::MAX);
+        if request.content_length() > limit {
+            return Response::error(413);
+        }
+
+        // Chunked bodies can't be checked up front, so they are cut off at
+        // the limit, which makes the body end before its final delimiter
+        let mut body = body.take(limit);
+        let mut saved = Vec::new();
+        let result = self.save_all(&mut Multipart::new(&mut body, &boundary), &mut saved);
+        let result = match result {
+            Err(MultipartError::Malformed(_)) if body.limit() == 0 => Err(MultipartError::TooLarge),
+            result => result,
+        };
+        if let Err(e) = result {
+            for file in &saved {
+                let _ = fs::remove_file(self.dir.join(&file.name));
+            }
+            return match e {
+                MultipartError::TooLarge => Response::error(413),
+                MultipartError::Malformed(reason) => {
+                    eprintln!("Rejected upload: {}", reason);
+                    Response::error(400)
+                }
+                MultipartError::Io(e) => {
+                    eprintln!("Failed to save upload: {}", e);
+                    Response::error(500)
+                }
+            };
+        }
+        if saved.is_empty() {
+            return Response::text(400, "no files in upload\n");
+        }
+
+        for file in &saved {
+            eprintln!("Saved upload {} ({} bytes)", file.name, file.size);
+        }
+        let json = serde_json::json!({ "files": saved });
+        Response::new(201)
+            .with_header("Content-Type", "application/json")
+            .with_body(Body::Bytes(json.to_string().into_bytes()))
+    }
+
+    fn save_all<R: Read>(
+        &self,
+        multipart: &mut Multipart<R>,
+        saved: &mut Vec<Saved>,
+    ) -> Result<(), MultipartError> {
+        while let Some(part) = multipart.next_part()? {
+            // Plain form fields are not files and are skipped
+            let filename = match part.filename {
+                Some(filename) => filename,
+                None => continue,
+            };
+            saved.push(self.save(multipart, &sanitize(&filename))?);
+        }
+        Ok(())
+    }
+
+    /// Writes the current part to a hidden temporary file that is only
+    /// renamed to a free name once complete, so a half written upload is
+    /// never served. The temporary name doesn't depend on `name`, which
+    /// can be as long as names get already.
+    fn save<R: Read>(
+        &self,
+        multipart: &mut Multipart<R>,
+        name: &str,
+    ) -> Result<Saved, MultipartError> {
+        let (partial, file) = create_unique(&self.dir, ".upload", ".part")?;
+        let mut out = BufWriter::new(file);
+        let result = multipart
+            .copy_part(&mut out, self.max_file_size)
+            .and_then(|size| out.flush().map(|_| size).map_err(MultipartError::Io));
+        drop(out);
+
+        let result = result.and_then(|size| {
+            let target = claim_name(&self.dir, name)?;
+            fs::rename(&partial, &target)?;
+            let name = target.file_name().unwrap().to_string_lossy().into_owned();
+            Ok(Saved { name, size })
+        });
+        if result.is_err() {
+            let _ = fs::remove_file(&partial);
+        }
+        result
+    }
+}
+
+/// Turns the file name the client sent into one that is safe to store: the
+/// last path component only, without leading dots or unusual characters,
+/// and short enough to take a `-N` suffix.
+pub fn sanitize(filename: &str) -> String {
+    let base = filename.rsplit(['/', '\\']).next().unwrap_or("");
+    let mut name: String = base
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '.' || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    name = name.trim_start_matches('.').to_string();
+
+    if name.len() > MAX_NAME_LENGTH - SUFFIX_LENGTH {
+        let mut end = MAX_NAME_LENGTH - SUFFIX_LENGTH;
+        while !name.is_char_boundary(end) {
+            end -= 1;
+        }
+        name.truncate(end);
+    }
+    if name.is_empty() {
+        "upload".to_string()
+    } else {
+        name
+    }
+}
+
+/// Creates an empty file at `name`, or `name-1`, `name-2`... when taken,
+/// keeping the extension last, and returns its path.
+fn claim_name(dir: &Path, name: &str) -> io::Result<PathBuf> {
+    let (stem, extension) = match name.rfind('.') {
+        Some(dot) if dot > 0 => name.split_at(dot),
+        _ => (name, ""),
+    };
+    let (path, _) = create_unique(dir, stem, extension)?;
+    Ok(path)
+}
+
+fn create_unique(dir: &Path, stem: &str, extension: &str) -> io::Result<(PathBuf, File)> {
+    for attempt in 0.. {
+        let name = if attempt == 0 {
+            format!("{}{}", stem, extension)
+        } else {
+            format!("{}-{}{}", stem, attempt, extension)
+        };
+        let path = dir.join(name);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!("ran out of suffixes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitizes_file_names() {
+        assert_eq!(sanitize("report.pdf"), "report.pdf");
+        assert_eq!(sanitize("../../etc/passwd"), "passwd");
+        assert_eq!(sanitize("C:\\Users\\me\\notes.txt"), "notes.txt");
+        assert_eq!(sanitize("..hidden"), "hidden");
+        assert_eq!(sanitize("a b;c<d>.txt"), "a_b_c_d_.txt");
+        assert_eq!(sanitize("dir/"), "upload");
+        assert_eq!(sanitize("..."), "upload");
+        assert_eq!(sanitize(&"é".repeat(200)).len(), 246);
+    }
+}
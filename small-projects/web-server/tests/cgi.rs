@@ -0,0 +1,243 @@
+#![cfg(unix)]
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpStream},
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    thread,
+    time::{Duration, Instant},
+};
+
+mod common;
+use common::{body, send, spawn_server, temp_dir};
+
+const SCRIPTS: [(&str, &str); 7] = [
+    (
+        "env.sh",
+        "printf 'Content-Type: text/plain\\r\\n\\r\\n'\n\
+         for name in GATEWAY_INTERFACE SERVER_NAME SERVER_PORT REQUEST_METHOD SCRIPT_NAME \
+         PATH_INFO QUERY_STRING REMOTE_ADDR CONTENT_LENGTH CONTENT_TYPE HTTP_X_CUSTOM HTTP_PROXY; do\n\
+           eval \"echo $name=\\${$name-unset}\"\n\
+         done\n\
+         echo \"body=$(cat)\"",
+    ),
+    (
+        "status.sh",
+        "printf 'Status: 404 Not Found\\r\\nContent-Type: text/plain\\r\\nX-Script: yes\\r\\n\\r\\ngone\\n'",
+    ),
+    ("redirect.sh", "printf 'Location: https://example.com/\\n\\n'"),
+    (
+        "length.sh",
+        "printf 'Content-Type: text/plain\\nContent-Length: 6\\n\\nsized\\n'",
+    ),
+    ("malformed.sh", "echo 'not a header'; echo"),
+    // Replaced by `sleep`, so nothing else keeps its output open
+    ("slow.sh", "exec sleep 5"),
+    (
+        "ticker.sh",
+        "echo $$ > ticker.pid\n\
+         printf 'Content-Type: text/plain\\n\\n'\n\
+         while true; do echo tick; sleep 0.1; done",
+    ),
+];
+
+/// Server for a site with the scripts above in `cgi-bin`, run for
+/// `/cgi-bin` and, with a one second timeout, for `/slow-bin`, and that
+/// directory. `scripts` links to it too.
+///
+/// Shared by every test: writing an executable while another thread forks
+/// can leave it open in the child, and running it then fails with "text
+/// file busy".
+fn site() -> &'static (SocketAddr, PathBuf) {
+    static SITE: OnceLock<(SocketAddr, PathBuf)> = OnceLock::new();
+    SITE.get_or_init(|| {
+        let root = temp_dir("cgi");
+        fs::write(root.join("404.html"), "not found").unwrap();
+        let dir = root.join("cgi-bin");
+        fs::create_dir(&dir).unwrap();
+        for (name, script) in SCRIPTS.iter() {
+            write_script(&dir.join(name), script, 0o755);
+        }
+        write_script(&dir.join("plain.sh"), "echo", 0o644);
+        std::os::unix::fs::symlink(&dir, root.join("scripts")).unwrap();
+
+        let server = spawn_server(&format!(
+            "root = {:?}\n\
+             [[routes]]\nkind = \"cgi\"\npath = \"/cgi-bin\"\ndir = \"cgi-bin\"\n\
+             [[routes]]\nkind = \"cgi\"\npath = \"/slow-bin\"\ndir = \"cgi-bin\"\ntimeout = 1",
+            root
+        ));
+        (server, dir)
+    })
+}
+
+fn server() -> SocketAddr {
+    site().0
+}
+
+fn write_script(path: &Path, script: &str, mode: u32) {
+    fs::write(path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+}
+
+fn text(response: &[u8]) -> String {
+    String::from_utf8_lossy(response).into_owned()
+}
+
+#[test]
+fn passes_requests_to_scripts() {
+    let response = send(
+        server(),
+        b"POST /cgi-bin/env.sh/some%20where/else?x=1&y=2 HTTP/1.1\r\n\
+          Host: example.com:8080\r\nX-Custom: a\r\nX-Custom: b\r\nProxy: evil\r\n\
+          Content-Type: text/plain\r\nContent-Length: 5\r\n\r\nhello",
+    );
+    assert!(
+        response.starts_with(b"HTTP/1.1 200 OK\r\n"),
+        "{}",
+        text(&response)
+    );
+    let output = text(body(&response));
+    for line in [
+        "GATEWAY_INTERFACE=CGI/1.1",
+        "SERVER_NAME=example.com",
+        "SERVER_PORT=8080",
+        "REQUEST_METHOD=POST",
+        "SCRIPT_NAME=/cgi-bin/env.sh",
+        "PATH_INFO=/some where/else",
+        "QUERY_STRING=x=1&y=2",
+        "REMOTE_ADDR=127.0.0.1",
+        "CONTENT_LENGTH=5",
+        "CONTENT_TYPE=text/plain",
+        "HTTP_X_CUSTOM=a, b",
+        "HTTP_PROXY=unset",
+        "body=hello",
+    ]
+    .iter()
+    {
+        assert!(
+            output.lines().any(|l| l == *line),
+            "{} not in\n{}",
+            line,
+            output
+        );
+    }
+}
+
+#[test]
+fn turns_script_output_into_responses() {
+    let response = text(&send(server(), b"GET /cgi-bin/status.sh HTTP/1.1\r\n\r\n"));
+    assert!(
+        response.starts_with("HTTP/1.1 404 Not Found\r\n"),
+        "{}",
+        response
+    );
+    assert!(response.contains("X-Script: yes\r\n"));
+    assert!(!response.contains("Status:"));
+    assert!(response.ends_with("\r\n\r\n5\r\ngone\n\r\n0\r\n\r\n"));
+
+    let response = text(&send(
+        server(),
+        b"GET /cgi-bin/redirect.sh HTTP/1.1\r\n\r\n",
+    ));
+    assert!(
+        response.starts_with("HTTP/1.1 302 Found\r\n"),
+        "{}",
+        response
+    );
+    assert!(response.contains("Location: https://example.com/\r\n"));
+
+    let response = text(&send(server(), b"GET /cgi-bin/length.sh HTTP/1.1\r\n\r\n"));
+    assert!(response.contains("Content-Length: 6\r\n"), "{}", response);
+    assert!(!response.contains("chunked"));
+    assert!(response.ends_with("\r\n\r\nsized\n"));
+}
+
+#[test]
+fn never_serves_scripts_as_files() {
+    // However the path is spelled, the script is run...
+    for request in [
+        &b"GET /cgi%2Dbin/env.sh HTTP/1.1\r\n\r\n"[..],
+        b"GET /./cgi-bin/env.sh HTTP/1.1\r\n\r\n",
+    ]
+    .iter()
+    {
+        let response = send(server(), request);
+        assert!(
+            response.starts_with(b"HTTP/1.1 200 OK\r\n"),
+            "{}",
+            text(&response)
+        );
+        let output = text(body(&response));
+        assert!(output.contains("GATEWAY_INTERFACE=CGI/1.1"), "{}", output);
+        assert!(!output.contains("#!/bin/sh"));
+    }
+
+    // ...and not even another way into its directory gets its source
+    let response = text(&send(server(), b"GET /scripts/env.sh HTTP/1.1\r\n\r\n"));
+    assert!(
+        response.starts_with("HTTP/1.1 404 Not Found\r\n"),
+        "{}",
+        response
+    );
+    assert!(!response.contains("#!/bin/sh"));
+}
+
+#[test]
+fn reports_scripts_that_cannot_run() {
+    for (request, status) in [
+        (&b"GET /cgi-bin/missing.sh HTTP/1.1\r\n\r\n"[..], "404"),
+        (b"GET /cgi-bin/ HTTP/1.1\r\n\r\n", "404"),
+        (
+            b"GET /cgi-bin/..%2Fcgi-bin%2Fenv.sh HTTP/1.1\r\n\r\n",
+            "404",
+        ),
+        (b"GET /cgi-bin/plain.sh HTTP/1.1\r\n\r\n", "403"),
+        (b"GET /cgi-bin/malformed.sh HTTP/1.1\r\n\r\n", "502"),
+        (
+            b"POST /cgi-bin/env.sh HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n",
+            "411",
+        ),
+    ]
+    .iter()
+    {
+        let response = text(&send(server(), request));
+        assert!(
+            response.starts_with(&format!("HTTP/1.1 {} ", status)),
+            "{}: {}",
+            text(request),
+            response
+        );
+    }
+
+    let started = Instant::now();
+    let response = text(&send(server(), b"GET /slow-bin/slow.sh HTTP/1.1\r\n\r\n"));
+    assert!(response.starts_with("HTTP/1.1 504 "), "{}", response);
+    assert!(started.elapsed() < Duration::from_secs(4));
+}
+
+#[test]
+fn kills_scripts_when_the_client_disconnects() {
+    let mut stream = TcpStream::connect(server()).unwrap();
+    stream
+        .write_all(b"GET /cgi-bin/ticker.sh HTTP/1.1\r\n\r\n")
+        .unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    while line != "tick\r\n" && line != "tick\n" {
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+    }
+    let pid = fs::read_to_string(site().1.join("ticker.pid")).unwrap();
+    let process = Path::new("/proc").join(pid.trim());
+    assert!(process.exists());
+    drop(reader);
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while process.exists() {
+        assert!(Instant::now() < deadline, "script still runs");
+        thread::sleep(Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,199 @@
+#![allow(dead_code)]
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    path::Path,
+    thread,
+};
+
+use web_server::{
+    config::{Config, Timeouts},
+    http::{ChunkedReader, Headers, Request, RequestBody},
+    router::Sites,
+    server::{Server, ServerHandle},
+};
+
+/// Starts the server on an ephemeral port, in background threads.
+/// Paths in `config` are relative to the crate's directory.
+pub fn spawn_server(config: &str) -> SocketAddr {
+    start_server(config).local_addr()
+}
+
+/// Like `spawn_server`, but returns the handle, e.g. to shut it down.
+pub fn start_server(config: &str) -> ServerHandle {
+    let config = Config::parse(config, Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+    Server::from_config(&config).bind("127.0.0.1:0").unwrap()
+}
+
+/// Starts a server for hand-built sites, e.g. with `Handler::Function` routes.
+pub fn spawn_sites(sites: Sites) -> SocketAddr {
+    Server::new(sites, 4, Timeouts::default())
+        .bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+}
+
+/// A response, as read by `request`.
+#[derive(Debug)]
+pub struct Reply {
+    pub status: u16,
+    pub headers: Headers,
+    pub body: Vec<u8>,
+}
+
+impl Reply {
+    pub fn text(&self) -> &str {
+        std::str::from_utf8(&self.body).expect("body is not UTF-8")
+    }
+}
+
+/// Minimal blocking HTTP/1.1 client: sends a request on a connection of
+/// its own and reads the response, whatever its framing.
+pub fn request(
+    addr: SocketAddr,
+    method: &str,
+    target: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+) -> io::Result<Reply> {
+    let mut stream = TcpStream::connect(addr)?;
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n",
+        method, target
+    );
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    if !body.is_empty() {
+        head.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body)?;
+
+    let mut reader = BufReader::new(stream);
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line)?;
+    let status = status_line
+        .split(' ')
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| invalid(format!("invalid status line {:?}", status_line)))?;
+
+    let mut headers = Headers::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| invalid(format!("invalid header {:?}", line)))?;
+        headers.append(name, value.trim());
+    }
+
+    let mut body = Vec::new();
+    if method != "HEAD" {
+        if headers.has_token("Transfer-Encoding", "chunked") {
+            ChunkedReader::new(reader).read_to_end(&mut body)?;
+        } else if let Some(length) = headers.get("Content-Length") {
+            let length = length
+                .parse()
+                .map_err(|_| invalid(format!("invalid length {:?}", length)))?;
+            reader.take(length).read_to_end(&mut body)?;
+        } else {
+            reader.read_to_end(&mut body)?;
+        }
+    }
+    Ok(Reply {
+        status,
+        headers,
+        body,
+    })
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Sends `raw` as is and returns everything the server answered with,
+/// until it closed the connection. Sending nothing more tells the server
+/// not to wait for another request.
+pub fn send(addr: SocketAddr, raw: &[u8]) -> Vec<u8> {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(raw).unwrap();
+    stream.shutdown(Shutdown::Write).unwrap();
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).unwrap();
+    response
+}
+
+pub fn get(addr: SocketAddr, path: &str) -> String {
+    let raw = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path);
+    String::from_utf8(send(addr, raw.as_bytes())).unwrap()
+}
+
+/// Body of a raw response, i.e. everything after the empty line.
+pub fn body(response: &[u8]) -> &[u8] {
+    let end = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .expect("response without a head");
+    &response[end + 4..]
+}
+
+/// Upstream for proxy tests. Answers every request with its `name`, the
+/// request line, headers and trailers it received and, after an empty line,
+/// the request body.
+pub fn spawn_upstream(name: &'static str) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = stream.unwrap();
+            thread::spawn(move || echo(name, stream));
+        }
+    });
+    addr
+}
+
+fn echo(name: &str, stream: TcpStream) {
+    let mut reader = BufReader::new(stream);
+    let request = Request::read_from(&mut reader).unwrap();
+    let mut request_body = RequestBody::new(&request, &mut reader);
+    let mut body = Vec::new();
+    request_body.read_to_end(&mut body).unwrap();
+
+    let mut contents = format!("{}\n{} {}\n", name, request.method, request.target).into_bytes();
+    let trailers = request_body.trailers().cloned().unwrap_or_default();
+    for (header, value) in request.headers.iter().chain(trailers.iter()) {
+        contents.extend_from_slice(format!("{}: {}\n", header, value).as_bytes());
+    }
+    contents.extend_from_slice(b"\n");
+    contents.extend_from_slice(&body);
+
+    let stream = reader.get_mut();
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        contents.len()
+    )
+    .unwrap();
+    stream.write_all(&contents).unwrap();
+}
+
+/// Address nothing is listening on.
+pub fn dead_address() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap()
+}
+
+/// Fresh, empty directory under the system's temp dir.
+pub fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("web-server-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
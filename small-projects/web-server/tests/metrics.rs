@@ -0,0 +1,116 @@
+mod common;
+use common::{get, send, spawn_server, temp_dir};
+use web_server::{
+    config::Timeouts,
+    router::{Handler, Route, Router, Sites},
+    server::Server,
+};
+
+#[test]
+fn exports_request_metrics() {
+    let server = spawn_server(
+        "root = \"public\"\n[[routes]]\nkind = \"file\"\npath = \"/home\"\nfile = \"index.html\"",
+    );
+    get(server, "/");
+    get(server, "/home");
+    get(server, "/home");
+    get(server, "/missing");
+    send(server, b"NOT HTTP\r\n\r\n");
+
+    let response = get(server, "/metrics");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+    assert!(response.contains("Content-Type: text/plain; version=0.0.4; charset=utf-8\r\n"));
+    for line in [
+        "http_requests_total{route=\"static\",status=\"200\"} 1",
+        "http_requests_total{route=\"/home\",status=\"200\"} 2",
+        "http_requests_total{route=\"static\",status=\"404\"} 1",
+        "http_requests_total{route=\"rejected\",status=\"400\"} 1",
+        "http_request_duration_seconds_count{route=\"/home\"} 2",
+        "http_request_duration_seconds_bucket{route=\"/home\",le=\"+Inf\"} 2",
+        // The request asking for the metrics is being served
+        "http_connections_active 1",
+        "thread_pool_queue_depth 0",
+        "thread_pool_workers 4",
+    ]
+    .iter()
+    {
+        assert!(
+            response.lines().any(|l| l == *line),
+            "{} not in\n{}",
+            line,
+            response
+        );
+    }
+
+    // The scrape itself shows up in the next one
+    let response = get(server, "/metrics");
+    assert!(response.contains("http_requests_total{route=\"/metrics\",status=\"200\"} 1\n"));
+}
+
+#[test]
+fn stops_counting_connections_whose_handler_panicked() {
+    let router = Router::new("public").route(Route::exact(
+        "/panic",
+        Handler::function(|_, _| panic!("handler failed")),
+    ));
+    let server = Server::new(Sites::new(router), 2, Timeouts::default())
+        .metrics_endpoint("/metrics", vec!["127.0.0.1".parse().unwrap()])
+        .bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr();
+
+    // The worker drops the connection without an answer
+    assert_eq!(get(server, "/panic"), "");
+    let response = get(server, "/metrics");
+    assert!(
+        response.lines().any(|l| l == "http_connections_active 1"),
+        "{}",
+        response
+    );
+}
+
+#[test]
+fn restricts_the_metrics_endpoint() {
+    let server = spawn_server("[metrics]\npath = \"/internal/stats\"\nallow = [\"10.0.0.0/8\"]");
+    let response = get(server, "/internal/stats");
+    assert!(
+        response.starts_with("HTTP/1.1 403 Forbidden\r\n"),
+        "{}",
+        response
+    );
+    let response = get(server, "/metrics");
+    assert!(
+        response.starts_with("HTTP/1.1 404 Not Found\r\n"),
+        "{}",
+        response
+    );
+
+    let server = spawn_server("[metrics]\nallow = [\"127.0.0.1\"]");
+    assert!(get(server, "/metrics").contains("thread_pool_workers 4"));
+
+    let server = spawn_server("[metrics]\nenabled = false");
+    assert!(get(server, "/metrics").starts_with("HTTP/1.1 404 Not Found\r\n"));
+}
+
+#[test]
+fn answers_health_checks() {
+    let root = temp_dir("health");
+    std::fs::write(root.join("404.html"), "gone").unwrap();
+    let server = spawn_server(&format!("root = {:?}", root));
+
+    let response = get(server, "/healthz");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.ends_with("\r\n\r\nok\n"));
+    let response = get(server, "/readyz");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+
+    // Without its document root the server can't serve the site
+    std::fs::remove_dir_all(&root).unwrap();
+    let response = get(server, "/readyz");
+    assert!(
+        response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"),
+        "{}",
+        response
+    );
+    assert!(get(server, "/healthz").starts_with("HTTP/1.1 200 OK\r\n"));
+}
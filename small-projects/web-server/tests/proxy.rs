@@ -0,0 +1,216 @@
+use std::{
+    io::{self, BufReader, Write},
+    net::{SocketAddr, TcpListener},
+    thread,
+};
+
+use web_server::http::{Request, RequestBody};
+
+mod common;
+use common::{body, dead_address, get, send, spawn_server, spawn_upstream};
+
+/// Upstream that reads a request, body included, and answers with
+/// `response` as it is.
+fn spawn_raw_upstream(response: &'static [u8]) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let upstream = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let request = Request::read_from(&mut reader).unwrap();
+        io::copy(
+            &mut RequestBody::new(&request, &mut reader),
+            &mut io::sink(),
+        )
+        .unwrap();
+        reader.get_mut().write_all(response).unwrap();
+    });
+    upstream
+}
+
+fn spawn_proxy(upstreams: &[String]) -> SocketAddr {
+    spawn_server(&format!(
+        "root = \"public\"\n[[routes]]\nkind = \"proxy\"\npath = \"/api\"\nupstreams = {:?}",
+        upstreams
+    ))
+}
+
+#[test]
+fn forwards_with_rewritten_host_and_forwarded_headers() {
+    let upstream = spawn_upstream("backend");
+    let server = spawn_proxy(&[upstream.to_string()]);
+
+    let raw =
+        "GET /api/users?page=2 HTTP/1.1\r\nHost: app.local\r\nX-Forwarded-For: 10.0.0.1\r\n\r\n";
+    let response = String::from_utf8(send(server, raw.as_bytes())).unwrap();
+
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.contains("\nGET /api/users?page=2\n"));
+    assert!(response.contains(&format!("\nHost: {}\n", upstream)));
+    assert!(response.contains("\nX-Forwarded-Host: app.local\n"));
+    assert!(response.contains("\nX-Forwarded-For: 10.0.0.1, 127.0.0.1\n"));
+    assert!(response.contains("\nX-Forwarded-Proto: http\n"));
+}
+
+#[test]
+fn only_matching_paths_are_proxied() {
+    let upstream = spawn_upstream("backend");
+    let server = spawn_proxy(&[upstream.to_string()]);
+
+    assert!(get(server, "/api").contains("\nbackend\n"));
+    assert!(get(server, "/apiary").starts_with("HTTP/1.1 404 Not Found\r\n"));
+}
+
+#[test]
+fn streams_request_and_response_bodies() {
+    let upstream = spawn_upstream("backend");
+    let server = spawn_proxy(&[upstream.to_string()]);
+
+    let payload: Vec<u8> = (0..2_000_000u32).map(|i| (i % 251) as u8).collect();
+    let mut raw = format!(
+        "POST /api/upload HTTP/1.1\r\nHost: app.local\r\nContent-Length: {}\r\n\r\n",
+        payload.len()
+    )
+    .into_bytes();
+    raw.extend_from_slice(&payload);
+
+    let response = send(server, &raw);
+    let echoed = body(&response);
+    assert!(echoed.ends_with(&payload));
+}
+
+#[test]
+fn forwards_chunked_request_bodies_with_trailers() {
+    let upstream = spawn_upstream("backend");
+    let server = spawn_proxy(&[upstream.to_string()]);
+
+    let raw = "POST /api/upload HTTP/1.1\r\nHost: app.local\r\nTransfer-Encoding: chunked\r\n\r\n\
+               5\r\nhello\r\n6\r\n world\r\n0\r\nChecksum: 42\r\n\r\n";
+    let response = String::from_utf8(send(server, raw.as_bytes())).unwrap();
+
+    assert!(response.contains("\nTransfer-Encoding: chunked\n"));
+    assert!(response.contains("\nChecksum: 42\n"));
+    assert!(response.ends_with("\n\nhello world"));
+}
+
+#[test]
+fn rechunks_chunked_upstream_responses() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let upstream = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let _ = Request::read_from(&mut BufReader::new(&stream));
+        stream
+            .write_all(
+                b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n",
+            )
+            .unwrap();
+    });
+    let server = spawn_proxy(&[upstream.to_string()]);
+
+    let response = get(server, "/api/stream");
+    assert!(response.contains("Transfer-Encoding: chunked\r\n"));
+    assert!(response.ends_with("\r\n\r\n5\r\nhello\r\n0\r\n\r\n"));
+}
+
+#[test]
+fn round_robins_over_upstreams() {
+    let first = spawn_upstream("first");
+    let second = spawn_upstream("second");
+    let server = spawn_proxy(&[first.to_string(), second.to_string()]);
+
+    let names: Vec<bool> = (0..4)
+        .map(|_| get(server, "/api").contains("\nfirst\n"))
+        .collect();
+    assert_eq!(names.iter().filter(|first| **first).count(), 2);
+    assert_ne!(names[0], names[1]);
+    assert_ne!(names[1], names[2]);
+}
+
+#[test]
+fn fails_over_unhealthy_upstreams() {
+    let healthy = spawn_upstream("healthy");
+    let server = spawn_proxy(&[dead_address().to_string(), healthy.to_string()]);
+
+    for _ in 0..4 {
+        let response = get(server, "/api");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("\nhealthy\n"));
+    }
+}
+
+#[test]
+fn bad_gateway_when_every_upstream_is_down() {
+    let server = spawn_proxy(&[dead_address().to_string(), dead_address().to_string()]);
+
+    assert!(get(server, "/api").starts_with("HTTP/1.1 502 Bad Gateway\r\n"));
+}
+
+#[test]
+fn short_client_bodies_leave_upstreams_up() {
+    let first = spawn_upstream("first");
+    let second = spawn_upstream("second");
+    let server = spawn_proxy(&[first.to_string(), second.to_string()]);
+
+    let raw =
+        "POST /api/upload HTTP/1.1\r\nHost: app.local\r\nContent-Length: 100\r\n\r\ntoo short";
+    let response = String::from_utf8(send(server, raw.as_bytes())).unwrap();
+    assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+
+    // Neither upstream was taken out of rotation
+    let firsts = (0..4)
+        .filter(|_| get(server, "/api").contains("\nfirst\n"))
+        .count();
+    assert_eq!(firsts, 2);
+}
+
+#[test]
+fn skips_interim_upstream_responses() {
+    let upstream = spawn_raw_upstream(
+        b"HTTP/1.1 100 Continue\r\n\r\n\
+          HTTP/1.1 103 Early Hints\r\nLink: </style.css>; rel=preload\r\n\r\n\
+          HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+    );
+    let server = spawn_proxy(&[upstream.to_string()]);
+
+    let raw = "POST /api/upload HTTP/1.1\r\nHost: app.local\r\nContent-Length: 4\r\n\r\nbody";
+    let response = String::from_utf8(send(server, raw.as_bytes())).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+    assert!(response.contains("Content-Length: 5\r\n"));
+    assert!(!response.contains("Link:"));
+    assert!(response.ends_with("\r\n\r\nhello"));
+}
+
+#[test]
+fn does_not_forward_expect() {
+    let upstream = spawn_upstream("backend");
+    let server = spawn_proxy(&[upstream.to_string()]);
+
+    let raw = "POST /api/upload HTTP/1.1\r\nHost: app.local\r\nExpect: 100-continue\r\n\
+               Content-Length: 4\r\n\r\nbody";
+    let response = String::from_utf8(send(server, raw.as_bytes())).unwrap();
+    assert!(response.contains("HTTP/1.1 200 OK\r\n"), "{}", response);
+    assert!(!response.contains("\nExpect:"));
+    assert!(response.ends_with("\n\nbody"));
+}
+
+#[test]
+fn drops_headers_named_by_connection() {
+    let upstream = spawn_upstream("backend");
+    let server = spawn_proxy(&[upstream.to_string()]);
+
+    let raw = "GET /api HTTP/1.1\r\nHost: app.local\r\nConnection: X-Hop, keep-alive\r\n\
+               X-Hop: secret\r\nX-Kept: yes\r\n\r\n";
+    let response = String::from_utf8(send(server, raw.as_bytes())).unwrap();
+    assert!(response.contains("\nX-Kept: yes\n"), "{}", response);
+    assert!(!response.contains("X-Hop"));
+
+    let upstream = spawn_raw_upstream(
+        b"HTTP/1.1 200 OK\r\nConnection: close, X-Internal\r\nX-Internal: secret\r\n\
+          X-Public: yes\r\nContent-Length: 2\r\n\r\nok",
+    );
+    let server = spawn_proxy(&[upstream.to_string()]);
+    let response = get(server, "/api");
+    assert!(response.contains("X-Public: yes\r\n"), "{}", response);
+    assert!(!response.contains("X-Internal"));
+}
@@ -0,0 +1,139 @@
+use std::{
+    fs,
+    net::TcpStream,
+    thread,
+    time::{Duration, Instant},
+};
+
+mod common;
+use common::{request, send, start_server};
+
+/// The shipped site, with four workers and a `/sleep` that takes a second.
+const CONFIG: &str = r#"
+    workers = 4
+    root = "public"
+
+    [[routes]]
+    kind = "sleep"
+    path = "/sleep"
+    file = "index.html"
+    seconds = 1
+"#;
+
+fn public(file: &str) -> Vec<u8> {
+    fs::read(format!("{}/public/{}", env!("CARGO_MANIFEST_DIR"), file)).unwrap()
+}
+
+#[test]
+fn serves_the_index() {
+    let server = start_server(CONFIG).local_addr();
+
+    let reply = request(server, "GET", "/", &[], b"").unwrap();
+    assert_eq!(reply.status, 200);
+    assert_eq!(
+        reply.headers.get("Content-Type"),
+        Some("text/html; charset=utf-8")
+    );
+    assert_eq!(reply.body, public("index.html"));
+
+    let reply = request(server, "HEAD", "/", &[], b"").unwrap();
+    assert_eq!(reply.status, 200);
+    let length = public("index.html").len().to_string();
+    assert_eq!(reply.headers.get("Content-Length"), Some(length.as_str()));
+    assert!(reply.body.is_empty());
+
+    let reply = request(server, "GET", "/index.html?cache=bust", &[], b"").unwrap();
+    assert_eq!(reply.body, public("index.html"));
+}
+
+#[test]
+fn answers_unknown_paths_with_the_404_page() {
+    let server = start_server(CONFIG).local_addr();
+    for path in [
+        "/missing",
+        "/missing/",
+        "/../Cargo.toml",
+        "/%2e%2e/Cargo.toml",
+    ]
+    .iter()
+    {
+        let reply = request(server, "GET", path, &[], b"").unwrap();
+        assert_eq!(reply.status, 404, "{}", path);
+        assert_eq!(reply.body, public("404.html"), "{}", path);
+    }
+
+    let reply = request(server, "DELETE", "/", &[], b"").unwrap();
+    assert_eq!(reply.status, 405);
+    assert_eq!(reply.headers.get("Allow"), Some("GET, HEAD"));
+}
+
+#[test]
+fn serves_requests_while_others_sleep() {
+    let server = start_server(CONFIG).local_addr();
+
+    // Three sleepers leave a worker for everyone else
+    let started = Instant::now();
+    let sleepers: Vec<_> = (0..3)
+        .map(|_| thread::spawn(move || request(server, "GET", "/sleep", &[], b"").unwrap()))
+        .collect();
+    thread::sleep(Duration::from_millis(100));
+    let reply = request(server, "GET", "/", &[], b"").unwrap();
+    assert_eq!(reply.status, 200);
+    assert!(started.elapsed() < Duration::from_millis(900));
+
+    for sleeper in sleepers {
+        let reply = sleeper.join().unwrap();
+        assert_eq!(reply.status, 200);
+        assert_eq!(reply.body, public("index.html"));
+    }
+    // ...and they slept side by side, not one after the other
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    assert!(started.elapsed() < Duration::from_millis(1900));
+}
+
+#[test]
+fn rejects_malformed_requests() {
+    let server = start_server(CONFIG).local_addr();
+    let long_header = format!("GET / HTTP/1.1\r\nX-Long: {}\r\n\r\n", "a".repeat(9000));
+    let cases: [(&[u8], &str); 7] = [
+        (b"HELLO\r\n\r\n", "400"),
+        (b"get / HTTP/1.1\r\n\r\n", "400"),
+        (b"GET / HTTP/2.0\r\n\r\n", "400"),
+        (b"GET index.html HTTP/1.1\r\n\r\n", "400"),
+        (long_header.as_bytes(), "431"),
+        (b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\r\n", "501"),
+        (
+            b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nContent-Length: 3\r\n\r\n",
+            "400",
+        ),
+    ];
+    for (raw, status) in cases.iter() {
+        let response = String::from_utf8(send(server, raw)).unwrap();
+        assert!(
+            response.starts_with(&format!("HTTP/1.1 {} ", status)),
+            "{:?} -> {}",
+            String::from_utf8_lossy(&raw[..raw.len().min(40)]),
+            response
+        );
+        assert!(response.contains("Connection: close\r\n"));
+    }
+
+    // None of which got in the way of well-formed requests
+    assert_eq!(request(server, "GET", "/", &[], b"").unwrap().status, 200);
+}
+
+#[test]
+fn shuts_down_after_finishing_requests() {
+    let handle = start_server(CONFIG);
+    let server = handle.local_addr();
+
+    let sleeper = thread::spawn(move || request(server, "GET", "/sleep", &[], b"").unwrap());
+    thread::sleep(Duration::from_millis(200));
+    let started = Instant::now();
+    handle.shutdown();
+    // Waited for the sleeper, rather than cutting it off
+    assert!(started.elapsed() >= Duration::from_millis(500));
+    assert_eq!(sleeper.join().unwrap().status, 200);
+
+    assert!(TcpStream::connect(server).is_err());
+}
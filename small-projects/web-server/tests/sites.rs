@@ -0,0 +1,47 @@
+use std::fs;
+
+mod common;
+use common::{get, send, spawn_server, temp_dir};
+
+#[test]
+fn picks_the_site_by_host_header() {
+    let docs = temp_dir("docs-site");
+    fs::write(docs.join("index.html"), "docs home").unwrap();
+    let server = spawn_server(&format!(
+        "root = \"public\"\n[[vhosts]]\nhost = \"docs.local\"\naliases = [\"www.docs.local\"]\nroot = {:?}",
+        docs
+    ));
+
+    for host in ["docs.local", "DOCS.local:7878", "www.docs.local"].iter() {
+        let raw = format!("GET / HTTP/1.1\r\nHost: {}\r\n\r\n", host);
+        let response = String::from_utf8(send(server, raw.as_bytes())).unwrap();
+        assert!(response.ends_with("\r\n\r\ndocs home"), "{}", host);
+    }
+
+    let raw = "GET / HTTP/1.1\r\nHost: elsewhere.local\r\n\r\n";
+    let response = String::from_utf8(send(server, raw.as_bytes())).unwrap();
+    assert!(response.contains("Hi from Rust"));
+}
+
+#[test]
+fn serves_files_from_the_document_root() {
+    let root = temp_dir("static-root");
+    fs::create_dir(root.join("css")).unwrap();
+    fs::create_dir(root.join("guide")).unwrap();
+    fs::write(root.join("css/site.css"), "body {}").unwrap();
+    fs::write(root.join("guide/index.html"), "guide").unwrap();
+    let server = spawn_server(&format!("root = {:?}\nnot_found = \"css/site.css\"", root));
+
+    let response = get(server, "/css/site.css");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.contains("Content-Type: text/css; charset=utf-8\r\n"));
+
+    let response = get(server, "/guide");
+    assert!(response.starts_with("HTTP/1.1 301 Moved Permanently\r\n"));
+    assert!(response.contains("Location: /guide/\r\n"));
+    assert!(get(server, "/guide/").ends_with("\r\n\r\nguide"));
+
+    assert!(get(server, "/missing").starts_with("HTTP/1.1 404 Not Found\r\n"));
+    assert!(get(server, "/../Cargo.toml").starts_with("HTTP/1.1 404 Not Found\r\n"));
+    assert!(get(server, "/%2e%2e/Cargo.toml").starts_with("HTTP/1.1 404 Not Found\r\n"));
+}
@@ -0,0 +1,109 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    thread,
+    time::Duration,
+};
+
+use web_server::{
+    http::{sse::Event, Body, Response},
+    router::{Handler, Route, Router, Sites},
+};
+
+mod common;
+use common::{body, send, spawn_sites};
+
+fn spawn(route: Route) -> std::net::SocketAddr {
+    spawn_sites(Sites::new(Router::new("public").route(route)))
+}
+
+#[test]
+fn streams_bodies_of_unknown_length_chunked() {
+    let server = spawn(Route::exact(
+        "/count",
+        Handler::function(|_, _| {
+            Response::new(200).with_body(Body::Stream(Box::new(|out: &mut dyn Write| {
+                for i in 1..=3 {
+                    out.write_all(format!("{} ", i).as_bytes())?;
+                }
+                Ok(())
+            })))
+        }),
+    ));
+
+    let response =
+        String::from_utf8(send(server, b"GET /count HTTP/1.1\r\nHost: a\r\n\r\n")).unwrap();
+    assert!(response.contains("Transfer-Encoding: chunked\r\n"));
+    assert!(!response.contains("Content-Length"));
+    assert!(response.ends_with("\r\n\r\n2\r\n1 \r\n2\r\n2 \r\n2\r\n3 \r\n0\r\n\r\n"));
+
+    // HTTP/1.0 doesn't know chunks, the body ends when the connection does
+    let response = String::from_utf8(send(server, b"GET /count HTTP/1.0\r\n\r\n")).unwrap();
+    assert!(!response.contains("Transfer-Encoding"));
+    assert!(response.ends_with("\r\n\r\n1 2 3 "));
+}
+
+#[test]
+fn decodes_chunked_request_bodies_and_trailers() {
+    let server = spawn(Route::exact(
+        "/echo",
+        Handler::function(|_, body| {
+            let mut contents = String::new();
+            if body.read_to_string(&mut contents).is_err() {
+                return Response::error(400);
+            }
+            let checksum = body
+                .trailers()
+                .and_then(|trailers| trailers.get("Checksum"));
+            Response::text(200, format!("{} ({})", contents, checksum.unwrap_or("-")))
+        }),
+    ));
+
+    let raw = "POST /echo HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n\
+               4\r\nWiki\r\n5;note=x\r\npedia\r\n0\r\nChecksum: 9\r\n\r\n";
+    let response = send(server, raw.as_bytes());
+    assert_eq!(body(&response), b"Wikipedia (9)");
+
+    let raw = "POST /echo HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n";
+    let response = String::from_utf8(send(server, raw.as_bytes())).unwrap();
+    assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+}
+
+#[test]
+fn sends_server_sent_events_as_they_happen() {
+    let server = spawn(Route::exact(
+        "/events",
+        Handler::function(|_, _| {
+            Response::event_stream(|events| {
+                events.send(&Event::new("first").id("1"))?;
+                thread::sleep(Duration::from_millis(300));
+                events.send(&Event::new("second").id("2").event("update"))
+            })
+        }),
+    ));
+
+    let mut stream = TcpStream::connect(server).unwrap();
+    stream
+        .write_all(b"GET /events HTTP/1.1\r\nHost: a\r\nAccept: text/event-stream\r\nConnection: close\r\n\r\n")
+        .unwrap();
+    let mut reader = BufReader::new(stream);
+
+    let mut head = String::new();
+    while !head.ends_with("\r\n\r\n") {
+        reader.read_line(&mut head).unwrap();
+    }
+    assert!(head.contains("Content-Type: text/event-stream\r\n"));
+    assert!(head.contains("Cache-Control: no-cache\r\n"));
+
+    // The first event arrives before the handler is done sleeping
+    let mut size = String::new();
+    reader.read_line(&mut size).unwrap();
+    let mut chunk = vec![0; usize::from_str_radix(size.trim(), 16).unwrap()];
+    reader.read_exact(&mut chunk).unwrap();
+    assert_eq!(chunk, b"id: 1\ndata: first\n\n");
+
+    let mut rest = String::new();
+    reader.read_to_string(&mut rest).unwrap();
+    assert!(rest.contains("id: 2\nevent: update\ndata: second\n\n"));
+    assert!(rest.ends_with("0\r\n\r\n"));
+}
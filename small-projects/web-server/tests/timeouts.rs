@@ -0,0 +1,296 @@
+use std::{
+    io::{BufRead, BufReader, ErrorKind, Read, Write},
+    net::{SocketAddr, TcpStream},
+    time::{Duration, Instant},
+};
+
+use web_server::{
+    config::Timeouts,
+    http::Response,
+    router::{Handler, Route, Router, Sites},
+    server::Server,
+};
+
+const TIMEOUTS: Timeouts = Timeouts {
+    header: Duration::from_millis(300),
+    body: Duration::from_millis(300),
+    write: Duration::from_secs(1),
+    idle: Duration::from_millis(300),
+    min_rate: 1000,
+};
+
+fn spawn() -> SocketAddr {
+    spawn_with(TIMEOUTS)
+}
+
+/// Server whose `/echo` answers with the size of the request body, whose
+/// `/ignore` doesn't read it, and whose `/large` answers with 64 MiB.
+fn spawn_with(timeouts: Timeouts) -> SocketAddr {
+    let router = Router::new("public")
+        .route(Route::exact(
+            "/echo",
+            Handler::function(|_, body| {
+                let mut contents = Vec::new();
+                match body.read_to_end(&mut contents) {
+                    Ok(size) => Response::text(200, format!("{} bytes", size)),
+                    Err(_) => Response::error(500),
+                }
+            }),
+        ))
+        .route(Route::exact(
+            "/ignore",
+            Handler::function(|_, _| Response::text(200, "ignored")),
+        ))
+        .route(Route::exact(
+            "/large",
+            Handler::function(|_, _| Response::text(200, vec![b'x'; 64 << 20])),
+        ));
+    Server::new(Sites::new(router), 2, timeouts)
+        .bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+}
+
+/// Reads one response with a `Content-Length`, and returns its head and
+/// body.
+fn read_response(reader: &mut BufReader<TcpStream>) -> (String, String) {
+    let mut head = String::new();
+    while !head.ends_with("\r\n\r\n") {
+        assert!(
+            reader.read_line(&mut head).unwrap() > 0,
+            "closed after {:?}",
+            head
+        );
+    }
+    let length = head
+        .lines()
+        .find_map(|line| line.strip_prefix("Content-Length: "))
+        .expect("response without a length")
+        .parse()
+        .unwrap();
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+    (head, String::from_utf8(body).unwrap())
+}
+
+/// Sends `bytes` one `piece` at a time, `every` so often, until the server
+/// answers, and returns the answer.
+fn trickle(stream: &mut TcpStream, bytes: &[u8], piece: usize, every: Duration) -> Vec<u8> {
+    stream.set_read_timeout(Some(every)).unwrap();
+    let mut response = Vec::new();
+    for piece in bytes.chunks(piece) {
+        stream.write_all(piece).unwrap();
+        match stream.read_to_end(&mut response) {
+            Ok(_) => return response,
+            Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {
+                if !response.is_empty() {
+                    break;
+                }
+            }
+            Err(e) => panic!("{}", e),
+        }
+    }
+    stream.set_read_timeout(None).unwrap();
+    stream.read_to_end(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn keeps_connections_alive() {
+    let server = spawn();
+    let stream = TcpStream::connect(server).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut writer = stream;
+
+    writer
+        .write_all(b"POST /echo HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\n\r\nhello")
+        .unwrap();
+    let (head, body) = read_response(&mut reader);
+    assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(!head.contains("Connection"), "{}", head);
+    assert_eq!(body, "5 bytes");
+
+    // A body the handler didn't read is skipped, and pipelined requests
+    // are answered in order
+    writer
+        .write_all(
+            b"POST /ignore HTTP/1.1\r\nHost: a\r\nContent-Length: 3\r\n\r\nabc\
+              GET /echo HTTP/1.1\r\nHost: a\r\n\r\n",
+        )
+        .unwrap();
+    assert_eq!(read_response(&mut reader).1, "ignored");
+    assert_eq!(read_response(&mut reader).1, "0 bytes");
+
+    // Idle connections are closed, without an answer
+    let started = Instant::now();
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert!(rest.is_empty(), "{:?}", String::from_utf8_lossy(&rest));
+    assert!(started.elapsed() >= Duration::from_millis(250));
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn closes_connections_when_asked_to() {
+    let server = spawn();
+    for request in [
+        &b"GET /echo HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n"[..],
+        b"GET /echo HTTP/1.0\r\n\r\n",
+    ]
+    .iter()
+    {
+        let started = Instant::now();
+        let mut stream = TcpStream::connect(server).unwrap();
+        stream.write_all(request).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.contains("Connection: close\r\n"), "{}", response);
+        assert!(started.elapsed() < TIMEOUTS.idle);
+    }
+
+    // HTTP/1.0 clients have to ask for it
+    let stream = TcpStream::connect(server).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut writer = stream;
+    for _ in 0..2 {
+        writer
+            .write_all(b"GET /echo HTTP/1.0\r\nConnection: keep-alive\r\n\r\n")
+            .unwrap();
+        let (head, _) = read_response(&mut reader);
+        assert!(head.contains("Connection: keep-alive\r\n"), "{}", head);
+    }
+}
+
+#[test]
+fn tells_clients_expecting_it_to_continue() {
+    let server = spawn();
+    let stream = TcpStream::connect(server).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut writer = stream;
+
+    // The body is only sent once the server asks for it...
+    writer
+        .write_all(
+            b"POST /echo HTTP/1.1\r\nHost: a\r\nExpect: 100-continue\r\nContent-Length: 5\r\n\r\n",
+        )
+        .unwrap();
+    let mut interim = String::new();
+    while !interim.ends_with("\r\n\r\n") {
+        reader.read_line(&mut interim).unwrap();
+    }
+    assert_eq!(interim, "HTTP/1.1 100 Continue\r\n\r\n");
+    writer.write_all(b"hello").unwrap();
+    let (head, body) = read_response(&mut reader);
+    assert!(head.starts_with("HTTP/1.1 200 OK\r\n"), "{}", head);
+    assert_eq!(body, "5 bytes");
+
+    // ...which it doesn't when the handler doesn't want it, and the
+    // connection is closed rather than wait for a body that may not come
+    writer
+        .write_all(b"POST /ignore HTTP/1.1\r\nHost: a\r\nExpect: 100-continue\r\nContent-Length: 5\r\n\r\n")
+        .unwrap();
+    let (head, body) = read_response(&mut reader);
+    assert!(head.starts_with("HTTP/1.1 200 OK\r\n"), "{}", head);
+    assert!(head.contains("Connection: close\r\n"), "{}", head);
+    assert_eq!(body, "ignored");
+}
+
+#[test]
+fn answers_slow_request_heads_with_408() {
+    let server = spawn();
+
+    // A client that never says anything...
+    let started = Instant::now();
+    let mut response = Vec::new();
+    let mut stream = TcpStream::connect(server).unwrap();
+    stream.read_to_end(&mut response).unwrap();
+    assert!(response.starts_with(b"HTTP/1.1 408 Request Timeout\r\n"));
+    assert!(started.elapsed() >= Duration::from_millis(250));
+
+    // ...and one that sends its head a byte at a time, each in time for
+    // the read before, but the whole head too late
+    let started = Instant::now();
+    let mut stream = TcpStream::connect(server).unwrap();
+    let head = b"GET /echo HTTP/1.1\r\nHost: a\r\nUser-Agent: slowloris\r\n\r\n";
+    let response = trickle(&mut stream, head, 1, Duration::from_millis(50));
+    assert!(response.starts_with(b"HTTP/1.1 408 Request Timeout\r\n"));
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn answers_slow_request_bodies_with_408() {
+    let server = spawn();
+    let head = b"POST /echo HTTP/1.1\r\nHost: a\r\nContent-Length: 2000\r\n\r\n";
+
+    // 500 bytes every 200ms is over the minimum rate, so the body may take
+    // longer than the body timeout
+    let mut stream = TcpStream::connect(server).unwrap();
+    stream.write_all(head).unwrap();
+    let response = trickle(&mut stream, &[b'x'; 2000], 500, Duration::from_millis(200));
+    let response = String::from_utf8(response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+    assert!(response.ends_with("2000 bytes"));
+
+    // 10 bytes every 100ms isn't, although no read waits for long
+    let started = Instant::now();
+    let mut stream = TcpStream::connect(server).unwrap();
+    stream.write_all(head).unwrap();
+    let response = trickle(&mut stream, &[b'x'; 2000], 10, Duration::from_millis(100));
+    let response = String::from_utf8(response).unwrap();
+    assert!(
+        response.starts_with("HTTP/1.1 408 Request Timeout\r\n"),
+        "{}",
+        response
+    );
+    assert!(response.contains("Connection: close\r\n"));
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[test]
+fn answers_request_heads_under_the_minimum_rate_with_408() {
+    // Plenty of time for the whole head, but not at 20 bytes a second
+    let server = spawn_with(Timeouts {
+        header: Duration::from_secs(10),
+        ..TIMEOUTS
+    });
+    let started = Instant::now();
+    let mut stream = TcpStream::connect(server).unwrap();
+    let head = format!(
+        "GET /echo HTTP/1.1\r\nHost: a\r\nX-Padding: {}\r\n\r\n",
+        "x".repeat(100)
+    );
+    let response = trickle(&mut stream, head.as_bytes(), 1, Duration::from_millis(50));
+    assert!(response.starts_with(b"HTTP/1.1 408 Request Timeout\r\n"));
+    assert!(started.elapsed() < Duration::from_secs(3));
+}
+
+#[test]
+fn drops_clients_taking_responses_under_the_minimum_rate() {
+    // 10 MB a second, after the write timeout
+    let server = spawn_with(Timeouts {
+        min_rate: 10_000_000,
+        ..TIMEOUTS
+    });
+    let started = Instant::now();
+    let mut stream = TcpStream::connect(server).unwrap();
+    stream
+        .write_all(b"GET /large HTTP/1.1\r\nHost: a\r\n\r\n")
+        .unwrap();
+
+    // Never stops reading, so no single write blocks for long, but takes
+    // far longer than the deadline to read it all
+    let mut received = 0;
+    let mut buffer = [0; 16 * 1024];
+    loop {
+        match stream.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => received += read,
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert!(received < 64 << 20, "{} bytes", received);
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
@@ -0,0 +1,234 @@
+use std::{
+    convert::TryFrom,
+    fs,
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::Path,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use rcgen::{BasicConstraints, CertificateParams, DnType, IsCa, KeyPair};
+use rustls::{
+    client::Resumption,
+    crypto::ring,
+    pki_types::{CertificateDer, ServerName},
+    ClientConfig, ClientConnection, RootCertStore, StreamOwned,
+};
+use web_server::{
+    config::{Config, ConfigError},
+    server::Server,
+};
+
+mod common;
+use common::{body, temp_dir};
+
+/// Test CA, signing a certificate per site.
+struct Authority {
+    cert: rcgen::Certificate,
+    key: KeyPair,
+}
+
+impl Authority {
+    fn new() -> Authority {
+        let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "web-server test CA");
+        let key = KeyPair::generate().unwrap();
+        let cert = params.self_signed(&key).unwrap();
+        Authority { cert, key }
+    }
+
+    /// Writes a certificate for `names` and its key to `dir` as
+    /// `<file>.pem` and `<file>-key.pem`, and returns the certificate.
+    fn issue(&self, names: &[&str], dir: &Path, file: &str) -> CertificateDer<'static> {
+        let names = names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        let key = KeyPair::generate().unwrap();
+        let cert = CertificateParams::new(names)
+            .unwrap()
+            .signed_by(&key, &self.cert, &self.key)
+            .unwrap();
+        fs::write(dir.join(format!("{}-key.pem", file)), key.serialize_pem()).unwrap();
+        fs::write(dir.join(format!("{}.pem", file)), cert.pem()).unwrap();
+        cert.der().clone()
+    }
+
+    /// Client trusting only this CA. It never resumes sessions, so every
+    /// connection shows the certificate the server presents right now.
+    fn client(&self) -> Arc<ClientConfig> {
+        let mut roots = RootCertStore::empty();
+        roots.add(self.cert.der().clone()).unwrap();
+        let mut config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        config.resumption = Resumption::disabled();
+        Arc::new(config)
+    }
+}
+
+/// Starts a server for `config` with its HTTPS listener on an ephemeral
+/// port, and returns that port's address.
+fn spawn_https(config: &str) -> SocketAddr {
+    let config = Config::parse(config, Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let certificates = Arc::new(config.certificates().unwrap().unwrap());
+    certificates.watch(config.tls.as_ref().unwrap().reload_interval);
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = Server::from_config(&config).https(vec![listener], certificates.server_config());
+    thread::spawn(move || server.run(Vec::new()));
+    addr
+}
+
+/// GETs `/` from `server_name`, and returns the certificate the server
+/// presented with the response.
+fn get_https(
+    client: &Arc<ClientConfig>,
+    addr: SocketAddr,
+    server_name: &str,
+) -> (CertificateDer<'static>, Vec<u8>) {
+    let name = ServerName::try_from(server_name.to_string()).unwrap();
+    let connection = ClientConnection::new(Arc::clone(client), name).unwrap();
+    let mut stream = StreamOwned::new(connection, TcpStream::connect(addr).unwrap());
+    let request = format!(
+        "GET / HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        server_name
+    );
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = Vec::new();
+    // Fails if the server closed the connection without a close_notify
+    stream.read_to_end(&mut response).unwrap();
+    let cert = stream.conn.peer_certificates().unwrap()[0].clone();
+    (cert, response)
+}
+
+fn site(dir: &Path, name: &str) -> std::path::PathBuf {
+    let root = dir.join(name);
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("index.html"), format!("{} home", name)).unwrap();
+    fs::write(root.join("404.html"), "not found").unwrap();
+    root
+}
+
+#[test]
+fn serves_https_with_a_certificate_per_site() {
+    let dir = temp_dir("tls-sni");
+    let ca = Authority::new();
+    let default_cert = ca.issue(&["localhost"], &dir, "default");
+    let docs_cert = ca.issue(&["docs.local", "www.docs.local"], &dir, "docs");
+    let server = spawn_https(&format!(
+        r#"
+        root = {:?}
+        [tls]
+        listen = ["127.0.0.1:0"]
+        cert = {:?}
+        key = {:?}
+
+        [[vhosts]]
+        host = "docs.local"
+        aliases = ["www.docs.local"]
+        root = {:?}
+        tls = {{ cert = {:?}, key = {:?} }}
+        "#,
+        site(&dir, "default"),
+        dir.join("default.pem"),
+        dir.join("default-key.pem"),
+        site(&dir, "docs"),
+        dir.join("docs.pem"),
+        dir.join("docs-key.pem"),
+    ));
+    let client = ca.client();
+
+    let (cert, response) = get_https(&client, server, "localhost");
+    assert_eq!(cert, default_cert);
+    assert!(response.starts_with(b"HTTP/1.1 200 OK\r\n"));
+    assert_eq!(body(&response), b"default home");
+
+    for name in ["docs.local", "WWW.docs.local"].iter() {
+        let (cert, response) = get_https(&client, server, name);
+        assert_eq!(cert, docs_cert, "{}", name);
+        assert_eq!(body(&response), b"docs home");
+    }
+
+    // Plain HTTP on the HTTPS port is a failed handshake, not a crash
+    let mut stream = TcpStream::connect(server).unwrap();
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+    let mut response = Vec::new();
+    let _ = stream.read_to_end(&mut response);
+    assert!(!response.starts_with(b"HTTP/1.1 200"));
+    let (_, response) = get_https(&client, server, "localhost");
+    assert!(response.starts_with(b"HTTP/1.1 200 OK\r\n"));
+}
+
+#[test]
+fn reloads_certificates_when_the_files_change() {
+    let dir = temp_dir("tls-reload");
+    let ca = Authority::new();
+    let first = ca.issue(&["localhost"], &dir, "site");
+    let server = spawn_https(&format!(
+        "root = {:?}\n[tls]\nlisten = [\"127.0.0.1:0\"]\ncert = {:?}\nkey = {:?}\nreload_interval = 1",
+        site(&dir, "site"),
+        dir.join("site.pem"),
+        dir.join("site-key.pem"),
+    ));
+    let client = ca.client();
+    assert_eq!(get_https(&client, server, "localhost").0, first);
+
+    // A half-replaced pair doesn't load, so the old one stays in use
+    let key = fs::read(dir.join("site-key.pem")).unwrap();
+    let second = ca.issue(&["localhost"], &dir, "site");
+    fs::write(dir.join("site-key.pem"), key).unwrap();
+    thread::sleep(Duration::from_millis(1500));
+    assert_eq!(get_https(&client, server, "localhost").0, first);
+
+    let third = ca.issue(&["localhost"], &dir, "site");
+    assert_ne!(second, third);
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let (cert, _) = get_https(&client, server, "localhost");
+        if cert == third {
+            break;
+        }
+        assert!(Instant::now() < deadline, "certificate was not reloaded");
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+#[test]
+fn reports_unusable_certificates() {
+    let dir = temp_dir("tls-invalid");
+    let ca = Authority::new();
+    ca.issue(&["localhost"], &dir, "a");
+    ca.issue(&["localhost"], &dir, "b");
+    let config = format!(
+        "[tls]\nlisten = [\"127.0.0.1:0\"]\ncert = {:?}\nkey = {:?}\n\
+         [[vhosts]]\nhost = \"docs.local\"\nroot = \"public\"\ntls = {{ cert = {:?}, key = {:?} }}",
+        dir.join("a.pem"),
+        dir.join("b-key.pem"),
+        dir.join("missing.pem"),
+        dir.join("b-key.pem"),
+    );
+    let problems = match Config::parse(&config, Path::new(env!("CARGO_MANIFEST_DIR"))) {
+        Err(ConfigError::Invalid(problems)) => problems,
+        other => panic!("expected validation errors, got {:?}", other),
+    };
+    assert_eq!(problems.len(), 2, "{:#?}", problems);
+    assert!(
+        problems[0].starts_with("tls: unusable key"),
+        "{}",
+        problems[0]
+    );
+    assert!(
+        problems[1].starts_with("vhosts[0].tls: cannot read"),
+        "{}",
+        problems[1]
+    );
+}
@@ -0,0 +1,277 @@
+use std::{
+    fs::{self, File},
+    net::SocketAddr,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+mod common;
+use common::{body, get, send, spawn_server, temp_dir};
+
+const BOUNDARY: &str = "----formboundary7MA4YWxk";
+
+/// A `multipart/form-data` body with a part per `(field, filename, contents)`.
+fn form(parts: &[(&str, Option<&str>, &[u8])]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (field, filename, contents) in parts {
+        body.extend_from_slice(format!("--{}\r\n", BOUNDARY).as_bytes());
+        match filename {
+            Some(filename) => body.extend_from_slice(
+                format!(
+                    "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\
+                     Content-Type: application/octet-stream\r\n\r\n",
+                    field, filename
+                )
+                .as_bytes(),
+            ),
+            None => body.extend_from_slice(
+                format!("Content-Disposition: form-data; name=\"{}\"\r\n\r\n", field).as_bytes(),
+            ),
+        }
+        body.extend_from_slice(contents);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", BOUNDARY).as_bytes());
+    body
+}
+
+fn post(server: SocketAddr, path: &str, content_type: &str, body: &[u8]) -> String {
+    let mut raw = format!(
+        "POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n",
+        path,
+        content_type,
+        body.len()
+    )
+    .into_bytes();
+    raw.extend_from_slice(body);
+    String::from_utf8(send(server, &raw)).unwrap()
+}
+
+fn upload(server: SocketAddr, parts: &[(&str, Option<&str>, &[u8])]) -> String {
+    let content_type = format!("multipart/form-data; boundary={}", BOUNDARY);
+    post(server, "/upload", &content_type, &form(parts))
+}
+
+fn spawn_uploads(root: &Path, limits: &str) -> SocketAddr {
+    fs::create_dir_all(root.join("files")).unwrap();
+    fs::write(root.join("404.html"), "not found").unwrap();
+    spawn_server(&format!(
+        "root = {:?}\nlisting = true\n\
+         [[routes]]\nkind = \"upload\"\npath = \"/upload\"\ndir = \"files\"\n{}",
+        root, limits
+    ))
+}
+
+fn listing(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn saves_uploaded_files() {
+    let root = temp_dir("uploads-saved");
+    let server = spawn_uploads(&root, "");
+
+    let response = upload(
+        server,
+        &[
+            ("note", None, b"not a file"),
+            ("file", Some("report.txt"), b"first\r\n--almost a boundary"),
+            ("file", Some("../../escape me.txt"), b"second"),
+        ],
+    );
+    assert!(
+        response.starts_with("HTTP/1.1 201 Created\r\n"),
+        "{}",
+        response
+    );
+    assert!(response.contains("Content-Type: application/json\r\n"));
+    assert!(response.ends_with(
+        r#"{"files":[{"name":"report.txt","size":26},{"name":"escape_me.txt","size":6}]}"#
+    ));
+    let files = root.join("files");
+    assert_eq!(
+        fs::read(files.join("report.txt")).unwrap(),
+        b"first\r\n--almost a boundary"
+    );
+    assert_eq!(fs::read(files.join("escape_me.txt")).unwrap(), b"second");
+
+    // Taken names get a suffix instead of being overwritten
+    let response = upload(server, &[("file", Some("report.txt"), b"again")]);
+    assert!(
+        response.contains(r#""name":"report-1.txt""#),
+        "{}",
+        response
+    );
+    assert_eq!(
+        listing(&files),
+        ["escape_me.txt", "report-1.txt", "report.txt"]
+    );
+
+    // Uploaded files are served like any other
+    let response = get(server, "/files/report-1.txt");
+    assert!(
+        response.ends_with(
+            // debug
+            "\r\n\r\nagain"
+        ),
+        "{}",
+        response
+    );
+}
+
+#[test]
+fn saves_files_with_long_names() {
+    let root = temp_dir("uploads-long-names");
+    let server = spawn_uploads(&root, "");
+
+    // Cut short, with room left for a suffix
+    let name = "a".repeat(300);
+    let kept = "a".repeat(247);
+    let suffixed = format!("{}-1", kept);
+    for saved in &[&kept, &suffixed] {
+        let response = upload(server, &[("file", Some(&name), b"long")]);
+        assert!(
+            response.starts_with("HTTP/1.1 201 Created\r\n"),
+            "{}",
+            response
+        );
+        assert!(response.contains(&format!(r#""name":"{}""#, saved)));
+    }
+    assert_eq!(listing(&root.join("files")), [kept, suffixed]);
+}
+
+#[test]
+fn rejects_uploads_over_the_limits() {
+    let root = temp_dir("uploads-limits");
+    let server = spawn_uploads(&root, "max_file_size = 10\nmax_request_size = 200");
+    let files = root.join("files");
+
+    let response = upload(server, &[("file", Some("big.bin"), &[b'x'; 11])]);
+    assert!(
+        response.starts_with("HTTP/1.1 413 Payload Too Large\r\n"),
+        "{}",
+        response
+    );
+
+    // Each file fits, together they don't: rejected by `Content-Length`...
+    let two_files = form(&[
+        ("a", Some("a.bin"), &[b'a'; 10]),
+        ("b", Some("b.bin"), &[b'b'; 10]),
+    ]);
+    let content_type = format!("multipart/form-data; boundary={}", BOUNDARY);
+    let response = post(server, "/upload", &content_type, &two_files);
+    assert!(
+        response.starts_with("HTTP/1.1 413 Payload Too Large\r\n"),
+        "{}",
+        response
+    );
+
+    // ...or, when chunked, once the body grows too large, without keeping
+    // the file that was already saved
+    let mut raw = format!(
+        "POST /upload HTTP/1.1\r\nContent-Type: {}\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n",
+        content_type,
+        two_files.len()
+    )
+    .into_bytes();
+    raw.extend_from_slice(&two_files);
+    raw.extend_from_slice(b"\r\n0\r\n\r\n");
+    let response = String::from_utf8(send(server, &raw)).unwrap();
+    assert!(
+        response.starts_with("HTTP/1.1 413 Payload Too Large\r\n"),
+        "{}",
+        response
+    );
+    assert!(listing(&files).is_empty(), "{:?}", listing(&files));
+
+    let response = upload(server, &[("a", Some("a.bin"), &[b'a'; 10])]);
+    assert!(
+        response.starts_with("HTTP/1.1 201 Created\r\n"),
+        "{}",
+        response
+    );
+}
+
+#[test]
+fn rejects_requests_that_are_not_uploads() {
+    let root = temp_dir("uploads-invalid");
+    let server = spawn_uploads(&root, "");
+
+    let response = post(server, "/upload", "application/json", b"{}");
+    assert!(response.starts_with("HTTP/1.1 415 Unsupported Media Type\r\n"));
+
+    let response = upload(server, &[("note", None, b"only a field")]);
+    assert!(
+        response.starts_with("HTTP/1.1 400 Bad Request\r\n"),
+        "{}",
+        response
+    );
+
+    let content_type = format!("multipart/form-data; boundary={}", BOUNDARY);
+    let response = post(server, "/upload", &content_type, b"no parts at all");
+    assert!(
+        response.starts_with("HTTP/1.1 400 Bad Request\r\n"),
+        "{}",
+        response
+    );
+
+    assert!(listing(&root.join("files")).is_empty());
+}
+
+#[test]
+fn lists_directories_without_an_index() {
+    let root = temp_dir("listing");
+    let server = spawn_uploads(&root, "");
+    let files = root.join("files");
+    fs::create_dir(files.join("sub dir")).unwrap();
+    fs::write(files.join(".hidden"), "").unwrap();
+    let now = SystemTime::now();
+    for (name, size, age) in [("b.txt", 1, 30), ("a.txt", 300, 10), ("c&d.txt", 20, 20)].iter() {
+        let path = files.join(name);
+        fs::write(&path, vec![b'.'; *size]).unwrap();
+        let file = File::options().write(true).open(&path).unwrap();
+        file.set_modified(now - Duration::from_secs(*age)).unwrap();
+    }
+
+    let response = get(server, "/files/");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.contains("<title>Index of /files/</title>"));
+    assert!(response.contains(r#"<a href="c%26d.txt">c&amp;d.txt</a>"#));
+    assert!(response.contains(r#"<a href="sub%20dir/">sub dir/</a>"#));
+    assert!(!response.contains(".hidden"));
+
+    let names = |query: &str| -> Vec<String> {
+        let raw = format!(
+            "GET /files/{} HTTP/1.1\r\nAccept: application/json\r\n\r\n",
+            query
+        );
+        let response = send(server, raw.as_bytes());
+        let listing: serde_json::Value = serde_json::from_slice(body(&response)).unwrap();
+        assert_eq!(listing["path"], "/files/");
+        listing["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(names(""), ["a.txt", "b.txt", "c&d.txt", "sub dir"]);
+    assert_eq!(
+        names("?sort=size"),
+        ["sub dir", "b.txt", "c&d.txt", "a.txt"]
+    );
+    assert_eq!(
+        names("?sort=mtime&order=desc"),
+        ["sub dir", "a.txt", "c&d.txt", "b.txt"]
+    );
+
+    // Sites without `listing = true` keep answering 404
+    let server = spawn_server(&format!("root = {:?}\nnot_found = \"files/a.txt\"", root));
+    let response = get(server, "/files/");
+    assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+}